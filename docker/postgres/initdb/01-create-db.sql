@@ -1,3 +1,2 @@
 CREATE DATABASE keycloakdb;
-
-
+CREATE DATABASE kubeatlas;
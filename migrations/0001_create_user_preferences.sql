@@ -0,0 +1,7 @@
+-- Per-user UI preferences keyed by the Keycloak subject (`sub` claim)
+CREATE TABLE IF NOT EXISTS user_preferences (
+    user_sub    TEXT PRIMARY KEY,
+    preferences JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now()
+);
@@ -0,0 +1,12 @@
+-- Registered Kubernetes clusters
+CREATE TABLE IF NOT EXISTS clusters (
+    id          UUID PRIMARY KEY,
+    name        TEXT NOT NULL,
+    description TEXT,
+    endpoint    TEXT NOT NULL,
+    status      TEXT NOT NULL DEFAULT 'pending'
+                CHECK (status IN ('active', 'inactive', 'error', 'pending')),
+    created_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    CONSTRAINT clusters_name_key UNIQUE (name)
+);
@@ -0,0 +1,16 @@
+-- Cluster credentials, envelope-encrypted: `ciphertext` is the credential JSON
+-- encrypted with a per-row data key, `wrapped_key` is that data key encrypted
+-- with the master key identified by `key_id`. Both are `nonce || ciphertext`.
+CREATE TABLE IF NOT EXISTS cluster_credentials (
+    cluster_id               UUID PRIMARY KEY REFERENCES clusters (id) ON DELETE CASCADE,
+    auth_method              TEXT NOT NULL,
+    has_ca_bundle            BOOLEAN NOT NULL DEFAULT false,
+    insecure_skip_tls_verify BOOLEAN NOT NULL DEFAULT false,
+    key_id                   TEXT NOT NULL,
+    wrapped_key              BYTEA NOT NULL,
+    ciphertext               BYTEA NOT NULL,
+    created_at               TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at               TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS cluster_credentials_key_id_idx ON cluster_credentials (key_id);
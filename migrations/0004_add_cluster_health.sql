@@ -0,0 +1,9 @@
+-- Results of the background health prober
+ALTER TABLE clusters
+    ADD COLUMN IF NOT EXISTS kubernetes_version    TEXT,
+    ADD COLUMN IF NOT EXISTS probe_latency_ms      INTEGER,
+    ADD COLUMN IF NOT EXISTS last_error            TEXT,
+    ADD COLUMN IF NOT EXISTS last_probe_at         TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS last_success_at       TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS consecutive_failures  INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS consecutive_successes INTEGER NOT NULL DEFAULT 0;
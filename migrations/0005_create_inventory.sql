@@ -0,0 +1,26 @@
+-- Normalized inventory collected from each cluster. Cluster-scoped objects
+-- (nodes, namespaces) use an empty namespace so the primary key stays simple.
+CREATE TABLE IF NOT EXISTS inventory_objects (
+    cluster_id   UUID NOT NULL REFERENCES clusters (id) ON DELETE CASCADE,
+    kind         TEXT NOT NULL,
+    namespace    TEXT NOT NULL DEFAULT '',
+    name         TEXT NOT NULL,
+    uid          TEXT NOT NULL,
+    labels       JSONB NOT NULL DEFAULT '{}'::jsonb,
+    details      JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at   TIMESTAMPTZ,
+    collected_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (cluster_id, kind, namespace, name)
+);
+
+CREATE INDEX IF NOT EXISTS inventory_objects_labels_idx ON inventory_objects USING GIN (labels);
+CREATE INDEX IF NOT EXISTS inventory_objects_namespace_idx ON inventory_objects (cluster_id, namespace);
+
+-- Latest collection run per cluster
+CREATE TABLE IF NOT EXISTS inventory_collections (
+    cluster_id  UUID PRIMARY KEY REFERENCES clusters (id) ON DELETE CASCADE,
+    started_at  TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    counts      JSONB NOT NULL DEFAULT '{}'::jsonb,
+    errors      JSONB NOT NULL DEFAULT '{}'::jsonb
+);
@@ -0,0 +1,23 @@
+-- Mutating calls made through the Kubernetes API proxy, allowed or denied.
+-- No foreign key to clusters: entries outlive the cluster they refer to.
+CREATE TABLE IF NOT EXISTS audit_log (
+    id           UUID PRIMARY KEY,
+    occurred_at  TIMESTAMPTZ NOT NULL DEFAULT now(),
+    user_id      TEXT NOT NULL,
+    username     TEXT NOT NULL,
+    cluster_id   UUID NOT NULL,
+    cluster_name TEXT NOT NULL,
+    verb         TEXT NOT NULL,
+    api_group    TEXT NOT NULL DEFAULT '',
+    resource     TEXT NOT NULL,
+    namespace    TEXT,
+    name         TEXT,
+    path         TEXT NOT NULL,
+    allowed      BOOLEAN NOT NULL,
+    status_code  INTEGER,
+    error        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_occurred_at_idx ON audit_log (occurred_at DESC);
+CREATE INDEX IF NOT EXISTS audit_log_cluster_idx ON audit_log (cluster_id, occurred_at DESC);
+CREATE INDEX IF NOT EXISTS audit_log_user_idx ON audit_log (username, occurred_at DESC);
@@ -0,0 +1,9 @@
+-- Per-cluster Kubernetes impersonation: requests made for a user carry
+-- Impersonate-User/Impersonate-Group so the cluster's own RBAC applies.
+ALTER TABLE clusters
+    ADD COLUMN IF NOT EXISTS impersonation_enabled        BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN IF NOT EXISTS impersonation_user_claim     TEXT NOT NULL DEFAULT 'preferred_username',
+    ADD COLUMN IF NOT EXISTS impersonation_user_prefix    TEXT NOT NULL DEFAULT '',
+    ADD COLUMN IF NOT EXISTS impersonation_group_prefix   TEXT NOT NULL DEFAULT '',
+    ADD COLUMN IF NOT EXISTS impersonation_include_groups BOOLEAN NOT NULL DEFAULT true,
+    ADD COLUMN IF NOT EXISTS impersonation_include_roles  BOOLEAN NOT NULL DEFAULT true;
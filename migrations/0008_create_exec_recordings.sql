@@ -0,0 +1,26 @@
+-- Asciicast recordings of exec sessions. The cast itself lives in the
+-- recording store (local directory or S3) under `storage_key`.
+CREATE TABLE IF NOT EXISTS exec_recordings (
+    id           UUID PRIMARY KEY,
+    audit_id     UUID REFERENCES audit_log (id) ON DELETE SET NULL,
+    user_id      TEXT NOT NULL,
+    username     TEXT NOT NULL,
+    cluster_id   UUID NOT NULL,
+    cluster_name TEXT NOT NULL,
+    namespace    TEXT NOT NULL,
+    pod          TEXT NOT NULL,
+    container    TEXT,
+    command      TEXT NOT NULL,
+    storage_key  TEXT NOT NULL,
+    -- recording, complete, interrupted
+    status       TEXT NOT NULL DEFAULT 'recording',
+    started_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    ended_at     TIMESTAMPTZ,
+    size_bytes   BIGINT,
+    exit_code    INTEGER,
+    end_reason   TEXT
+);
+
+CREATE INDEX IF NOT EXISTS exec_recordings_started_at_idx ON exec_recordings (started_at DESC);
+CREATE INDEX IF NOT EXISTS exec_recordings_cluster_idx ON exec_recordings (cluster_id, started_at DESC);
+CREATE INDEX IF NOT EXISTS exec_recordings_audit_idx ON exec_recordings (audit_id);
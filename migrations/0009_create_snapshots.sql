@@ -0,0 +1,37 @@
+-- The collected object without status and bookkeeping fields; snapshots are
+-- taken from it. NULL until the next collection after this migration.
+ALTER TABLE inventory_objects ADD COLUMN IF NOT EXISTS manifest JSONB;
+
+-- Point-in-time copies of a cluster's inventory.
+CREATE TABLE IF NOT EXISTS cluster_snapshots (
+    id                UUID PRIMARY KEY,
+    cluster_id        UUID NOT NULL REFERENCES clusters (id) ON DELETE CASCADE,
+    taken_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- scheduled or manual
+    trigger           TEXT NOT NULL,
+    created_by        TEXT,
+    -- When the inventory the snapshot was taken from finished collecting
+    collected_at      TIMESTAMPTZ,
+    object_count      INTEGER NOT NULL DEFAULT 0,
+    counts            JSONB NOT NULL DEFAULT '{}'::jsonb
+);
+
+CREATE INDEX IF NOT EXISTS cluster_snapshots_cluster_idx ON cluster_snapshots (cluster_id, taken_at DESC);
+
+-- Manifests by SHA-256 of their JSON text, shared by every snapshot in which
+-- the object did not change.
+CREATE TABLE IF NOT EXISTS snapshot_manifests (
+    hash     TEXT PRIMARY KEY,
+    manifest JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS snapshot_objects (
+    snapshot_id UUID NOT NULL REFERENCES cluster_snapshots (id) ON DELETE CASCADE,
+    kind        TEXT NOT NULL,
+    namespace   TEXT NOT NULL DEFAULT '',
+    name        TEXT NOT NULL,
+    hash        TEXT NOT NULL REFERENCES snapshot_manifests (hash),
+    PRIMARY KEY (snapshot_id, kind, namespace, name)
+);
+
+CREATE INDEX IF NOT EXISTS snapshot_objects_hash_idx ON snapshot_objects (hash);
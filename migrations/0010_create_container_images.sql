@@ -0,0 +1,24 @@
+-- Where each container image has been seen running, kept across inventory
+-- collections for first/last seen times. Empty strings stand for absent
+-- tags and digests so they can be part of the key.
+CREATE TABLE IF NOT EXISTS container_images (
+    cluster_id    UUID NOT NULL REFERENCES clusters (id) ON DELETE CASCADE,
+    namespace     TEXT NOT NULL,
+    workload_kind TEXT NOT NULL,
+    workload_name TEXT NOT NULL,
+    container     TEXT NOT NULL,
+    -- The reference as written in the pod spec
+    image         TEXT NOT NULL,
+    registry      TEXT NOT NULL,
+    repository    TEXT NOT NULL,
+    tag           TEXT NOT NULL DEFAULT '',
+    -- From the reference, or resolved by the container runtime
+    digest        TEXT NOT NULL DEFAULT '',
+    first_seen    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    last_seen     TIMESTAMPTZ NOT NULL DEFAULT now(),
+    -- Seen in the latest pod collection of the cluster
+    running       BOOLEAN NOT NULL DEFAULT TRUE,
+    PRIMARY KEY (cluster_id, namespace, workload_kind, workload_name, container, image, digest)
+);
+
+CREATE INDEX IF NOT EXISTS container_images_repository_idx ON container_images (registry, repository, tag, digest);
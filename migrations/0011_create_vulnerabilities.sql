@@ -0,0 +1,65 @@
+-- Imported advisory feeds. Importing a feed under an existing name replaces it.
+CREATE TABLE IF NOT EXISTS vulnerability_feeds (
+    id             UUID PRIMARY KEY,
+    name           TEXT NOT NULL UNIQUE,
+    -- osv or trivy
+    format         TEXT NOT NULL,
+    advisory_count INTEGER NOT NULL DEFAULT 0,
+    imported_at    TIMESTAMPTZ NOT NULL DEFAULT now(),
+    imported_by    TEXT
+);
+
+-- One advisory for one package. `ecosystem` is normalized to lower case
+-- without its release (`debian`, `npm`); `release` is e.g. `12` for Debian 12.
+CREATE TABLE IF NOT EXISTS advisories (
+    feed_id     UUID NOT NULL REFERENCES vulnerability_feeds (id) ON DELETE CASCADE,
+    advisory_id TEXT NOT NULL,
+    ecosystem   TEXT NOT NULL,
+    release     TEXT NOT NULL DEFAULT '',
+    package     TEXT NOT NULL,
+    -- critical, high, medium, low or unknown
+    severity    TEXT NOT NULL,
+    summary     TEXT,
+    aliases     JSONB NOT NULL DEFAULT '[]'::jsonb,
+    -- Affected versions and ranges, see vulnerability_matching.rs
+    affected    JSONB NOT NULL,
+    PRIMARY KEY (feed_id, advisory_id, ecosystem, release, package)
+);
+
+CREATE INDEX IF NOT EXISTS advisories_package_idx ON advisories (ecosystem, package);
+
+-- Package lists of images, by image digest.
+CREATE TABLE IF NOT EXISTS image_sboms (
+    digest        TEXT PRIMARY KEY,
+    -- cyclonedx or spdx
+    format        TEXT NOT NULL,
+    package_count INTEGER NOT NULL DEFAULT 0,
+    uploaded_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+    uploaded_by   TEXT
+);
+
+CREATE TABLE IF NOT EXISTS sbom_packages (
+    digest    TEXT NOT NULL REFERENCES image_sboms (digest) ON DELETE CASCADE,
+    ecosystem TEXT NOT NULL,
+    release   TEXT NOT NULL DEFAULT '',
+    name      TEXT NOT NULL,
+    version   TEXT NOT NULL,
+    purl      TEXT NOT NULL,
+    PRIMARY KEY (digest, ecosystem, name, version)
+);
+
+-- Advisories matched against SBOM packages; recomputed when an SBOM is
+-- uploaded or a feed is imported.
+CREATE TABLE IF NOT EXISTS image_vulnerabilities (
+    digest        TEXT NOT NULL REFERENCES image_sboms (digest) ON DELETE CASCADE,
+    feed_id       UUID NOT NULL REFERENCES vulnerability_feeds (id) ON DELETE CASCADE,
+    advisory_id   TEXT NOT NULL,
+    ecosystem     TEXT NOT NULL,
+    -- The advisory's release, to find it again
+    release       TEXT NOT NULL DEFAULT '',
+    package       TEXT NOT NULL,
+    version       TEXT NOT NULL,
+    severity      TEXT NOT NULL,
+    fixed_version TEXT,
+    PRIMARY KEY (digest, feed_id, advisory_id, ecosystem, release, package, version)
+);
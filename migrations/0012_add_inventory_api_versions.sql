@@ -0,0 +1,5 @@
+-- API versions clients wrote each object with, from kubectl's
+-- last-applied-configuration annotation and metadata.managedFields:
+-- [{"api_version": "apps/v1", "source": "helm"}]. Empty until the next
+-- collection after this migration.
+ALTER TABLE inventory_objects ADD COLUMN IF NOT EXISTS api_versions JSONB NOT NULL DEFAULT '[]'::jsonb;
@@ -0,0 +1,8 @@
+-- Which server instance writes the recording. Spool files are local to that
+-- instance, so only it finishes recordings left running by a restart; rows
+-- from before this migration are finished once no session can still be
+-- running. `status` can now also be `failed`: the cast could not be stored.
+ALTER TABLE exec_recordings ADD COLUMN IF NOT EXISTS instance_id TEXT;
+
+CREATE INDEX IF NOT EXISTS exec_recordings_in_progress_idx
+    ON exec_recordings (instance_id) WHERE status = 'recording';
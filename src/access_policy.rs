@@ -0,0 +1,484 @@
+use anyhow::{anyhow, Context, Result};
+use axum::http::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::config::ProxyConfig;
+
+/// Verbs that change cluster state; proxied calls with these are audited.
+pub const MUTATING_VERBS: [&str; 5] = ["create", "update", "patch", "delete", "deletecollection"];
+
+/// Decodes one percent-encoded path segment; malformed escapes and
+/// non-UTF-8 results are errors rather than passed through.
+fn decode_segment(segment: &str) -> Result<String> {
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let byte = segment
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| anyhow!("invalid percent-encoding in '{}'", segment))?;
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| anyhow!("path segment '{}' is not UTF-8", segment))
+}
+
+/// Decodes a query component the way Go's `url.QueryUnescape` does: `+` is
+/// a space, and a malformed escape makes the whole component invalid.
+fn decode_query_component(component: &str) -> Option<String> {
+    decode_segment(&component.replace('+', " ")).ok()
+}
+
+/// Whether the API server treats the query as a watch: like Go's
+/// `url.Query()`, pairs are split on `&` and decoded, pairs containing `;`
+/// or malformed escapes are dropped, and only the first `watch` value
+/// counts. Anything but `0` or `false` (any case) is a watch, so `watch`,
+/// `watch=` and `watch=yes` are watches too.
+pub fn is_watch(query: &str) -> bool {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.contains(';'))
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Some((decode_query_component(key)?, decode_query_component(value)?))
+        })
+        .find(|(key, _)| key == "watch")
+        .is_some_and(|(_, value)| !matches!(value.to_ascii_lowercase().as_str(), "0" | "false"))
+}
+
+/// Path segments of a proxied request as the API server reads them, and the
+/// path rebuilt from them: the request is authorized on the segments and the
+/// canonical path is what gets forwarded, so both always agree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanonicalPath {
+    pub segments: Vec<String>,
+    pub path: String,
+}
+
+impl CanonicalPath {
+    /// Rejects empty segments (`//` or a trailing `/`), `.`, `..` and
+    /// segments that decode to something containing `/`. Only `/` itself
+    /// has no segments.
+    pub fn parse(path: &str) -> Result<Self> {
+        let Some(rest) = path.strip_prefix('/') else {
+            return Err(anyhow!("path must start with '/'"));
+        };
+        if rest.is_empty() {
+            return Ok(Self { segments: Vec::new(), path: "/".to_string() });
+        }
+        let mut segments = Vec::new();
+        let mut canonical = String::with_capacity(path.len());
+        for raw in rest.split('/') {
+            let segment = decode_segment(raw)?;
+            if segment.is_empty() {
+                return Err(anyhow!("empty path segment in '{}'", path));
+            }
+            if matches!(segment.as_str(), "." | "..") || segment.contains('/') {
+                return Err(anyhow!("invalid path segment '{}'", raw));
+            }
+            canonical.push('/');
+            for byte in segment.bytes() {
+                match byte {
+                    b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' | b'@' => {
+                        canonical.push(byte as char)
+                    }
+                    _ => canonical.push_str(&format!("%{:02X}", byte)),
+                }
+            }
+            segments.push(segment);
+        }
+        Ok(Self { segments, path: canonical })
+    }
+}
+
+/// What a proxied Kubernetes API request does, derived like the API server's
+/// own request info: `/api/v1/namespaces/shop/pods/web-0/log` is a `get` of
+/// `pods/log` named `web-0` in namespace `shop`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct KubeRequestInfo {
+    pub verb: String,
+    /// `false` for discovery and other non-resource paths (`/version`, `/apis`)
+    pub is_resource: bool,
+    pub api_group: String,
+    pub resource: String,
+    pub subresource: Option<String>,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+}
+
+impl KubeRequestInfo {
+    /// Classifies a canonical path and the request method. `watch` is the
+    /// `watch` query parameter.
+    pub fn parse(method: &Method, path: &CanonicalPath, watch: bool) -> Result<Self> {
+        let parts: Vec<&str> = path.segments.iter().map(String::as_str).collect();
+
+        let non_resource = || -> Result<Self> {
+            Ok(Self {
+                verb: method.as_str().to_ascii_lowercase(),
+                ..Default::default()
+            })
+        };
+        let (api_group, rest) = match parts.as_slice() {
+            ["api", _version, rest @ ..] => ("", rest),
+            ["apis", group, _version, rest @ ..] => (*group, rest),
+            _ => return non_resource(),
+        };
+        let (mut watch, mut rest) = (watch, rest);
+        if let ["watch", tail @ ..] = rest {
+            watch = true;
+            rest = tail;
+        }
+        if rest.is_empty() {
+            return non_resource();
+        }
+
+        let mut info = Self {
+            is_resource: true,
+            api_group: api_group.to_string(),
+            ..Default::default()
+        };
+        match rest {
+            // A namespace object is treated as living in itself, as RBAC does
+            ["namespaces", namespace] => {
+                info.resource = "namespaces".to_string();
+                info.name = Some(namespace.to_string());
+                info.namespace = Some(namespace.to_string());
+            }
+            ["namespaces", namespace, resource, tail @ ..] => {
+                info.namespace = Some(namespace.to_string());
+                info.resource = resource.to_string();
+                info.name = tail.first().map(|n| n.to_string());
+                info.subresource = tail.get(1).map(|s| s.to_string());
+            }
+            [resource, tail @ ..] => {
+                info.resource = resource.to_string();
+                info.name = tail.first().map(|n| n.to_string());
+                info.subresource = tail.get(1).map(|s| s.to_string());
+            }
+            [] => unreachable!(),
+        }
+
+        let has_name = info.name.is_some();
+        info.verb = match *method {
+            Method::GET | Method::HEAD if watch => "watch",
+            Method::GET | Method::HEAD if has_name => "get",
+            Method::GET | Method::HEAD => "list",
+            Method::POST => "create",
+            Method::PUT => "update",
+            Method::PATCH => "patch",
+            Method::DELETE if has_name => "delete",
+            Method::DELETE => "deletecollection",
+            _ => return Err(anyhow!("method {} is not supported", method)),
+        }
+        .to_string();
+        Ok(info)
+    }
+
+    /// A resource request built directly, for endpoints that are not a plain proxy.
+    pub fn resource_request(
+        verb: &str,
+        api_group: &str,
+        resource: &str,
+        subresource: Option<&str>,
+        namespace: Option<&str>,
+    ) -> Self {
+        Self {
+            verb: verb.to_string(),
+            is_resource: true,
+            api_group: api_group.to_string(),
+            resource: resource.to_string(),
+            subresource: subresource.map(str::to_string),
+            namespace: namespace.map(str::to_string),
+            name: None,
+        }
+    }
+
+    /// Error message in the wording of Kubernetes RBAC denials.
+    pub fn denied_message(&self, username: &str) -> String {
+        let mut message = format!(
+            "user \"{}\" cannot {} resource \"{}\"",
+            username,
+            self.verb,
+            self.resource_path()
+        );
+        if !self.api_group.is_empty() {
+            message.push_str(&format!(" in API group \"{}\"", self.api_group));
+        }
+        match &self.namespace {
+            Some(namespace) => message.push_str(&format!(" in the namespace \"{}\"", namespace)),
+            None => message.push_str(" at the cluster scope"),
+        }
+        message
+    }
+
+    pub fn is_mutating(&self) -> bool {
+        self.is_resource && MUTATING_VERBS.contains(&self.verb.as_str())
+    }
+
+    /// `pods` or `pods/log`, the form used in policy rules.
+    pub fn resource_path(&self) -> String {
+        match &self.subresource {
+            Some(sub) => format!("{}/{}", self.resource, sub),
+            None => self.resource.clone(),
+        }
+    }
+}
+
+/// One allow rule; a request matches when every list matches. `*` matches anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolicyRule {
+    pub verbs: Vec<String>,
+    /// `pods`, `pods/log`, `pods/*` (any subresource) or `*`
+    pub resources: Vec<String>,
+    /// Resources excluded even if `resources` matches, e.g. `secrets`
+    #[serde(default)]
+    pub exclude_resources: Vec<String>,
+    /// Namespaces; cluster-scoped requests need `*`
+    #[serde(default = "any")]
+    pub namespaces: Vec<String>,
+    /// Cluster names the rule applies to
+    #[serde(default = "any")]
+    pub clusters: Vec<String>,
+}
+
+fn any() -> Vec<String> {
+    vec!["*".to_string()]
+}
+
+fn matches_name(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|p| p == "*" || p == value)
+}
+
+fn matches_resource(patterns: &[String], info: &KubeRequestInfo) -> bool {
+    let path = info.resource_path();
+    patterns.iter().any(|p| {
+        p == "*"
+            || *p == path
+            || (info.subresource.is_some() && *p == format!("{}/*", info.resource))
+    })
+}
+
+impl PolicyRule {
+    fn allows(&self, cluster: &str, info: &KubeRequestInfo) -> bool {
+        let namespace_ok = match &info.namespace {
+            Some(namespace) => matches_name(&self.namespaces, namespace),
+            None => self.namespaces.iter().any(|n| n == "*"),
+        };
+        matches_name(&self.clusters, cluster)
+            && matches_name(&self.verbs, &info.verb)
+            && matches_resource(&self.resources, info)
+            && !matches_resource(&self.exclude_resources, info)
+            && namespace_ok
+    }
+}
+
+/// Maps KubeAtlas roles to what their holders may do through the API proxy.
+/// A request is allowed if any rule of any of the user's roles allows it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessPolicy {
+    pub roles: BTreeMap<String, Vec<PolicyRule>>,
+}
+
+impl Default for AccessPolicy {
+    /// `admin` may do anything, `user` may read everything except secrets and
+    /// pod logs, `log-viewer` may read pod logs, `pod-exec` may exec into pods.
+    fn default() -> Self {
+        let mut roles = BTreeMap::new();
+        roles.insert(
+            "admin".to_string(),
+            vec![PolicyRule {
+                verbs: any(),
+                resources: any(),
+                exclude_resources: Vec::new(),
+                namespaces: any(),
+                clusters: any(),
+            }],
+        );
+        roles.insert(
+            "user".to_string(),
+            vec![PolicyRule {
+                verbs: ["get", "list", "watch"].map(str::to_string).to_vec(),
+                resources: any(),
+                exclude_resources: vec!["secrets".to_string(), "pods/log".to_string()],
+                namespaces: any(),
+                clusters: any(),
+            }],
+        );
+        roles.insert(
+            "log-viewer".to_string(),
+            vec![PolicyRule {
+                verbs: ["get", "list"].map(str::to_string).to_vec(),
+                resources: ["pods", "pods/log"].map(str::to_string).to_vec(),
+                exclude_resources: Vec::new(),
+                namespaces: any(),
+                clusters: any(),
+            }],
+        );
+        roles.insert(
+            "pod-exec".to_string(),
+            vec![PolicyRule {
+                verbs: vec!["create".to_string()],
+                resources: vec!["pods/exec".to_string()],
+                exclude_resources: Vec::new(),
+                namespaces: any(),
+                clusters: any(),
+            }],
+        );
+        Self { roles }
+    }
+}
+
+impl AccessPolicy {
+    /// Loads `ACCESS_POLICY_FILE` (YAML) or falls back to the built-in policy.
+    pub fn from_config(config: &ProxyConfig) -> Result<Self> {
+        let Some(path) = &config.policy_file else {
+            return Ok(Self::default());
+        };
+        let data = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read access policy '{}'", path))?;
+        serde_yaml::from_str(&data).with_context(|| format!("invalid access policy '{}'", path))
+    }
+
+    fn rules<'a>(&'a self, roles: &'a [String]) -> impl Iterator<Item = &'a PolicyRule> {
+        roles
+            .iter()
+            .filter_map(|role| self.roles.get(role))
+            .flatten()
+    }
+
+    /// Resource requests need a matching rule; read-only non-resource requests
+    /// (discovery, `/version`) are open to anyone with at least one rule.
+    pub fn allows(&self, roles: &[String], cluster: &str, info: &KubeRequestInfo) -> bool {
+        if !info.is_resource {
+            return matches!(info.verb.as_str(), "get" | "head")
+                && self.rules(roles).any(|r| matches_name(&r.clusters, cluster));
+        }
+        self.rules(roles).any(|rule| rule.allows(cluster, info))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(method: Method, path: &str) -> KubeRequestInfo {
+        KubeRequestInfo::parse(&method, &CanonicalPath::parse(path).unwrap(), false).unwrap()
+    }
+
+    #[test]
+    fn canonical_path_decodes_and_reencodes_segments() {
+        let canonical = CanonicalPath::parse("/api/v1/namespaces/sh%6Fp/configmaps/system%3Aa%20b").unwrap();
+        assert_eq!(canonical.segments, ["api", "v1", "namespaces", "shop", "configmaps", "system:a b"]);
+        assert_eq!(canonical.path, "/api/v1/namespaces/shop/configmaps/system:a%20b");
+        assert_eq!(CanonicalPath::parse("/").unwrap().segments, Vec::<String>::new());
+    }
+
+    #[test]
+    fn canonical_path_rejects_ambiguous_paths() {
+        for path in [
+            "api/v1/pods",
+            "/api/v1/pods/",
+            "/api//v1/pods",
+            "/api/v1/namespaces/shop/secrets/.",
+            "/api/v1/namespaces/%2e%2e/secrets",
+            "/api/v1/namespaces/shop%2Fsecrets",
+            "/api/v1/pods/%zz",
+            "/api/v1/pods/%ff",
+        ] {
+            assert!(CanonicalPath::parse(path).is_err(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn classifies_resource_requests() {
+        let log = info(Method::GET, "/api/v1/namespaces/shop/pods/web-0/log");
+        assert_eq!(log.verb, "get");
+        assert_eq!(log.resource_path(), "pods/log");
+        assert_eq!(log.namespace.as_deref(), Some("shop"));
+        assert_eq!(log.name.as_deref(), Some("web-0"));
+
+        let list = info(Method::GET, "/apis/apps/v1/deployments");
+        assert_eq!((list.verb.as_str(), list.api_group.as_str(), list.namespace), ("list", "apps", None));
+
+        let namespace = info(Method::DELETE, "/api/v1/namespaces/shop");
+        assert_eq!(namespace.verb, "delete");
+        assert_eq!(namespace.namespace.as_deref(), Some("shop"));
+
+        let collection = info(Method::DELETE, "/api/v1/namespaces/shop/pods");
+        assert_eq!(collection.verb, "deletecollection");
+        assert!(collection.is_mutating());
+
+        let watch = info(Method::GET, "/api/v1/watch/namespaces/shop/pods");
+        assert_eq!(watch.verb, "watch");
+        assert_eq!(watch.resource, "pods");
+
+        assert!(!info(Method::GET, "/version").is_resource);
+        assert!(!info(Method::GET, "/apis/apps/v1").is_resource);
+        assert!(!info(Method::GET, "/").is_resource);
+    }
+
+    #[test]
+    fn encoded_paths_are_authorized_as_decoded() {
+        let policy = AccessPolicy::default();
+        let user = ["user".to_string()];
+        let secrets = info(Method::GET, "/api/v1/namespaces/shop/%73ecrets");
+        assert_eq!(secrets.resource, "secrets");
+        assert!(!policy.allows(&user, "prod", &secrets));
+        assert!(policy.allows(&user, "prod", &info(Method::GET, "/api/v1/namespaces/shop/pods")));
+    }
+
+    #[test]
+    fn watch_is_detected_like_the_api_server() {
+        for watch in [
+            "watch=true",
+            "watch=1",
+            "watch=True",
+            "watch=yes",
+            "watch",
+            "watch=",
+            "labelSelector=app%3Dweb&watch",
+            "w%61tch=1",
+            "watch=tru%65",
+            "watch=+false",
+            "watch=1&watch=false",
+            "watch=true;x&watch=yes",
+        ] {
+            assert!(is_watch(watch), "{}", watch);
+        }
+        for not_watch in [
+            "",
+            "watch=false",
+            "watch=FALSE",
+            "watch=0",
+            "watch=f%61lse",
+            "watch=0&watch=true",
+            "watching=true",
+            "watch=%zz",
+            "watch=true;x",
+            "labelSelector=watch",
+        ] {
+            assert!(!is_watch(not_watch), "{}", not_watch);
+        }
+    }
+
+    #[test]
+    fn log_viewer_cannot_watch_through_query_variants() {
+        let policy = AccessPolicy::default();
+        let roles = ["log-viewer".to_string()];
+        let pods = CanonicalPath::parse("/api/v1/namespaces/shop/pods").unwrap();
+        for query in ["watch=yes", "watch", "watch=", "watch=True", "w%61tch=1"] {
+            let info = KubeRequestInfo::parse(&Method::GET, &pods, is_watch(query)).unwrap();
+            assert_eq!(info.verb, "watch", "{}", query);
+            assert!(!policy.allows(&roles, "prod", &info), "{}", query);
+        }
+        let list = KubeRequestInfo::parse(&Method::GET, &pods, is_watch("watch=false")).unwrap();
+        assert!(policy.allows(&roles, "prod", &list));
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::inventory_collector::{array_at, str_at};
+use crate::models::{FeedFormat, Severity};
+use crate::repositories::NewAdvisory;
+use crate::vulnerability_matching::{cvss3_base_score, normalize_ecosystem, normalize_package, Affected, Bound, VersionRange};
+
+/// Trivy's numeric severities, from `UNKNOWN` to `CRITICAL`.
+const TRIVY_SEVERITIES: [Severity; 5] = [
+    Severity::Unknown,
+    Severity::Low,
+    Severity::Medium,
+    Severity::High,
+    Severity::Critical,
+];
+
+impl FeedFormat {
+    pub fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "osv" => Ok(Self::Osv),
+            "trivy" => Ok(Self::Trivy),
+            other => Err(anyhow!("Unsupported feed format '{}'", other)),
+        }
+    }
+}
+
+/// Reads a JSON document, or JSON lines with one document each.
+fn documents(data: &[u8]) -> Result<Vec<Value>> {
+    if let Ok(value) = serde_json::from_slice::<Value>(data) {
+        return Ok(vec![value]);
+    }
+    std::str::from_utf8(data)
+        .context("Feed is not UTF-8")?
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .enumerate()
+        .map(|(i, line)| serde_json::from_str(line).with_context(|| format!("Invalid JSON on line {}", i + 1)))
+        .collect()
+}
+
+/// Advisories of a feed, one per advisory, ecosystem, release and package.
+pub fn parse(data: &[u8], format: FeedFormat) -> Result<Vec<NewAdvisory>> {
+    let mut advisories: BTreeMap<(String, String, Option<String>, String), NewAdvisory> = BTreeMap::new();
+    let parsed = match format {
+        FeedFormat::Osv => parse_osv(documents(data)?),
+        FeedFormat::Trivy => parse_trivy(serde_json::from_slice(data).context("Invalid JSON")?)?,
+    };
+    for advisory in parsed {
+        let key = (
+            advisory.advisory_id.clone(),
+            advisory.ecosystem.clone(),
+            advisory.release.clone(),
+            advisory.package.clone(),
+        );
+        match advisories.get_mut(&key) {
+            Some(existing) => {
+                existing.affected.merge(advisory.affected);
+                existing.severity = existing.severity.min(advisory.severity);
+            }
+            None => {
+                advisories.insert(key, advisory);
+            }
+        }
+    }
+    Ok(advisories.into_values().collect())
+}
+
+fn severity_value(value: Option<&Value>) -> Option<Severity> {
+    match value? {
+        Value::String(s) => Severity::parse(s),
+        Value::Number(n) => n.as_u64().and_then(|n| TRIVY_SEVERITIES.get(n as usize).copied()),
+        _ => None,
+    }
+}
+
+/// The worst severity among the advisory's CVSS vectors.
+fn osv_score_severity(advisory: &Value) -> Option<Severity> {
+    array_at(advisory, "/severity")
+        .iter()
+        .filter_map(|s| {
+            let score = s.get("score")?;
+            score
+                .as_f64()
+                .or_else(|| score.as_str().and_then(|s| s.parse().ok().or_else(|| cvss3_base_score(s))))
+        })
+        .map(Severity::from_score)
+        .min()
+}
+
+/// OSV `ECOSYSTEM` and `SEMVER` ranges as intervals; `GIT` ranges cannot be
+/// matched against package versions and are skipped.
+fn osv_affected(entry: &Value) -> Affected {
+    let mut affected = Affected {
+        versions: array_at(entry, "/versions").iter().filter_map(|v| v.as_str().map(str::to_string)).collect(),
+        ..Default::default()
+    };
+    for range in array_at(entry, "/ranges") {
+        if str_at(range, "/type").as_deref() == Some("GIT") {
+            continue;
+        }
+        let mut open: Option<VersionRange> = None;
+        for event in array_at(range, "/events") {
+            if let Some(introduced) = str_at(event, "/introduced") {
+                let lower = (introduced != "0").then_some(Bound { version: introduced, inclusive: true });
+                open = Some(VersionRange { lower, upper: None });
+            } else if let Some(fixed) = str_at(event, "/fixed") {
+                let mut range = open.take().unwrap_or_default();
+                range.upper = Some(Bound { version: fixed.clone(), inclusive: false });
+                affected.ranges.push(range);
+                affected.fixed.push(fixed);
+            } else if let Some(last) = str_at(event, "/last_affected") {
+                let mut range = open.take().unwrap_or_default();
+                range.upper = Some(Bound { version: last, inclusive: true });
+                affected.ranges.push(range);
+            }
+        }
+        affected.ranges.extend(open);
+    }
+    affected
+}
+
+fn parse_osv(documents: Vec<Value>) -> Vec<NewAdvisory> {
+    let advisories = documents.into_iter().flat_map(|document| match document {
+        Value::Array(items) => items,
+        Value::Object(ref map) if map.get("vulns").is_some_and(Value::is_array) => {
+            array_at(&document, "/vulns").to_vec()
+        }
+        other => vec![other],
+    });
+    let mut parsed = Vec::new();
+    for advisory in advisories {
+        let Some(id) = str_at(&advisory, "/id") else {
+            continue;
+        };
+        let summary = str_at(&advisory, "/summary").or_else(|| {
+            str_at(&advisory, "/details").map(|d| d.lines().next().unwrap_or_default().to_string())
+        });
+        let aliases: Vec<String> = array_at(&advisory, "/aliases")
+            .iter()
+            .filter_map(|a| a.as_str().map(str::to_string))
+            .collect();
+        let advisory_severity = severity_value(advisory.pointer("/database_specific/severity"))
+            .or_else(|| osv_score_severity(&advisory));
+        for entry in array_at(&advisory, "/affected") {
+            let (Some(ecosystem), Some(package)) =
+                (str_at(entry, "/package/ecosystem"), str_at(entry, "/package/name"))
+            else {
+                continue;
+            };
+            let affected = osv_affected(entry);
+            if affected.is_empty() {
+                continue;
+            }
+            let (ecosystem, release) = normalize_ecosystem(&ecosystem);
+            let package = normalize_package(&ecosystem, &package);
+            let severity = severity_value(entry.pointer("/ecosystem_specific/severity"))
+                .or_else(|| severity_value(entry.pointer("/database_specific/severity")))
+                .or(advisory_severity)
+                .unwrap_or(Severity::Unknown);
+            parsed.push(NewAdvisory {
+                advisory_id: id.clone(),
+                ecosystem,
+                release,
+                package,
+                severity,
+                summary: summary.clone(),
+                aliases: aliases.clone(),
+                affected,
+            });
+        }
+    }
+    parsed
+}
+
+fn constraints(value: Option<&Value>) -> Vec<VersionRange> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .flat_map(|c| c.split("||"))
+        .map(str::trim)
+        // An empty alternative, as in `<1.0 || `, must not become "any version"
+        .filter(|c| !c.is_empty())
+        .filter_map(VersionRange::parse_constraint)
+        .collect()
+}
+
+/// Trivy DB buckets exported as JSON: `advisories` maps a bucket (`debian 12`,
+/// `npm::GitHub Security Advisory npm`) to packages to advisory ids, and
+/// `vulnerabilities` holds each id's severity and title.
+fn parse_trivy(document: Value) -> Result<Vec<NewAdvisory>> {
+    let buckets = document
+        .get("advisories")
+        .and_then(Value::as_object)
+        .ok_or_else(|| anyhow!("Trivy feed has no 'advisories' object"))?;
+    let details = document.get("vulnerabilities").cloned().unwrap_or(Value::Null);
+    let mut parsed = Vec::new();
+    for (bucket, packages) in buckets {
+        let (ecosystem, release) = normalize_ecosystem(bucket);
+        for (package, advisories) in packages.as_object().into_iter().flatten() {
+            for (id, advisory) in advisories.as_object().into_iter().flatten() {
+                let mut affected = Affected {
+                    ranges: constraints(advisory.get("VulnerableVersions")),
+                    unaffected: constraints(advisory.get("PatchedVersions")),
+                    ..Default::default()
+                };
+                affected.fixed = affected
+                    .unaffected
+                    .iter()
+                    .filter_map(|r| r.lower.as_ref().map(|b| b.version.clone()))
+                    .collect();
+                // `VulnerableVersions` already bounds the range; an open-ended
+                // `< fixed` on top would flag every older release line
+                let bounded = !affected.ranges.is_empty();
+                match str_at(advisory, "/FixedVersion") {
+                    Some(fixed) if !fixed.trim().is_empty() => {
+                        for fixed in fixed.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                            if !bounded {
+                                affected.ranges.push(VersionRange {
+                                    lower: None,
+                                    upper: Some(Bound { version: fixed.to_string(), inclusive: false }),
+                                });
+                            }
+                            affected.fixed.push(fixed.to_string());
+                        }
+                    }
+                    // No fix yet: every version is affected
+                    _ if affected.is_empty() => affected.ranges.push(VersionRange::default()),
+                    _ => {}
+                }
+                let detail = details.get(id).unwrap_or(&Value::Null);
+                let severity = severity_value(advisory.get("Severity"))
+                    .or_else(|| severity_value(detail.get("Severity")))
+                    .unwrap_or(Severity::Unknown);
+                parsed.push(NewAdvisory {
+                    advisory_id: id.clone(),
+                    ecosystem: ecosystem.clone(),
+                    release: release.clone(),
+                    package: normalize_package(&ecosystem, package),
+                    severity,
+                    summary: str_at(detail, "/Title"),
+                    aliases: Vec::new(),
+                    affected,
+                });
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+/// Trivy exports have an `advisories` object; anything else is read as OSV.
+pub fn detect(data: &[u8]) -> FeedFormat {
+    match serde_json::from_slice::<Value>(data) {
+        Ok(document) if document.get("advisories").is_some_and(Value::is_object) => FeedFormat::Trivy,
+        _ => FeedFormat::Osv,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn trivy(advisory: Value) -> Affected {
+        let document = json!({
+            "advisories": {"npm::GitHub Security Advisory npm": {"lodash": {"GHSA-1": advisory}}},
+            "vulnerabilities": {"GHSA-1": {"Severity": "HIGH", "Title": "Prototype pollution"}}
+        });
+        let mut parsed = parse(document.to_string().as_bytes(), FeedFormat::Trivy).unwrap();
+        assert_eq!(parsed.len(), 1);
+        let advisory = parsed.remove(0);
+        assert_eq!(advisory.ecosystem, "npm");
+        assert_eq!(advisory.severity, Severity::High);
+        advisory.affected
+    }
+
+    #[test]
+    fn trivy_fixed_version_stays_within_vulnerable_versions() {
+        let affected = trivy(json!({"VulnerableVersions": [">=2.0, <2.5"], "FixedVersion": "2.5"}));
+        assert!(affected.matches("2.1"));
+        assert!(!affected.matches("1.0"));
+        assert!(!affected.matches("2.5"));
+        assert_eq!(affected.fixed, ["2.5"]);
+    }
+
+    #[test]
+    fn trivy_fixed_version_alone_bounds_from_below() {
+        let affected = trivy(json!({"FixedVersion": "2.5"}));
+        assert!(affected.matches("1.0"));
+        assert!(affected.matches("2.4.9"));
+        assert!(!affected.matches("2.5"));
+    }
+
+    #[test]
+    fn trivy_empty_constraint_parts_are_ignored() {
+        let affected = trivy(json!({"VulnerableVersions": ["<1.0 || ", " "], "FixedVersion": "1.0"}));
+        assert!(affected.matches("0.9"));
+        assert!(!affected.matches("1.0"));
+        assert!(!affected.matches("2.0"));
+
+        let affected = trivy(json!({"PatchedVersions": [">=1.2 ||"], "FixedVersion": "1.2"}));
+        assert!(affected.matches("1.1"));
+        assert!(!affected.matches("1.2"));
+        assert_eq!(affected.unaffected.len(), 1);
+    }
+
+    #[test]
+    fn trivy_without_fix_affects_every_version() {
+        let affected = trivy(json!({"FixedVersion": ""}));
+        assert!(affected.matches("0.1"));
+        assert!(affected.matches("99.0"));
+        assert!(affected.fixed.is_empty());
+    }
+
+    #[test]
+    fn detect_tells_trivy_from_osv() {
+        assert_eq!(detect(br#"{"advisories": {}}"#), FeedFormat::Trivy);
+        assert_eq!(detect(br#"{"id": "GHSA-1", "affected": []}"#), FeedFormat::Osv);
+        assert_eq!(detect(b"{\"id\": \"a\"}\n{\"id\": \"b\"}"), FeedFormat::Osv);
+    }
+}
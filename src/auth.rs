@@ -1,17 +1,117 @@
 use anyhow::{anyhow, Result};
-use axum::{
-    extract::Request,
-    http::{HeaderMap, StatusCode},
-    response::Json,
-};
-use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm, TokenData, Header};
+use axum::http::{HeaderMap, Uri};
+use jsonwebtoken::{decode, DecodingKey, Validation, Algorithm, TokenData};
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
 use crate::config::Config;
-use crate::models::{CreateUserRequest, UpdateUserRequest};
+use crate::models::{
+    CreateRoleRequest, CreateUserRequest, Role, RoleMember, UpdateProfileRequest,
+    UpdateRoleRequest, UpdateUserRequest,
+};
+
+/// Realm roles shipped with KubeAtlas and Keycloak; they must never be deleted.
+pub const PROTECTED_REALM_ROLES: [&str; 3] = ["admin", "user", "guest"];
+
+/// Typed Keycloak Admin API failures that callers map to specific HTTP statuses.
+/// Carried inside `anyhow::Error`; use `downcast_ref` to inspect.
+#[derive(Debug, thiserror::Error)]
+pub enum KeycloakError {
+    #[error("{0} not found")]
+    NotFound(String),
+    #[error("{0} already exists")]
+    Conflict(String),
+    #[error("{0}")]
+    Forbidden(String),
+    #[error("{0}")]
+    BadRequest(String),
+}
+
+/// Steps of `create_keycloak_user`, reported back when one of them fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CreateUserStep {
+    Authenticate,
+    ValidateRoles,
+    ValidateGroups,
+    CreateUser,
+    SetPassword,
+    AssignRoles,
+    JoinGroups,
+}
+
+impl std::fmt::Display for CreateUserStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::Authenticate => "authenticate",
+            Self::ValidateRoles => "validate_roles",
+            Self::ValidateGroups => "validate_groups",
+            Self::CreateUser => "create_user",
+            Self::SetPassword => "set_password",
+            Self::AssignRoles => "assign_roles",
+            Self::JoinGroups => "join_groups",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("step '{step}' failed: {cause}")]
+pub struct CreateUserError {
+    pub step: CreateUserStep,
+    pub cause: anyhow::Error,
+    /// Whether the partially created user was deleted again
+    pub rolled_back: bool,
+}
+
+impl CreateUserError {
+    fn new(step: CreateUserStep, cause: anyhow::Error) -> Self {
+        Self {
+            step,
+            cause,
+            rolled_back: false,
+        }
+    }
+}
+
+/// Percent-encodes everything but unreserved characters, so a name can be
+/// used as one path segment of an Admin API URL.
+fn encode_path_segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// A group path for `group-by-path`, each segment encoded so only the
+/// separators stay unescaped; a bare name is a top-level group.
+fn group_path(group: &str) -> String {
+    group
+        .trim_start_matches('/')
+        .split('/')
+        .map(encode_path_segment)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Extracts the human readable part of a Keycloak error body
+/// (`errorMessage` or `error_description`), falling back to the raw text.
+fn keycloak_error_message(body: &str) -> String {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|v| {
+            ["errorMessage", "error_description", "error"]
+                .iter()
+                .find_map(|k| v.get(*k).and_then(|m| m.as_str()).map(str::to_string))
+        })
+        .unwrap_or_else(|| body.to_string())
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeycloakUser {
@@ -22,6 +122,9 @@ pub struct KeycloakUser {
     pub family_name: Option<String>,
     pub realm_access: Option<RealmAccess>,
     pub resource_access: Option<HashMap<String, ResourceAccess>>,
+    /// Keycloak group membership, present when the realm maps a `groups` claim
+    #[serde(default)]
+    pub groups: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -48,6 +151,8 @@ struct KeycloakAccessTokenClaims {
     pub nbf: Option<usize>,
     pub realm_access: Option<RealmAccess>,
     pub resource_access: Option<HashMap<String, ResourceAccess>>,
+    #[serde(default)]
+    pub groups: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -180,7 +285,7 @@ impl AuthService {
                 return Ok(());
             }
             // Ensure admin role is present
-            self.assign_realm_roles(admin_token, &user_id, &vec!["admin".to_string()])
+            self.assign_realm_roles(admin_token, &user_id, &["admin".to_string()])
                 .await?;
             return Ok(());
         }
@@ -193,6 +298,7 @@ impl AuthService {
             last_name: Some("User".to_string()),
             password: self.config.adm_password.clone().unwrap_or_else(|| "admin".to_string()),
             roles: vec!["admin".to_string()],
+            groups: vec![],
         };
         let _ = self.create_keycloak_user(req).await?;
         Ok(())
@@ -226,7 +332,7 @@ impl AuthService {
             .send().await?;
         if !list.status().is_success() { return Err(anyhow!("Failed to query users: HTTP {}", list.status())); }
         let users: Vec<serde_json::Value> = list.json().await?;
-        let user_id = users.get(0).and_then(|u| u.get("id")).and_then(|v| v.as_str()).ok_or_else(|| anyhow!("admin-service user not found"))?;
+        let user_id = users.first().and_then(|u| u.get("id")).and_then(|v| v.as_str()).ok_or_else(|| anyhow!("admin-service user not found"))?;
 
         // Забираем описание роли admin
         let role_url = format!("{}/admin/realms/{}/roles/{}", self.config.keycloak_url, self.config.keycloak_realm, "admin");
@@ -296,7 +402,7 @@ impl AuthService {
 
         let decoding_key = DecodingKey::from_rsa_components(n, e)?;
 
-        let mut validation = Validation::new(Algorithm::RS256);
+        let validation = Validation::new(Algorithm::RS256);
         // Не проверяем audience, т.к. в Keycloak aud может отличаться (строка/массив/"account")
 
         let token_data: TokenData<KeycloakAccessTokenClaims> = decode::<KeycloakAccessTokenClaims>(token, &decoding_key, &validation)?;
@@ -311,11 +417,42 @@ impl AuthService {
             family_name: claims.family_name,
             realm_access: claims.realm_access,
             resource_access: claims.resource_access,
+            groups: claims.groups,
         };
 
         Ok(user)
     }
 
+    /// `access_token` query parameter; only meant for WebSocket handshakes,
+    /// where browsers cannot set an Authorization header.
+    pub fn extract_token_from_query(&self, query: Option<&str>) -> Option<String> {
+        query?
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, v)| *k == "access_token" && !v.is_empty())
+            .map(|(_, v)| v.to_string())
+    }
+
+    /// `uri` without the `access_token` query parameter, so the token is
+    /// neither logged nor passed on to handlers.
+    pub fn strip_token_from_uri(uri: &Uri) -> Uri {
+        let Some(query) = uri.query().filter(|q| q.contains("access_token")) else {
+            return uri.clone();
+        };
+        let query = query
+            .split('&')
+            .filter(|pair| pair.split_once('=').map_or(*pair, |(k, _)| k) != "access_token")
+            .collect::<Vec<_>>()
+            .join("&");
+        let path_and_query = match query.is_empty() {
+            true => uri.path().to_string(),
+            false => format!("{}?{}", uri.path(), query),
+        };
+        let mut parts = uri.clone().into_parts();
+        parts.path_and_query = path_and_query.parse().ok();
+        Uri::from_parts(parts).unwrap_or_else(|_| uri.clone())
+    }
+
     pub fn extract_token_from_headers(&self, headers: &HeaderMap) -> Result<String> {
         let auth_header = headers
             .get("Authorization")
@@ -340,7 +477,7 @@ impl AuthService {
         
         // Add client roles
         if let Some(resource_access) = &user.resource_access {
-            for (_, access) in resource_access {
+            for access in resource_access.values() {
                 roles.extend(access.roles.clone());
             }
         }
@@ -382,14 +519,21 @@ struct RoleRepresentation {
     name: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct GroupRepresentation {
+    id: String,
+    name: String,
+    path: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct UserRepresentation<'a> {
     username: &'a str,
     email: &'a str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    firstName: Option<&'a str>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    lastName: Option<&'a str>,
+    #[serde(rename = "firstName", skip_serializing_if = "Option::is_none")]
+    first_name: Option<&'a str>,
+    #[serde(rename = "lastName", skip_serializing_if = "Option::is_none")]
+    last_name: Option<&'a str>,
     enabled: bool,
 }
 
@@ -410,7 +554,7 @@ impl AuthService {
 
         let resp = self
             .client
-            .post(&self.config.keycloak_token_url())
+            .post(self.config.keycloak_token_url())
             .form(&params)
             .send()
             .await?;
@@ -423,18 +567,102 @@ impl AuthService {
         Ok(token.access_token)
     }
 
-    pub async fn create_keycloak_user(&self, req: CreateUserRequest) -> Result<String> {
-        let admin_token = self.get_admin_access_token().await?;
+    /// Creates a user with password, realm roles and groups as one unit.
+    ///
+    /// Roles and groups are resolved before anything is written; if any step
+    /// after the user exists fails, the user is deleted again so a retry starts
+    /// from a clean state.
+    pub async fn create_keycloak_user(
+        &self,
+        req: CreateUserRequest,
+    ) -> std::result::Result<String, CreateUserError> {
+        let admin_token = self
+            .get_admin_access_token()
+            .await
+            .map_err(|e| CreateUserError::new(CreateUserStep::Authenticate, e))?;
+
+        let role_reps = self
+            .resolve_realm_roles(&admin_token, &req.roles)
+            .await
+            .map_err(|e| CreateUserError::new(CreateUserStep::ValidateRoles, e))?;
+
+        let mut group_ids = Vec::with_capacity(req.groups.len());
+        for group in &req.groups {
+            let group_id = self
+                .find_group_id(&admin_token, group)
+                .await
+                .and_then(|id| {
+                    id.ok_or_else(|| KeycloakError::NotFound(format!("Group '{}'", group)).into())
+                })
+                .map_err(|e| CreateUserError::new(CreateUserStep::ValidateGroups, e))?;
+            group_ids.push((group.clone(), group_id));
+        }
 
+        let user_id = self
+            .post_user_representation(&admin_token, &req)
+            .await
+            .map_err(|e| CreateUserError::new(CreateUserStep::CreateUser, e))?;
+
+        let completed = async {
+            self.set_user_password(&admin_token, &user_id, &req.password, false)
+                .await
+                .map_err(|e| (CreateUserStep::SetPassword, e))?;
+            if !role_reps.is_empty() {
+                self.add_realm_role_mappings(&admin_token, &user_id, &role_reps)
+                    .await
+                    .map_err(|e| (CreateUserStep::AssignRoles, e))?;
+            }
+            for (group, group_id) in &group_ids {
+                self.add_user_to_group(&admin_token, &user_id, group, group_id)
+                    .await
+                    .map_err(|e| (CreateUserStep::JoinGroups, e))?;
+            }
+            Ok::<(), (CreateUserStep, anyhow::Error)>(())
+        }
+        .await;
+
+        if let Err((step, cause)) = completed {
+            tracing::warn!(
+                "Create user '{}' failed at step '{}', rolling back: {}",
+                req.username,
+                step,
+                cause
+            );
+            let rolled_back = match self.delete_keycloak_user(&admin_token, &user_id).await {
+                Ok(()) => true,
+                Err(e) => {
+                    tracing::error!(
+                        "Rollback of user '{}' ({}) failed, manual cleanup required: {}",
+                        req.username,
+                        user_id,
+                        e
+                    );
+                    false
+                }
+            };
+            return Err(CreateUserError {
+                step,
+                cause,
+                rolled_back,
+            });
+        }
+
+        Ok(user_id)
+    }
+
+    async fn post_user_representation(
+        &self,
+        admin_token: &str,
+        req: &CreateUserRequest,
+    ) -> Result<String> {
         let user_rep = UserRepresentation {
             username: &req.username,
             email: &req.email,
-            firstName: req.first_name.as_deref(),
-            lastName: req.last_name.as_deref(),
+            first_name: req.first_name.as_deref(),
+            last_name: req.last_name.as_deref(),
             enabled: true,
         };
 
-        // Create user
         let users_url = format!(
             "{}/admin/realms/{}/users",
             self.config.keycloak_url, self.config.keycloak_realm
@@ -443,11 +671,15 @@ impl AuthService {
         let resp = self
             .client
             .post(&users_url)
-            .bearer_auth(&admin_token)
+            .bearer_auth(admin_token)
             .json(&user_rep)
             .send()
             .await?;
 
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(KeycloakError::Conflict(format!("User ({})", keycloak_error_message(&text))).into());
+        }
         if resp.status() != reqwest::StatusCode::CREATED {
             let status = resp.status();
             let text = resp.text().await.unwrap_or_default();
@@ -461,17 +693,24 @@ impl AuthService {
             .and_then(|v| v.to_str().ok())
             .ok_or_else(|| anyhow!("Location header missing in create user response"))?;
 
-        let user_id = location
+        Ok(location
             .rsplit('/')
             .next()
             .ok_or_else(|| anyhow!("Cannot parse user id from Location header"))?
-            .to_string();
+            .to_string())
+    }
 
-        // Set password
+    async fn set_user_password(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+        password: &str,
+        temporary: bool,
+    ) -> Result<()> {
         let cred = CredentialRepresentation {
             r#type: "password",
-            value: &req.password,
-            temporary: false,
+            value: password,
+            temporary,
         };
 
         let pwd_url = format!(
@@ -482,24 +721,141 @@ impl AuthService {
         let resp = self
             .client
             .put(&pwd_url)
-            .bearer_auth(&admin_token)
+            .bearer_auth(admin_token)
             .json(&cred)
             .send()
             .await?;
 
+        match resp.status() {
+            s if s.is_success() => Ok(()),
+            // Password policy violations come back as 400 with an explanation
+            reqwest::StatusCode::BAD_REQUEST => {
+                let text = resp.text().await.unwrap_or_default();
+                Err(KeycloakError::BadRequest(keycloak_error_message(&text)).into())
+            }
+            s => Err(anyhow!("Failed to set password: HTTP {}", s)),
+        }
+    }
+
+    pub async fn delete_keycloak_user(&self, admin_token: &str, user_id: &str) -> Result<()> {
+        let url = format!(
+            "{}/admin/realms/{}/users/{}",
+            self.config.keycloak_url, self.config.keycloak_realm, user_id
+        );
+        let resp = self
+            .client
+            .delete(&url)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        match resp.status() {
+            s if s.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND => Err(KeycloakError::NotFound(format!("User '{}'", user_id)).into()),
+            s => Err(anyhow!("Failed to delete user: HTTP {}", s)),
+        }
+    }
+
+    /// Returns the id of the user with exactly this username, if any.
+    pub async fn find_user_id_by_username(
+        &self,
+        admin_token: &str,
+        username: &str,
+    ) -> Result<Option<String>> {
+        let url = format!(
+            "{}/admin/realms/{}/users",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .query(&[("username", username), ("exact", "true")])
+            .send()
+            .await?;
         if !resp.status().is_success() {
-            return Err(anyhow!(
-                "Failed to set password: HTTP {}",
-                resp.status()
-            ));
+            return Err(anyhow!("Failed to query users: HTTP {}", resp.status()));
         }
+        let users: Vec<serde_json::Value> = resp.json().await?;
+        Ok(users
+            .iter()
+            .find(|u| {
+                u.get("username")
+                    .and_then(|v| v.as_str())
+                    .is_some_and(|v| v.eq_ignore_ascii_case(username))
+            })
+            .and_then(|u| u.get("id"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string))
+    }
 
-        // Assign realm roles if provided
-        if !req.roles.is_empty() {
-            self.assign_realm_roles(&admin_token, &user_id, &req.roles).await?;
+    pub async fn list_realm_role_names(&self, admin_token: &str) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/admin/realms/{}/roles",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .query(&[("briefRepresentation", "true")])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to list realm roles: HTTP {}", resp.status()));
         }
+        let roles: Vec<RoleRepresentation> = resp.json().await?;
+        Ok(roles.into_iter().map(|r| r.name).collect())
+    }
 
-        Ok(user_id)
+    /// Resolves a group by path; a bare name is treated as a top-level group.
+    pub async fn find_group_id(&self, admin_token: &str, group: &str) -> Result<Option<String>> {
+        let url = format!(
+            "{}/admin/realms/{}/group-by-path/{}",
+            self.config.keycloak_url,
+            self.config.keycloak_realm,
+            group_path(group)
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        match resp.status() {
+            reqwest::StatusCode::OK => {
+                let rep: GroupRepresentation = resp.json().await?;
+                Ok(Some(rep.id))
+            }
+            reqwest::StatusCode::NOT_FOUND => Ok(None),
+            s => Err(anyhow!("Failed to get group '{}': HTTP {}", group, s)),
+        }
+    }
+
+    async fn add_user_to_group(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+        group: &str,
+        group_id: &str,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/admin/realms/{}/users/{}/groups/{}",
+            self.config.keycloak_url, self.config.keycloak_realm, user_id, group_id
+        );
+        let resp = self
+            .client
+            .put(&url)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "Failed to join group '{}': HTTP {}",
+                group,
+                resp.status()
+            ));
+        }
+        Ok(())
     }
 
     pub async fn update_keycloak_user(
@@ -523,8 +879,12 @@ impl AuthService {
             .bearer_auth(&admin_token)
             .send()
             .await?;
-        if !current_resp.status().is_success() {
-            return Err(anyhow!("Failed to fetch user: HTTP {}", current_resp.status()));
+        match current_resp.status() {
+            s if s.is_success() => {}
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(KeycloakError::NotFound(format!("User '{}'", user_id)).into())
+            }
+            s => return Err(anyhow!("Failed to fetch user: HTTP {}", s)),
         }
         let mut current: serde_json::Value = current_resp.json().await?;
 
@@ -545,8 +905,13 @@ impl AuthService {
             .json(&current)
             .send()
             .await?;
-        if !resp.status().is_success() {
-            return Err(anyhow!("Failed to update user: HTTP {}", resp.status()));
+        match resp.status() {
+            s if s.is_success() => {}
+            reqwest::StatusCode::CONFLICT => {
+                let text = resp.text().await.unwrap_or_default();
+                return Err(KeycloakError::Conflict(format!("User ({})", keycloak_error_message(&text))).into());
+            }
+            s => return Err(anyhow!("Failed to update user: HTTP {}", s)),
         }
 
         // Update roles if provided
@@ -561,17 +926,35 @@ impl AuthService {
         &self,
         admin_token: &str,
         user_id: &str,
-        roles: &Vec<String>,
+        roles: &[String],
     ) -> Result<()> {
+        let role_reps = self.resolve_realm_roles(admin_token, roles).await?;
+        self.add_realm_role_mappings(admin_token, user_id, &role_reps)
+            .await
+    }
+
+    async fn resolve_realm_roles(
+        &self,
+        admin_token: &str,
+        roles: &[String],
+    ) -> Result<Vec<RoleRepresentation>> {
         let mut role_reps: Vec<RoleRepresentation> = Vec::new();
         for role in roles {
             if let Some(rep) = self.get_realm_role_representation(admin_token, role).await? {
                 role_reps.push(rep);
             } else {
-                return Err(anyhow!("Role '{}' not found", role));
+                return Err(KeycloakError::NotFound(format!("Role '{}'", role)).into());
             }
         }
+        Ok(role_reps)
+    }
 
+    async fn add_realm_role_mappings(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+        role_reps: &[RoleRepresentation],
+    ) -> Result<()> {
         let url = format!(
             "{}/admin/realms/{}/users/{}/role-mappings/realm",
             self.config.keycloak_url, self.config.keycloak_realm, user_id
@@ -597,7 +980,7 @@ impl AuthService {
         &self,
         admin_token: &str,
         user_id: &str,
-        roles: &Vec<String>,
+        roles: &[String],
     ) -> Result<()> {
         // Fetch current roles
         let current_roles_url = format!(
@@ -618,6 +1001,9 @@ impl AuthService {
         }
         let current: Vec<RoleRepresentation> = current_resp.json().await?;
 
+        // Resolve the new roles first so an unknown role leaves the mappings untouched
+        let role_reps = self.resolve_realm_roles(admin_token, roles).await?;
+
         // Remove all current roles
         if !current.is_empty() {
             let del_resp = self
@@ -636,7 +1022,8 @@ impl AuthService {
         }
 
         // Assign provided roles
-        self.assign_realm_roles(admin_token, user_id, roles).await
+        self.add_realm_role_mappings(admin_token, user_id, &role_reps)
+            .await
     }
 
     async fn get_realm_role_representation(
@@ -644,13 +1031,9 @@ impl AuthService {
         admin_token: &str,
         role_name: &str,
     ) -> Result<Option<RoleRepresentation>> {
-        let url = format!(
-            "{}/admin/realms/{}/roles/{}",
-            self.config.keycloak_url, self.config.keycloak_realm, role_name
-        );
         let resp = self
             .client
-            .get(&url)
+            .get(self.role_url(role_name)?)
             .bearer_auth(admin_token)
             .send()
             .await?;
@@ -663,4 +1046,601 @@ impl AuthService {
             s => Err(anyhow!("Failed to get role '{}': HTTP {}", role_name, s)),
         }
     }
+
+    /// Fetches one page of realm users (full representations) starting at `first`.
+    pub async fn list_users_page(
+        &self,
+        admin_token: &str,
+        first: usize,
+        max: usize,
+    ) -> Result<Vec<serde_json::Value>> {
+        let url = format!(
+            "{}/admin/realms/{}/users",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .query(&[
+                ("first", first.to_string()),
+                ("max", max.to_string()),
+                ("briefRepresentation", "false".to_string()),
+            ])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to list users: HTTP {}", resp.status()));
+        }
+        Ok(resp.json().await?)
+    }
+
+    pub async fn get_user_realm_role_names(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+    ) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/admin/realms/{}/users/{}/role-mappings/realm",
+            self.config.keycloak_url, self.config.keycloak_realm, user_id
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to get user roles: HTTP {}", resp.status()));
+        }
+        let roles: Vec<RoleRepresentation> = resp.json().await?;
+        Ok(roles.into_iter().map(|r| r.name).collect())
+    }
+
+    pub async fn get_user_group_paths(
+        &self,
+        admin_token: &str,
+        user_id: &str,
+    ) -> Result<Vec<String>> {
+        let url = format!(
+            "{}/admin/realms/{}/users/{}/groups",
+            self.config.keycloak_url, self.config.keycloak_realm, user_id
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to get user groups: HTTP {}", resp.status()));
+        }
+        let groups: Vec<GroupRepresentation> = resp.json().await?;
+        Ok(groups.into_iter().map(|g| g.path).collect())
+    }
+
+    /// Time of the user's most recent LOGIN event in milliseconds since the epoch.
+    /// Returns `None` when the realm does not store login events, and
+    /// `KeycloakError::Forbidden` when the service account may not read them.
+    pub async fn get_user_last_login(&self, admin_token: &str, user_id: &str) -> Result<Option<i64>> {
+        let url = format!(
+            "{}/admin/realms/{}/events",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .query(&[("user", user_id), ("type", "LOGIN"), ("max", "1")])
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::FORBIDDEN {
+            return Err(KeycloakError::Forbidden(
+                "Service account lacks realm-management 'view-events'".to_string(),
+            )
+            .into());
+        }
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to get login events: HTTP {}", resp.status()));
+        }
+        let events: Vec<serde_json::Value> = resp.json().await?;
+        Ok(events
+            .first()
+            .and_then(|e| e.get("time"))
+            .and_then(|t| t.as_i64()))
+    }
+}
+
+// --------------------------
+// Realm role management
+// --------------------------
+
+impl AuthService {
+    pub fn is_protected_role(&self, role_name: &str) -> bool {
+        PROTECTED_REALM_ROLES.contains(&role_name)
+            || role_name == "offline_access"
+            || role_name == "uma_authorization"
+            || role_name == format!("default-roles-{}", self.config.keycloak_realm)
+    }
+
+    /// Admin API URL of a realm role; the name is percent-encoded as a
+    /// single path segment. Empty names are rejected.
+    fn role_url(&self, role_name: &str) -> Result<String> {
+        if role_name.trim().is_empty() {
+            return Err(KeycloakError::BadRequest("Role name must not be empty".to_string()).into());
+        }
+        Ok(format!(
+            "{}/admin/realms/{}/roles/{}",
+            self.config.keycloak_url,
+            self.config.keycloak_realm,
+            encode_path_segment(role_name)
+        ))
+    }
+
+    fn role_from_representation(&self, rep: serde_json::Value) -> Result<Role> {
+        let mut role: Role = serde_json::from_value(rep)?;
+        role.protected = self.is_protected_role(&role.name);
+        Ok(role)
+    }
+
+    pub async fn list_realm_roles(&self, admin_token: &str) -> Result<Vec<Role>> {
+        let url = format!(
+            "{}/admin/realms/{}/roles",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let resp = self
+            .client
+            .get(&url)
+            .bearer_auth(admin_token)
+            .query(&[("briefRepresentation", "false")])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to list realm roles: HTTP {}", resp.status()));
+        }
+        let reps: Vec<serde_json::Value> = resp.json().await?;
+        reps.into_iter()
+            .map(|rep| self.role_from_representation(rep))
+            .collect()
+    }
+
+    pub async fn get_realm_role(&self, admin_token: &str, role_name: &str) -> Result<Role> {
+        let rep = self.get_realm_role_json(admin_token, role_name).await?;
+        self.role_from_representation(rep)
+    }
+
+    async fn get_realm_role_json(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+    ) -> Result<serde_json::Value> {
+        let resp = self
+            .client
+            .get(self.role_url(role_name)?)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        match resp.status() {
+            reqwest::StatusCode::OK => Ok(resp.json().await?),
+            reqwest::StatusCode::NOT_FOUND => {
+                Err(KeycloakError::NotFound(format!("Role '{}'", role_name)).into())
+            }
+            s => Err(anyhow!("Failed to get role '{}': HTTP {}", role_name, s)),
+        }
+    }
+
+    pub async fn create_realm_role(&self, admin_token: &str, req: CreateRoleRequest) -> Result<Role> {
+        let url = format!(
+            "{}/admin/realms/{}/roles",
+            self.config.keycloak_url, self.config.keycloak_realm
+        );
+        let body = serde_json::json!({
+            "name": req.name,
+            "description": req.description,
+            "attributes": req.attributes,
+        });
+        let resp = self
+            .client
+            .post(&url)
+            .bearer_auth(admin_token)
+            .json(&body)
+            .send()
+            .await?;
+        match resp.status() {
+            reqwest::StatusCode::CREATED => self.get_realm_role(admin_token, &req.name).await,
+            reqwest::StatusCode::CONFLICT => {
+                Err(KeycloakError::Conflict(format!("Role '{}'", req.name)).into())
+            }
+            s => {
+                let text = resp.text().await.unwrap_or_default();
+                Err(anyhow!("Failed to create role '{}': HTTP {} - {}", req.name, s, text))
+            }
+        }
+    }
+
+    pub async fn update_realm_role(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+        req: UpdateRoleRequest,
+    ) -> Result<Role> {
+        // Keycloak expects the full representation on update
+        let mut current = self.get_realm_role_json(admin_token, role_name).await?;
+        if let Some(description) = req.description {
+            current["description"] = serde_json::Value::String(description);
+        }
+        if let Some(attributes) = req.attributes {
+            current["attributes"] = serde_json::to_value(attributes)?;
+        }
+
+        let resp = self
+            .client
+            .put(self.role_url(role_name)?)
+            .bearer_auth(admin_token)
+            .json(&current)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Failed to update role '{}': HTTP {}", role_name, resp.status()));
+        }
+        self.get_realm_role(admin_token, role_name).await
+    }
+
+    pub async fn delete_realm_role(&self, admin_token: &str, role_name: &str) -> Result<()> {
+        if self.is_protected_role(role_name) {
+            return Err(KeycloakError::Forbidden(format!(
+                "Role '{}' is built-in and cannot be deleted",
+                role_name
+            ))
+            .into());
+        }
+        let resp = self
+            .client
+            .delete(self.role_url(role_name)?)
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        match resp.status() {
+            s if s.is_success() => Ok(()),
+            reqwest::StatusCode::NOT_FOUND => {
+                Err(KeycloakError::NotFound(format!("Role '{}'", role_name)).into())
+            }
+            s => Err(anyhow!("Failed to delete role '{}': HTTP {}", role_name, s)),
+        }
+    }
+
+    /// Users that hold the role directly (not through groups or composites).
+    pub async fn list_realm_role_members(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+    ) -> Result<Vec<RoleMember>> {
+        let mut members = Vec::new();
+        let page_size = 100usize;
+        let mut first = 0usize;
+        loop {
+            let resp = self
+                .client
+                .get(format!("{}/users", self.role_url(role_name)?))
+                .bearer_auth(admin_token)
+                .query(&[("first", first), ("max", page_size)])
+                .send()
+                .await?;
+            match resp.status() {
+                reqwest::StatusCode::OK => {}
+                reqwest::StatusCode::NOT_FOUND => {
+                    return Err(KeycloakError::NotFound(format!("Role '{}'", role_name)).into())
+                }
+                s => return Err(anyhow!("Failed to list members of '{}': HTTP {}", role_name, s)),
+            }
+            let users: Vec<serde_json::Value> = resp.json().await?;
+            let fetched = users.len();
+            members.extend(users.into_iter().map(|u| {
+                let str_field = |name: &str| u.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                RoleMember {
+                    id: str_field("id").unwrap_or_default(),
+                    username: str_field("username").unwrap_or_default(),
+                    email: str_field("email"),
+                    first_name: str_field("firstName"),
+                    last_name: str_field("lastName"),
+                    enabled: u.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false),
+                }
+            }));
+            if fetched < page_size {
+                return Ok(members);
+            }
+            first += fetched;
+        }
+    }
+
+    pub async fn get_realm_role_composites(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+    ) -> Result<Vec<Role>> {
+        let resp = self
+            .client
+            .get(format!("{}/composites", self.role_url(role_name)?))
+            .bearer_auth(admin_token)
+            .send()
+            .await?;
+        match resp.status() {
+            reqwest::StatusCode::OK => {}
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(KeycloakError::NotFound(format!("Role '{}'", role_name)).into())
+            }
+            s => return Err(anyhow!("Failed to get composites of '{}': HTTP {}", role_name, s)),
+        }
+        let reps: Vec<serde_json::Value> = resp.json().await?;
+        reps.into_iter()
+            .map(|rep| self.role_from_representation(rep))
+            .collect()
+    }
+
+    pub async fn add_realm_role_composites(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+        composites: &[String],
+    ) -> Result<()> {
+        self.change_realm_role_composites(admin_token, role_name, composites, true)
+            .await
+    }
+
+    pub async fn remove_realm_role_composites(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+        composites: &[String],
+    ) -> Result<()> {
+        self.change_realm_role_composites(admin_token, role_name, composites, false)
+            .await
+    }
+
+    async fn change_realm_role_composites(
+        &self,
+        admin_token: &str,
+        role_name: &str,
+        composites: &[String],
+        add: bool,
+    ) -> Result<()> {
+        // Make sure the parent exists so a typo yields 404 rather than a Keycloak error
+        self.get_realm_role_json(admin_token, role_name).await?;
+
+        let mut reps: Vec<RoleRepresentation> = Vec::new();
+        for composite in composites {
+            if composite == role_name {
+                return Err(KeycloakError::BadRequest(format!(
+                    "Role '{}' cannot be a composite of itself",
+                    role_name
+                ))
+                .into());
+            }
+            match self.get_realm_role_representation(admin_token, composite).await? {
+                Some(rep) => reps.push(rep),
+                None => {
+                    return Err(KeycloakError::NotFound(format!("Role '{}'", composite)).into())
+                }
+            }
+        }
+
+        let url = format!("{}/composites", self.role_url(role_name)?);
+        let request = if add {
+            self.client.post(&url)
+        } else {
+            self.client.delete(&url)
+        };
+        let resp = request.bearer_auth(admin_token).json(&reps).send().await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!(
+                "Failed to update composites of '{}': HTTP {}",
+                role_name,
+                resp.status()
+            ));
+        }
+        Ok(())
+    }
+}
+
+// --------------------------
+// Self-service account
+// --------------------------
+
+impl AuthService {
+    /// Updates the caller's own name and email. Roles are never touched here.
+    ///
+    /// A changed email is marked unverified and the VERIFY_EMAIL required
+    /// action is added, so Keycloak asks the user to confirm it. Returns the
+    /// updated user representation.
+    pub async fn update_own_profile(
+        &self,
+        user_id: &str,
+        req: UpdateProfileRequest,
+    ) -> Result<serde_json::Value> {
+        let admin_token = self.get_admin_access_token().await?;
+        let user_url = format!(
+            "{}/admin/realms/{}/users/{}",
+            self.config.keycloak_url, self.config.keycloak_realm, user_id
+        );
+
+        let current_resp = self
+            .client
+            .get(&user_url)
+            .bearer_auth(&admin_token)
+            .send()
+            .await?;
+        match current_resp.status() {
+            s if s.is_success() => {}
+            reqwest::StatusCode::NOT_FOUND => {
+                return Err(KeycloakError::NotFound(format!("User '{}'", user_id)).into())
+            }
+            s => return Err(anyhow!("Failed to fetch user: HTTP {}", s)),
+        }
+        let mut current: serde_json::Value = current_resp.json().await?;
+
+        let mut email_changed = false;
+        if let Some(email) = req.email {
+            let old = current.get("email").and_then(|v| v.as_str()).unwrap_or("");
+            if !old.eq_ignore_ascii_case(&email) {
+                email_changed = true;
+                current["email"] = serde_json::Value::String(email);
+                current["emailVerified"] = serde_json::Value::Bool(false);
+                let mut actions: Vec<serde_json::Value> = current
+                    .get("requiredActions")
+                    .and_then(|v| v.as_array())
+                    .cloned()
+                    .unwrap_or_default();
+                if !actions.iter().any(|a| a == "VERIFY_EMAIL") {
+                    actions.push(serde_json::Value::String("VERIFY_EMAIL".to_string()));
+                }
+                current["requiredActions"] = serde_json::Value::Array(actions);
+            }
+        }
+        if let Some(first_name) = req.first_name {
+            current["firstName"] = serde_json::Value::String(first_name);
+        }
+        if let Some(last_name) = req.last_name {
+            current["lastName"] = serde_json::Value::String(last_name);
+        }
+
+        let resp = self
+            .client
+            .put(&user_url)
+            .bearer_auth(&admin_token)
+            .json(&current)
+            .send()
+            .await?;
+        match resp.status() {
+            s if s.is_success() => {}
+            reqwest::StatusCode::CONFLICT => {
+                let text = resp.text().await.unwrap_or_default();
+                return Err(KeycloakError::Conflict(format!("User ({})", keycloak_error_message(&text))).into());
+            }
+            s => return Err(anyhow!("Failed to update profile: HTTP {}", s)),
+        }
+
+        if email_changed {
+            // Without SMTP configured this fails; the required action still applies on next login
+            let verify_url = format!("{}/send-verify-email", user_url);
+            match self
+                .client
+                .put(&verify_url)
+                .bearer_auth(&admin_token)
+                .send()
+                .await
+            {
+                Ok(r) if r.status().is_success() => {}
+                Ok(r) => tracing::warn!("Verification email for {} not sent: HTTP {}", user_id, r.status()),
+                Err(e) => tracing::warn!("Verification email for {} not sent: {}", user_id, e),
+            }
+        }
+
+        Ok(current)
+    }
+
+    /// Checks a password with a direct grant against the backend client.
+    /// Returns `false` when Keycloak rejects the credentials.
+    pub async fn verify_user_password(&self, username: &str, password: &str) -> Result<bool> {
+        let params = [
+            ("grant_type", "password"),
+            ("client_id", self.config.keycloak_client_id.as_str()),
+            ("client_secret", self.config.keycloak_client_secret.as_str()),
+            ("username", username),
+            ("password", password),
+        ];
+        let resp = self
+            .client
+            .post(self.config.keycloak_token_url())
+            .form(&params)
+            .send()
+            .await?;
+        let status = resp.status();
+        if !status.is_success() {
+            // Only a rejected grant means a wrong password; disabled clients,
+            // required actions and the like are reported as errors
+            let text = resp.text().await.unwrap_or_default();
+            let error = serde_json::from_str::<serde_json::Value>(&text)
+                .ok()
+                .and_then(|v| v.get("error").and_then(|e| e.as_str()).map(str::to_string));
+            if error.as_deref() == Some("invalid_grant") {
+                return Ok(false);
+            }
+            return Err(anyhow!(
+                "Failed to verify password: HTTP {} - {}",
+                status,
+                keycloak_error_message(&text)
+            ));
+        }
+
+        // The check opened a session for the user; end it right away
+        let tokens: serde_json::Value = resp.json().await?;
+        if let Some(refresh_token) = tokens.get("refresh_token").and_then(|t| t.as_str()) {
+            if let Err(e) = self.revoke_session(refresh_token).await {
+                tracing::warn!("Failed to end password check session of '{}': {}", username, e);
+            }
+        }
+        Ok(true)
+    }
+
+    async fn revoke_session(&self, refresh_token: &str) -> Result<()> {
+        let params = [
+            ("client_id", self.config.keycloak_client_id.as_str()),
+            ("client_secret", self.config.keycloak_client_secret.as_str()),
+            ("refresh_token", refresh_token),
+        ];
+        let resp = self
+            .client
+            .post(self.config.keycloak_logout_url())
+            .form(&params)
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            return Err(anyhow!("Logout failed: HTTP {}", resp.status()));
+        }
+        Ok(())
+    }
+
+    /// Sets a new password for the caller after checking the current one.
+    pub async fn change_own_password(
+        &self,
+        user_id: &str,
+        username: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> Result<()> {
+        if !self.verify_user_password(username, current_password).await? {
+            return Err(KeycloakError::Forbidden("Current password is incorrect".to_string()).into());
+        }
+        let admin_token = self.get_admin_access_token().await?;
+        self.set_user_password(&admin_token, user_id, new_password, false)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(uri: &str) -> String {
+        AuthService::strip_token_from_uri(&uri.parse().unwrap()).to_string()
+    }
+
+    #[test]
+    fn access_token_is_stripped_from_uris() {
+        assert_eq!(strip("/api/v1/clusters/x/watch?access_token=secret"), "/api/v1/clusters/x/watch");
+        assert_eq!(
+            strip("/watch?kind=Pod&access_token=secret&namespace=shop"),
+            "/watch?kind=Pod&namespace=shop"
+        );
+        assert_eq!(strip("/watch?kind=Pod&my_access_token=1"), "/watch?kind=Pod&my_access_token=1");
+        assert_eq!(strip("/health"), "/health");
+    }
+
+    #[test]
+    fn group_paths_encode_each_segment() {
+        assert_eq!(group_path("/Platform Team"), "Platform%20Team");
+        assert_eq!(group_path("devs"), "devs");
+        assert_eq!(group_path("/org/R&D #1?"), "org/R%26D%20%231%3F");
+        assert_eq!(group_path("/org/sub/team"), "org/sub/team");
+    }
 }
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::config::ProxyConfig;
+use crate::credentials::CredentialVault;
+use crate::kube_client::KubeClient;
+use crate::models::Cluster;
+
+struct CachedClient {
+    endpoint: String,
+    credential_updated_at: Option<DateTime<Utc>>,
+    expires: Instant,
+    client: KubeClient,
+}
+
+/// Streaming clients for proxied requests, reused per cluster so TLS setup and
+/// OIDC refreshes do not happen on every call. An entry is rebuilt when the
+/// endpoint or the stored credential changes, or after the TTL or shortly
+/// before its bearer token expires, whichever comes first.
+#[derive(Clone)]
+pub struct ClusterClients {
+    credentials: CredentialVault,
+    connect_timeout: Duration,
+    ttl: Duration,
+    entries: Arc<Mutex<HashMap<Uuid, CachedClient>>>,
+}
+
+impl ClusterClients {
+    pub fn new(credentials: CredentialVault, config: &ProxyConfig) -> Self {
+        Self {
+            credentials,
+            connect_timeout: Duration::from_secs(config.connect_timeout_secs),
+            ttl: Duration::from_secs(config.client_cache_ttl_secs),
+            entries: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub async fn get(&self, cluster: &Cluster) -> Result<KubeClient> {
+        let credential_updated_at = self.credentials.info(cluster.id).await?.map(|i| i.updated_at);
+        if let Some(entry) = self.entries.lock().unwrap().get(&cluster.id) {
+            if entry.endpoint == cluster.endpoint
+                && entry.credential_updated_at == credential_updated_at
+                && Instant::now() < entry.expires
+            {
+                return Ok(entry.client.clone());
+            }
+        }
+
+        let client = self.credentials.client(cluster, self.connect_timeout, None).await?;
+        // Refreshing an OIDC credential stores it again
+        let credential_updated_at = self.credentials.info(cluster.id).await?.map(|i| i.updated_at);
+        let lifetime = client.bearer_lifetime().map_or(self.ttl, |lifetime| lifetime.min(self.ttl));
+        self.entries.lock().unwrap().insert(
+            cluster.id,
+            CachedClient {
+                endpoint: cluster.endpoint.clone(),
+                credential_updated_at,
+                expires: Instant::now() + lifetime,
+                client: client.clone(),
+            },
+        );
+        Ok(client)
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+use crate::credentials::{CredentialError, CredentialVault};
+use crate::kubeconfig::{self, KubeconfigContext};
+use crate::models::{
+    CaSource, Cluster, ClusterCredential, ClusterImportResult, ClusterImportSelection, ClusterImportStatus, ClusterRef,
+    ImpersonationSettings, KubeconfigContextPreview,
+};
+use crate::repositories::{ClusterRepository, RepositoryError};
+use crate::validation::{self, ValidationErrors};
+
+fn find_duplicate<'a>(clusters: &'a [Cluster], server: &str) -> Option<&'a Cluster> {
+    clusters.iter().find(|c| c.endpoint == server)
+}
+
+/// Describes every context without exposing credential material.
+pub fn preview(contexts: &[KubeconfigContext], existing: &[Cluster]) -> Vec<KubeconfigContextPreview> {
+    contexts
+        .iter()
+        .map(|ctx| KubeconfigContextPreview {
+            context: ctx.context.clone(),
+            cluster_name: ctx.cluster_name.clone(),
+            user: ctx.user_name.clone(),
+            namespace: ctx.namespace.clone(),
+            server: ctx.server.clone(),
+            current: ctx.current,
+            suggested_name: kubeconfig::suggest_cluster_name(&ctx.context),
+            ca_source: if ctx.ca_bundle_pem.is_some() {
+                CaSource::Inline
+            } else if ctx.ca_file.is_some() {
+                CaSource::File
+            } else {
+                CaSource::None
+            },
+            insecure_skip_tls_verify: ctx.insecure_skip_tls_verify,
+            tls_server_name: ctx.tls_server_name.clone(),
+            auth_method: ctx.auth_method,
+            importable: ctx.problems.is_empty(),
+            problems: ctx.problems.clone(),
+            duplicate_of: find_duplicate(existing, &ctx.server).map(|c| ClusterRef {
+                id: c.id,
+                name: c.name.clone(),
+            }),
+        })
+        .collect()
+}
+
+/// Registers the selected contexts in order, together with their credentials.
+/// A context whose server is already registered, or was registered earlier in
+/// the same selection, is skipped; a context that fails is reported and the
+/// rest are still imported.
+pub async fn import_selected(
+    repo: &ClusterRepository,
+    vault: &CredentialVault,
+    contexts: &[KubeconfigContext],
+    existing: &[Cluster],
+    selections: Vec<ClusterImportSelection>,
+) -> Vec<ClusterImportResult> {
+    let by_name: HashMap<&str, &KubeconfigContext> =
+        contexts.iter().map(|c| (c.context.as_str(), c)).collect();
+    let mut registered: HashSet<String> = existing.iter().map(|c| c.endpoint.clone()).collect();
+    let mut seen_contexts: HashSet<String> = HashSet::new();
+    let mut results = Vec::with_capacity(selections.len());
+
+    for selection in selections {
+        let failed = |reasons: Vec<String>| ClusterImportResult {
+            context: selection.context.clone(),
+            status: ClusterImportStatus::Failed,
+            cluster: None,
+            reasons,
+        };
+
+        if !seen_contexts.insert(selection.context.clone()) {
+            results.push(failed(vec!["context selected more than once".to_string()]));
+            continue;
+        }
+        let Some(ctx) = by_name.get(selection.context.as_str()) else {
+            results.push(failed(vec!["context not found in kubeconfig".to_string()]));
+            continue;
+        };
+        if !ctx.problems.is_empty() {
+            results.push(failed(ctx.problems.clone()));
+            continue;
+        }
+
+        let name = selection
+            .name
+            .clone()
+            .unwrap_or_else(|| kubeconfig::suggest_cluster_name(&ctx.context));
+        let description = selection.description.as_deref().filter(|d| !d.trim().is_empty());
+        let credential = ctx.credential();
+        let mut errors = ValidationErrors::new();
+        validation::validate_cluster_name(&mut errors, "name", &name);
+        validation::validate_cluster_credential(&mut errors, "credential", &credential);
+        if !errors.is_empty() {
+            results.push(failed(errors.messages()));
+            continue;
+        }
+        let has_credential = credential != ClusterCredential::default();
+        if has_credential && !vault.is_configured() {
+            results.push(failed(vec![CredentialError::NotConfigured.to_string()]));
+            continue;
+        }
+
+        if registered.contains(&ctx.server) {
+            let reason = match find_duplicate(existing, &ctx.server) {
+                Some(c) => format!("endpoint already registered as cluster '{}'", c.name),
+                None => "endpoint already imported from another selected context".to_string(),
+            };
+            results.push(ClusterImportResult {
+                context: selection.context.clone(),
+                status: ClusterImportStatus::SkippedDuplicate,
+                cluster: None,
+                reasons: vec![reason],
+            });
+            continue;
+        }
+
+        match repo
+            .create(&name, description, &ctx.server, &ImpersonationSettings::default())
+            .await {
+            Ok(cluster) => {
+                if has_credential {
+                    if let Err(e) = vault.store(cluster.id, &credential).await {
+                        warn!("Storing credential of context '{}' failed: {}", selection.context, e);
+                        // Do not leave a cluster behind that cannot be reached
+                        if let Err(e) = repo.delete(cluster.id).await {
+                            warn!(
+                                "Removing cluster '{}' ({}) after its credential could not be stored failed: {}",
+                                cluster.name, cluster.id, e
+                            );
+                        }
+                        results.push(failed(vec![format!("failed to store credential: {}", e)]));
+                        continue;
+                    }
+                }
+                registered.insert(ctx.server.clone());
+                results.push(ClusterImportResult {
+                    context: selection.context.clone(),
+                    status: ClusterImportStatus::Created,
+                    cluster: Some(cluster),
+                    reasons: vec![],
+                });
+            }
+            Err(e) => {
+                // Clusters created from earlier contexts stay, so keep reporting
+                if !matches!(e.downcast_ref::<RepositoryError>(), Some(RepositoryError::Conflict { .. })) {
+                    warn!("Kubeconfig import of context '{}' failed: {}", selection.context, e);
+                }
+                results.push(failed(vec![e.to_string()]));
+            }
+        }
+    }
+
+    info!(
+        "Kubeconfig import finished: selected={}, created={}",
+        results.len(),
+        results
+            .iter()
+            .filter(|r| r.status == ClusterImportStatus::Created)
+            .count()
+    );
+    results
+}
@@ -0,0 +1,359 @@
+use anyhow::Result;
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::config::ClusterProbeConfig;
+use crate::credentials::CredentialVault;
+use crate::kube_client::{KubeApiError, KubeClient};
+use crate::models::{Cluster, ClusterHealth, ClusterStatus};
+use crate::repositories::ClusterRepository;
+
+/// Result of a single probe, before hysteresis is applied.
+#[derive(Debug, Clone)]
+pub enum ProbeOutcome {
+    Healthy {
+        version: String,
+        latency_ms: i32,
+    },
+    /// The API server answered but is not ready or rejected the credential
+    Unhealthy {
+        version: Option<String>,
+        latency_ms: Option<i32>,
+        error: String,
+    },
+    /// Connection, TLS or timeout failure
+    Unreachable { error: String },
+}
+
+fn classify(e: anyhow::Error, version: Option<String>, latency_ms: Option<i32>) -> ProbeOutcome {
+    if let Some(api) = e.downcast_ref::<KubeApiError>() {
+        let error = match api.status.as_u16() {
+            401 | 403 => format!("credential rejected: {}", api),
+            _ => api.to_string(),
+        };
+        return ProbeOutcome::Unhealthy { version, latency_ms, error };
+    }
+    match e.downcast_ref::<reqwest::Error>() {
+        Some(re) if re.is_decode() => ProbeOutcome::Unhealthy {
+            version,
+            latency_ms,
+            error: format!("unexpected response: {}", re),
+        },
+        // reqwest already includes the underlying cause, e.g. "connection refused"
+        _ => ProbeOutcome::Unreachable { error: e.to_string() },
+    }
+}
+
+/// Calls `/version`, then `/readyz` (falling back to `/healthz` on servers without it).
+pub async fn probe(client: &KubeClient) -> ProbeOutcome {
+    let started = Instant::now();
+    let version = match client.version().await {
+        Ok(version) => version.git_version,
+        Err(e) => return classify(e, None, None),
+    };
+    let latency_ms = started.elapsed().as_millis().min(i32::MAX as u128) as i32;
+
+    let ready = match client.get("/readyz").await {
+        Err(e)
+            if e.downcast_ref::<KubeApiError>()
+                .is_some_and(|api| api.status == reqwest::StatusCode::NOT_FOUND) =>
+        {
+            client.get("/healthz").await
+        }
+        other => other,
+    };
+    match ready {
+        Ok(_) => ProbeOutcome::Healthy { version, latency_ms },
+        Err(e) => classify(e, Some(version), Some(latency_ms)),
+    }
+}
+
+/// Applies the outcome with hysteresis: a healthy cluster needs
+/// `failure_threshold` failures in a row to leave `active`, a failing one
+/// `success_threshold` successes to return. `pending` follows the first probe.
+pub fn next_state(
+    cluster: &Cluster,
+    outcome: &ProbeOutcome,
+    config: &ClusterProbeConfig,
+) -> (ClusterStatus, ClusterHealth) {
+    let now = Utc::now();
+    let previous = &cluster.health;
+    let mut health = ClusterHealth {
+        last_probe_at: Some(now),
+        ..previous.clone()
+    };
+
+    let failed_status = match outcome {
+        ProbeOutcome::Healthy { version, latency_ms } => {
+            health.kubernetes_version = Some(version.clone());
+            health.probe_latency_ms = Some(*latency_ms);
+            health.last_error = None;
+            health.last_success_at = Some(now);
+            health.consecutive_successes = previous.consecutive_successes.saturating_add(1);
+            health.consecutive_failures = 0;
+            None
+        }
+        ProbeOutcome::Unhealthy { version, latency_ms, error } => {
+            if version.is_some() {
+                health.kubernetes_version = version.clone();
+            }
+            health.probe_latency_ms = *latency_ms;
+            health.last_error = Some(error.clone());
+            Some(ClusterStatus::Error)
+        }
+        ProbeOutcome::Unreachable { error } => {
+            health.probe_latency_ms = None;
+            health.last_error = Some(error.clone());
+            Some(ClusterStatus::Inactive)
+        }
+    };
+    if failed_status.is_some() {
+        health.consecutive_failures = previous.consecutive_failures.saturating_add(1);
+        health.consecutive_successes = 0;
+    }
+
+    let status = match (cluster.status, failed_status) {
+        (ClusterStatus::Pending | ClusterStatus::Active, None) => ClusterStatus::Active,
+        (current, None) => {
+            if health.consecutive_successes >= config.success_threshold as i32 {
+                ClusterStatus::Active
+            } else {
+                current
+            }
+        }
+        (ClusterStatus::Active, Some(failed)) => {
+            if health.consecutive_failures >= config.failure_threshold as i32 {
+                failed
+            } else {
+                ClusterStatus::Active
+            }
+        }
+        // Already failing (or never probed): report the latest kind of failure
+        (_, Some(failed)) => failed,
+    };
+    (status, health)
+}
+
+/// Periodically probes every registered cluster and records the result.
+#[derive(Clone)]
+pub struct ClusterProber {
+    clusters: ClusterRepository,
+    credentials: CredentialVault,
+    config: ClusterProbeConfig,
+}
+
+impl ClusterProber {
+    pub fn new(
+        clusters: ClusterRepository,
+        credentials: CredentialVault,
+        config: ClusterProbeConfig,
+    ) -> Self {
+        Self {
+            clusters,
+            credentials,
+            config,
+        }
+    }
+
+    /// Probes one cluster now and returns it with the updated health.
+    pub async fn probe_cluster(&self, cluster: &Cluster) -> Result<Cluster> {
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let outcome = match self.credentials.client(cluster, timeout, Some(timeout)).await {
+            Ok(client) => probe(&client).await,
+            // Covers OIDC refresh failures, which can also mean an unreachable issuer
+            Err(e) => ProbeOutcome::Unhealthy {
+                version: None,
+                latency_ms: None,
+                error: format!("{:#}", e),
+            },
+        };
+        let (status, health) = next_state(cluster, &outcome, &self.config);
+        if status != cluster.status {
+            info!(
+                "Cluster '{}' status {:?} -> {:?} ({})",
+                cluster.name,
+                cluster.status,
+                status,
+                health.last_error.as_deref().unwrap_or("ok")
+            );
+        } else {
+            debug!("Cluster '{}' probed: {:?}", cluster.name, outcome);
+        }
+        self.clusters.record_probe(cluster.id, status, &health).await?;
+        self.clusters.get(cluster.id).await
+    }
+
+    pub async fn probe_all(&self) -> Result<()> {
+        let clusters = self.clusters.list().await?;
+        stream::iter(clusters)
+            .for_each_concurrent(self.config.concurrency, |cluster| async move {
+                if let Err(e) = self.probe_cluster(&cluster).await {
+                    warn!("Failed to record probe of cluster '{}': {}", cluster.name, e);
+                }
+            })
+            .await;
+        Ok(())
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.probe_all().await {
+                    warn!("Cluster health probe round failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kube_client::fake_server;
+    use crate::models::ImpersonationSettings;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn config() -> ClusterProbeConfig {
+        ClusterProbeConfig {
+            enabled: true,
+            interval_secs: 30,
+            timeout_secs: 5,
+            concurrency: 1,
+            failure_threshold: 2,
+            success_threshold: 2,
+        }
+    }
+
+    fn cluster(status: ClusterStatus) -> Cluster {
+        Cluster {
+            id: Uuid::new_v4(),
+            name: "prod".to_string(),
+            description: None,
+            endpoint: "https://prod.example.test".to_string(),
+            status,
+            health: ClusterHealth::default(),
+            impersonation: ImpersonationSettings::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    async fn probe_router(router: Router) -> ProbeOutcome {
+        let url = fake_server::spawn(router).await;
+        let (client, _) = KubeClient::connect(&url, None, TIMEOUT, Some(TIMEOUT)).await.unwrap();
+        probe(&client).await
+    }
+
+    fn version() -> Json<serde_json::Value> {
+        Json(json!({ "gitVersion": "v1.29.3" }))
+    }
+
+    #[tokio::test]
+    async fn healthy_when_version_and_readyz_answer() {
+        let router = Router::new()
+            .route("/version", get(|| async { version() }))
+            .route("/readyz", get(|| async { "ok" }));
+        match probe_router(router).await {
+            ProbeOutcome::Healthy { version, .. } => assert_eq!(version, "v1.29.3"),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_healthz_without_readyz() {
+        let router = Router::new()
+            .route("/version", get(|| async { version() }))
+            .route("/healthz", get(|| async { "ok" }));
+        assert!(matches!(probe_router(router).await, ProbeOutcome::Healthy { .. }));
+    }
+
+    #[tokio::test]
+    async fn rejected_credential_is_unhealthy() {
+        let router = Router::new().route(
+            "/version",
+            get(|| async { (StatusCode::UNAUTHORIZED, Json(json!({ "message": "Unauthorized" }))) }),
+        );
+        match probe_router(router).await {
+            ProbeOutcome::Unhealthy { version: None, error, .. } => {
+                assert!(error.starts_with("credential rejected"), "{}", error)
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn not_ready_keeps_the_version() {
+        let router = Router::new()
+            .route("/version", get(|| async { version() }))
+            .route("/readyz", get(|| async { (StatusCode::INTERNAL_SERVER_ERROR, "etcd failed") }));
+        match probe_router(router).await {
+            ProbeOutcome::Unhealthy { version, latency_ms, .. } => {
+                assert_eq!(version.as_deref(), Some("v1.29.3"));
+                assert!(latency_ms.is_some());
+            }
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn closed_port_is_unreachable() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let (client, _) = KubeClient::connect(&url, None, TIMEOUT, Some(TIMEOUT)).await.unwrap();
+        assert!(matches!(probe(&client).await, ProbeOutcome::Unreachable { .. }));
+    }
+
+    #[test]
+    fn active_cluster_needs_consecutive_failures_to_leave() {
+        let config = config();
+        let unreachable = ProbeOutcome::Unreachable { error: "connection refused".to_string() };
+        let mut cluster = cluster(ClusterStatus::Active);
+
+        let (status, health) = next_state(&cluster, &unreachable, &config);
+        assert_eq!(status, ClusterStatus::Active);
+        assert_eq!(health.consecutive_failures, 1);
+
+        cluster.health = health;
+        let (status, health) = next_state(&cluster, &unreachable, &config);
+        assert_eq!(status, ClusterStatus::Inactive);
+        assert_eq!(health.last_error.as_deref(), Some("connection refused"));
+    }
+
+    #[test]
+    fn failing_cluster_needs_consecutive_successes_to_return() {
+        let config = config();
+        let healthy = ProbeOutcome::Healthy { version: "v1.29.3".to_string(), latency_ms: 12 };
+        let mut cluster = cluster(ClusterStatus::Error);
+        cluster.health.consecutive_failures = 3;
+
+        let (status, health) = next_state(&cluster, &healthy, &config);
+        assert_eq!(status, ClusterStatus::Error);
+        assert_eq!(health.consecutive_failures, 0);
+
+        cluster.health = health;
+        let (status, health) = next_state(&cluster, &healthy, &config);
+        assert_eq!(status, ClusterStatus::Active);
+        assert_eq!(health.kubernetes_version.as_deref(), Some("v1.29.3"));
+        assert_eq!(health.last_error, None);
+    }
+
+    #[test]
+    fn pending_cluster_follows_the_first_probe() {
+        let config = config();
+        let healthy = ProbeOutcome::Healthy { version: "v1.29.3".to_string(), latency_ms: 12 };
+        let unhealthy = ProbeOutcome::Unhealthy { version: None, latency_ms: None, error: "denied".to_string() };
+        assert_eq!(next_state(&cluster(ClusterStatus::Pending), &healthy, &config).0, ClusterStatus::Active);
+        assert_eq!(next_state(&cluster(ClusterStatus::Pending), &unhealthy, &config).0, ClusterStatus::Error);
+    }
+}
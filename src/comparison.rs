@@ -0,0 +1,200 @@
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use serde_json::Value;
+use sha2::Sha256;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::inventory_collector::{array_at, str_at};
+use crate::models::{
+    ComparedCluster, ComparisonReport, ComparisonStatus, ComparisonSummary, DifferenceCategory,
+    FieldDifference, ResourceComparison,
+};
+
+/// A kind that can be compared across clusters.
+#[derive(Debug, Clone, Copy)]
+pub struct ComparedKind {
+    pub kind: &'static str,
+    pub api_group: &'static str,
+    pub resource: &'static str,
+    /// Pointer to the pod spec of workloads
+    pod_spec: Option<&'static str>,
+}
+
+impl ComparedKind {
+    /// Case-insensitive, accepts the kind (`Deployment`) or its plural resource (`deployments`).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        COMPARED_KINDS
+            .into_iter()
+            .find(|kind| value == kind.kind.to_ascii_lowercase() || value == kind.resource)
+    }
+}
+
+pub const COMPARED_KINDS: [ComparedKind; 7] = [
+    ComparedKind { kind: "Deployment", api_group: "apps", resource: "deployments", pod_spec: Some("/spec/template/spec") },
+    ComparedKind { kind: "StatefulSet", api_group: "apps", resource: "statefulsets", pod_spec: Some("/spec/template/spec") },
+    ComparedKind { kind: "DaemonSet", api_group: "apps", resource: "daemonsets", pod_spec: Some("/spec/template/spec") },
+    ComparedKind { kind: "Job", api_group: "batch", resource: "jobs", pod_spec: Some("/spec/template/spec") },
+    ComparedKind {
+        kind: "CronJob",
+        api_group: "batch",
+        resource: "cronjobs",
+        pod_spec: Some("/spec/jobTemplate/spec/template/spec"),
+    },
+    ComparedKind { kind: "ConfigMap", api_group: "", resource: "configmaps", pod_spec: None },
+    ComparedKind { kind: "Secret", api_group: "", resource: "secrets", pod_spec: None },
+];
+
+/// Compared fields of one object: field name → category and value.
+type Facts = BTreeMap<String, (DifferenceCategory, Value)>;
+
+/// Fingerprints secret values with a key that lives for one comparison, so
+/// equal values can be recognised without exposing a reusable hash.
+struct Fingerprinter {
+    key: [u8; 32],
+}
+
+impl Fingerprinter {
+    fn new() -> Self {
+        Self { key: rand::random() }
+    }
+
+    fn fingerprint(&self, value: &[u8]) -> Value {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(value);
+        Value::String(format!("hmac-sha256:{}", hex::encode(&mac.finalize().into_bytes()[..12])))
+    }
+}
+
+fn container_facts(facts: &mut Facts, pod_spec: &Value) {
+    for (list, prefix) in [("/initContainers", "initContainers"), ("/containers", "containers")] {
+        for container in array_at(pod_spec, list) {
+            let name = str_at(container, "/name").unwrap_or_default();
+            let field = |rest: &str| format!("{}[{}].{}", prefix, name, rest);
+            if let Some(image) = container.get("image") {
+                facts.insert(field("image"), (DifferenceCategory::Image, image.clone()));
+            }
+            for section in ["requests", "limits"] {
+                let Some(values) = container.pointer(&format!("/resources/{}", section)).and_then(Value::as_object) else {
+                    continue;
+                };
+                for (resource, quantity) in values {
+                    facts.insert(
+                        field(&format!("resources.{}.{}", section, resource)),
+                        (DifferenceCategory::Resources, quantity.clone()),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn facts(kind: &ComparedKind, item: &Value, fingerprinter: &Fingerprinter) -> Facts {
+    let mut facts = Facts::new();
+    if let Some(pod_spec) = kind.pod_spec {
+        if let Some(replicas) = item.pointer("/spec/replicas") {
+            facts.insert("replicas".to_string(), (DifferenceCategory::Replicas, replicas.clone()));
+        }
+        container_facts(&mut facts, item.pointer(pod_spec).unwrap_or(&Value::Null));
+        return facts;
+    }
+    let data = |facts: &mut Facts, section: &str, value: &dyn Fn(&Value) -> Value| {
+        for (key, v) in item.get(section).and_then(Value::as_object).into_iter().flatten() {
+            facts.insert(format!("{}.{}", section, key), (DifferenceCategory::Data, value(v)));
+        }
+    };
+    if kind.kind == "Secret" {
+        if let Some(secret_type) = item.get("type") {
+            facts.insert("type".to_string(), (DifferenceCategory::Data, secret_type.clone()));
+        }
+        data(&mut facts, "data", &|v| {
+            let encoded = v.as_str().unwrap_or_default();
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .unwrap_or_else(|_| encoded.as_bytes().to_vec());
+            fingerprinter.fingerprint(&decoded)
+        });
+    } else {
+        data(&mut facts, "data", &Value::clone);
+        data(&mut facts, "binaryData", &|v| {
+            fingerprinter.fingerprint(v.as_str().unwrap_or_default().as_bytes())
+        });
+    }
+    facts
+}
+
+/// Compares the objects of `kind` listed from each cluster, matched by name.
+/// Secret values only ever appear as fingerprints.
+pub fn compare(
+    kind: &ComparedKind,
+    namespace: &str,
+    clusters: Vec<(ComparedCluster, Vec<Value>)>,
+) -> ComparisonReport {
+    let fingerprinter = Fingerprinter::new();
+    let mut by_name: BTreeMap<String, BTreeMap<String, Facts>> = BTreeMap::new();
+    for (cluster, items) in &clusters {
+        for item in items {
+            let name = str_at(item, "/metadata/name").unwrap_or_default();
+            by_name
+                .entry(name)
+                .or_default()
+                .insert(cluster.name.clone(), facts(kind, item, &fingerprinter));
+        }
+    }
+
+    let mut summary = ComparisonSummary::default();
+    let mut resources = Vec::new();
+    for (name, present) in by_name {
+        let missing_in: Vec<String> = clusters
+            .iter()
+            .map(|(c, _)| c.name.clone())
+            .filter(|c| !present.contains_key(c))
+            .collect();
+
+        let fields: BTreeSet<(&DifferenceCategory, &String)> = present
+            .values()
+            .flat_map(|facts| facts.iter().map(|(field, (category, _))| (category, field)))
+            .collect();
+        let mut differences = Vec::new();
+        for (category, field) in fields {
+            let values: BTreeMap<String, Option<Value>> = present
+                .iter()
+                .map(|(cluster, facts)| (cluster.clone(), facts.get(field).map(|(_, v)| v.clone())))
+                .collect();
+            let first = values.values().next();
+            if values.values().any(|v| Some(v) != first) {
+                differences.push(FieldDifference {
+                    category: *category,
+                    field: field.clone(),
+                    values,
+                });
+            }
+        }
+
+        let status = if !missing_in.is_empty() {
+            summary.missing += 1;
+            ComparisonStatus::Missing
+        } else if !differences.is_empty() {
+            summary.different += 1;
+            ComparisonStatus::Different
+        } else {
+            summary.identical += 1;
+            ComparisonStatus::Identical
+        };
+        resources.push(ResourceComparison {
+            name,
+            status,
+            missing_in,
+            differences,
+        });
+    }
+    summary.resources = resources.len();
+
+    ComparisonReport {
+        kind: kind.kind.to_string(),
+        namespace: namespace.to_string(),
+        clusters: clusters.into_iter().map(|(c, _)| c).collect(),
+        summary,
+        resources,
+    }
+}
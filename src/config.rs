@@ -17,6 +17,175 @@ pub struct Config {
     pub adm_password: Option<String>,
     pub keycloak_admin_user: Option<String>,
     pub keycloak_admin_password: Option<String>,
+    pub user_import_concurrency: usize,
+    pub password_policy: PasswordPolicy,
+    pub database: DatabaseConfig,
+    pub credentials: CredentialConfig,
+    pub cluster_probe: ClusterProbeConfig,
+    pub inventory: InventoryConfig,
+    pub snapshot: SnapshotConfig,
+    pub proxy: ProxyConfig,
+    pub exec: ExecConfig,
+    pub recording: RecordingConfig,
+    pub vulnerability: VulnerabilityConfig,
+}
+
+/// Offline vulnerability matching of image SBOMs against imported feeds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VulnerabilityConfig {
+    /// Largest accepted feed or SBOM upload
+    pub max_upload_mb: usize,
+}
+
+/// Recording of exec sessions as asciicast v2 files.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingConfig {
+    pub enabled: bool,
+    /// `local` or `s3`
+    pub storage: String,
+    /// Where casts are kept with `local` storage, and where casts of running
+    /// sessions are written with either storage
+    pub directory: String,
+    /// Identifies this server among replicas; its spool files are only
+    /// recovered by an instance with the same id
+    pub instance_id: String,
+    pub s3: S3Config,
+    /// Recordings older than this are deleted; 0 keeps them forever
+    pub retention_days: u32,
+    pub purge_interval_secs: u64,
+}
+
+/// An S3-compatible bucket (AWS, MinIO, Ceph, ...).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct S3Config {
+    /// e.g. `https://minio.example.com`; AWS's regional endpoint when unset
+    pub endpoint: Option<String>,
+    pub bucket: Option<String>,
+    pub region: String,
+    #[serde(skip_serializing)]
+    pub access_key: Option<String>,
+    #[serde(skip_serializing)]
+    pub secret_key: Option<String>,
+    /// Prepended to every object key
+    pub prefix: String,
+    /// `https://endpoint/bucket/key` instead of `https://bucket.endpoint/key`
+    pub path_style: bool,
+}
+
+/// Interactive exec sessions into pods.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecConfig {
+    /// A session without input or output for this long is closed
+    pub idle_timeout_secs: u64,
+    /// Hard limit on a session's length
+    pub max_session_secs: u64,
+}
+
+/// Kubernetes API proxy: authorization policy and upstream clients.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    /// YAML policy mapping roles to allowed verbs, resources and namespaces;
+    /// the built-in policy is used when unset
+    pub policy_file: Option<String>,
+    pub connect_timeout_secs: u64,
+    /// How long an upstream client (and its OIDC token) is reused
+    pub client_cache_ttl_secs: u64,
+}
+
+/// Background inventory collection from active clusters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    /// How many clusters are collected at the same time
+    pub concurrency: usize,
+    /// `limit` for Kubernetes list requests
+    pub page_size: usize,
+}
+
+/// Periodic snapshots of the collected inventory.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    /// Snapshots older than this are deleted; 0 keeps them forever
+    pub retention_days: u32,
+}
+
+/// Background health prober; status changes need several probes in a row.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterProbeConfig {
+    pub enabled: bool,
+    pub interval_secs: u64,
+    pub timeout_secs: u64,
+    /// How many clusters are probed at the same time
+    pub concurrency: usize,
+    /// Consecutive failures before a healthy cluster leaves `active`
+    pub failure_threshold: u32,
+    /// Consecutive successes before a failing cluster returns to `active`
+    pub success_threshold: u32,
+}
+
+/// Master keys for cluster credential encryption (base64-encoded 32 bytes).
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CredentialConfig {
+    #[serde(skip_serializing)]
+    pub master_key: Option<String>,
+    /// File holding the master key; used when `master_key` is not set
+    pub master_key_file: Option<String>,
+    /// Keys that may still wrap stored credentials until they are rotated
+    #[serde(skip_serializing)]
+    pub previous_master_keys: Vec<String>,
+}
+
+impl std::fmt::Debug for CredentialConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialConfig")
+            .field("master_key", &self.master_key.as_ref().map(|_| "[REDACTED]"))
+            .field("master_key_file", &self.master_key_file)
+            .field("previous_master_keys", &self.previous_master_keys.len())
+            .finish()
+    }
+}
+
+/// Connection pool settings for `DATABASE_URL`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub acquire_timeout_secs: u64,
+    pub idle_timeout_secs: u64,
+    pub max_lifetime_secs: u64,
+    /// How long startup keeps retrying while Postgres is not reachable yet
+    pub connect_wait_secs: u64,
+    /// Apply pending migrations on startup; otherwise run `kubeatlas-backend migrate`
+    pub auto_migrate: bool,
+}
+
+/// Server-side password rules checked before a password is sent to Keycloak.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+fn env_bool(name: &str, default: bool) -> bool {
+    match env::var(name).ok().as_deref().map(str::trim) {
+        Some("true") | Some("1") | Some("yes") => true,
+        Some("false") | Some("0") | Some("no") => false,
+        _ => default,
+    }
 }
 
 impl Config {
@@ -53,6 +222,89 @@ impl Config {
             adm_password: env::var("ADM_PASSWORD").ok(),
             keycloak_admin_user: env::var("KEYCLOAK_ADMIN_USER").ok(),
             keycloak_admin_password: env::var("KEYCLOAK_ADMIN_PASSWORD").ok(),
+            user_import_concurrency: env_parse("USER_IMPORT_CONCURRENCY", 4usize).max(1),
+            password_policy: PasswordPolicy {
+                min_length: env_parse("PASSWORD_MIN_LENGTH", 8),
+                require_uppercase: env_bool("PASSWORD_REQUIRE_UPPERCASE", true),
+                require_lowercase: env_bool("PASSWORD_REQUIRE_LOWERCASE", true),
+                require_digit: env_bool("PASSWORD_REQUIRE_DIGIT", true),
+                require_special: env_bool("PASSWORD_REQUIRE_SPECIAL", false),
+            },
+            database: DatabaseConfig {
+                max_connections: env_parse("DATABASE_MAX_CONNECTIONS", 10),
+                min_connections: env_parse("DATABASE_MIN_CONNECTIONS", 0),
+                acquire_timeout_secs: env_parse("DATABASE_ACQUIRE_TIMEOUT_SECS", 5),
+                idle_timeout_secs: env_parse("DATABASE_IDLE_TIMEOUT_SECS", 600),
+                max_lifetime_secs: env_parse("DATABASE_MAX_LIFETIME_SECS", 1800),
+                connect_wait_secs: env_parse("DATABASE_CONNECT_WAIT_SECS", 60),
+                auto_migrate: env_bool("DATABASE_AUTO_MIGRATE", true),
+            },
+            cluster_probe: ClusterProbeConfig {
+                enabled: env_bool("CLUSTER_PROBE_ENABLED", true),
+                interval_secs: env_parse("CLUSTER_PROBE_INTERVAL_SECS", 30u64).max(1),
+                timeout_secs: env_parse("CLUSTER_PROBE_TIMEOUT_SECS", 10u64).max(1),
+                concurrency: env_parse("CLUSTER_PROBE_CONCURRENCY", 8usize).max(1),
+                failure_threshold: env_parse("CLUSTER_PROBE_FAILURE_THRESHOLD", 3u32).max(1),
+                success_threshold: env_parse("CLUSTER_PROBE_SUCCESS_THRESHOLD", 2u32).max(1),
+            },
+            inventory: InventoryConfig {
+                enabled: env_bool("INVENTORY_ENABLED", true),
+                interval_secs: env_parse("INVENTORY_INTERVAL_SECS", 300u64).max(1),
+                timeout_secs: env_parse("INVENTORY_TIMEOUT_SECS", 30u64).max(1),
+                concurrency: env_parse("INVENTORY_CONCURRENCY", 2usize).max(1),
+                page_size: env_parse("INVENTORY_PAGE_SIZE", 500usize).max(1),
+            },
+            snapshot: SnapshotConfig {
+                enabled: env_bool("SNAPSHOT_ENABLED", true),
+                interval_secs: env_parse("SNAPSHOT_INTERVAL_SECS", 3600u64).max(1),
+                retention_days: env_parse("SNAPSHOT_RETENTION_DAYS", 30u32),
+            },
+            proxy: ProxyConfig {
+                policy_file: env::var("ACCESS_POLICY_FILE").ok().filter(|v| !v.trim().is_empty()),
+                connect_timeout_secs: env_parse("PROXY_CONNECT_TIMEOUT_SECS", 10u64).max(1),
+                client_cache_ttl_secs: env_parse("PROXY_CLIENT_CACHE_TTL_SECS", 300u64),
+            },
+            exec: ExecConfig {
+                idle_timeout_secs: env_parse("EXEC_IDLE_TIMEOUT_SECS", 900u64).max(1),
+                max_session_secs: env_parse("EXEC_MAX_SESSION_SECS", 14400u64).max(1),
+            },
+            recording: RecordingConfig {
+                enabled: env_bool("RECORDING_ENABLED", true),
+                storage: env::var("RECORDING_STORAGE")
+                    .map(|v| v.trim().to_ascii_lowercase())
+                    .unwrap_or_else(|_| "local".to_string()),
+                directory: env::var("RECORDING_DIR").unwrap_or_else(|_| "./recordings".to_string()),
+                instance_id: env::var("RECORDING_INSTANCE_ID")
+                    .or_else(|_| env::var("HOSTNAME"))
+                    .ok()
+                    .filter(|v| !v.trim().is_empty())
+                    .unwrap_or_else(|| "kubeatlas".to_string()),
+                s3: S3Config {
+                    endpoint: env::var("RECORDING_S3_ENDPOINT").ok().filter(|v| !v.trim().is_empty()),
+                    bucket: env::var("RECORDING_S3_BUCKET").ok().filter(|v| !v.trim().is_empty()),
+                    region: env::var("RECORDING_S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                    access_key: env::var("RECORDING_S3_ACCESS_KEY").ok().filter(|v| !v.trim().is_empty()),
+                    secret_key: env::var("RECORDING_S3_SECRET_KEY").ok().filter(|v| !v.trim().is_empty()),
+                    prefix: env::var("RECORDING_S3_PREFIX").unwrap_or_default(),
+                    path_style: env_bool("RECORDING_S3_PATH_STYLE", true),
+                },
+                retention_days: env_parse("RECORDING_RETENTION_DAYS", 90u32),
+                purge_interval_secs: env_parse("RECORDING_PURGE_INTERVAL_SECS", 3600u64).max(1),
+            },
+            vulnerability: VulnerabilityConfig {
+                max_upload_mb: env_parse("VULNERABILITY_UPLOAD_MAX_MB", 256usize).max(1),
+            },
+            credentials: CredentialConfig {
+                master_key: env::var("CREDENTIAL_MASTER_KEY").ok().filter(|v| !v.trim().is_empty()),
+                master_key_file: env::var("CREDENTIAL_MASTER_KEY_FILE").ok().filter(|v| !v.trim().is_empty()),
+                previous_master_keys: env::var("CREDENTIAL_PREVIOUS_MASTER_KEYS")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|v| !v.is_empty())
+                    .map(str::to_string)
+                    .collect(),
+            },
         };
 
         Ok(config)
@@ -66,6 +318,10 @@ impl Config {
         format!("{}/realms/{}/protocol/openid-connect/token", self.keycloak_url, self.keycloak_realm)
     }
 
+    pub fn keycloak_logout_url(&self) -> String {
+        format!("{}/realms/{}/protocol/openid-connect/logout", self.keycloak_url, self.keycloak_realm)
+    }
+
     pub fn keycloak_userinfo_url(&self) -> String {
         format!("{}/realms/{}/protocol/openid-connect/userinfo", self.keycloak_url, self.keycloak_realm)
     }
@@ -0,0 +1,137 @@
+use anyhow::{Context, Result};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::crypto::Keyring;
+use crate::kube_client::KubeClient;
+use crate::models::{Cluster, ClusterAuth, ClusterCredential, ClusterCredentialInfo, RotateCredentialsResponse};
+use crate::repositories::{CredentialMetadata, CredentialRepository};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CredentialError {
+    #[error("Credential storage is not configured; set CREDENTIAL_MASTER_KEY or CREDENTIAL_MASTER_KEY_FILE")]
+    NotConfigured,
+}
+
+/// Encrypts cluster credentials on the way into storage and decrypts them for
+/// internal use. Nothing here returns secret material to API callers.
+#[derive(Clone)]
+pub struct CredentialVault {
+    keyring: Option<Arc<Keyring>>,
+    repo: CredentialRepository,
+    /// Held while an OIDC refresh token is exchanged and the result stored,
+    /// so a rotated token is never used twice
+    refresh: Arc<Mutex<()>>,
+}
+
+impl CredentialVault {
+    pub fn new(keyring: Option<Keyring>, repo: CredentialRepository) -> Self {
+        Self {
+            keyring: keyring.map(Arc::new),
+            repo,
+            refresh: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.keyring.is_some()
+    }
+
+    fn keyring(&self) -> Result<&Keyring> {
+        Ok(self.keyring.as_deref().ok_or(CredentialError::NotConfigured)?)
+    }
+
+    pub async fn store(
+        &self,
+        cluster_id: Uuid,
+        credential: &ClusterCredential,
+    ) -> Result<ClusterCredentialInfo> {
+        let keyring = self.keyring()?;
+        let plaintext = serde_json::to_vec(credential)?;
+        let sealed = keyring.seal(&plaintext, cluster_id.as_bytes())?;
+        let metadata = CredentialMetadata {
+            auth_method: credential.auth.method(),
+            has_ca_bundle: credential.ca_bundle_pem.is_some(),
+            insecure_skip_tls_verify: credential.insecure_skip_tls_verify,
+        };
+        self.repo.upsert(cluster_id, metadata, &sealed).await
+    }
+
+    /// Decrypted credential for talking to the cluster; `None` if none is stored.
+    pub async fn load(&self, cluster_id: Uuid) -> Result<Option<ClusterCredential>> {
+        let Some(sealed) = self.repo.find_sealed(cluster_id).await? else {
+            return Ok(None);
+        };
+        let plaintext = self.keyring()?.open(&sealed, cluster_id.as_bytes())?;
+        Ok(Some(serde_json::from_slice(&plaintext)?))
+    }
+
+    /// Client for the cluster with its stored credential, see `KubeClient::connect`.
+    /// A refreshed OIDC credential is stored again right away.
+    pub async fn client(
+        &self,
+        cluster: &Cluster,
+        connect_timeout: Duration,
+        timeout: Option<Duration>,
+    ) -> Result<KubeClient> {
+        let mut credential = self.load(cluster.id).await.context("credential unavailable")?;
+        let refreshes = matches!(
+            &credential,
+            Some(ClusterCredential { auth: ClusterAuth::Oidc { refresh_token: Some(_), .. }, .. })
+        );
+        let _guard = if refreshes {
+            let guard = self.refresh.lock().await;
+            // Another caller may have rotated the token while we waited
+            credential = self.load(cluster.id).await.context("credential unavailable")?;
+            Some(guard)
+        } else {
+            None
+        };
+        let (client, refreshed) =
+            KubeClient::connect(&cluster.endpoint, credential.as_ref(), connect_timeout, timeout)
+                .await
+                .context("client setup failed")?;
+        if let Some(refreshed) = refreshed {
+            self.store(cluster.id, &refreshed)
+                .await
+                .context("failed to store refreshed OIDC credential")?;
+        }
+        Ok(client)
+    }
+
+    pub async fn info(&self, cluster_id: Uuid) -> Result<Option<ClusterCredentialInfo>> {
+        self.repo.find_info(cluster_id).await
+    }
+
+    pub async fn remove(&self, cluster_id: Uuid) -> Result<()> {
+        self.repo.delete(cluster_id).await
+    }
+
+    /// Re-encrypts every stored credential with a fresh data key under the
+    /// current master key, after which previous keys can be retired.
+    pub async fn rotate(&self) -> Result<RotateCredentialsResponse> {
+        let keyring = self.keyring()?;
+        let rotated = self
+            .repo
+            .reseal_all(|cluster_id, sealed| {
+                let aad = cluster_id.as_bytes();
+                let plaintext = keyring
+                    .open(sealed, aad)
+                    .with_context(|| format!("credential of cluster '{}'", cluster_id))?;
+                keyring.seal(&plaintext, aad)
+            })
+            .await?;
+        info!(
+            "Rotated {} cluster credentials to master key {}",
+            rotated,
+            keyring.current_key_id()
+        );
+        Ok(RotateCredentialsResponse {
+            key_id: keyring.current_key_id().to_string(),
+            rotated,
+        })
+    }
+}
@@ -0,0 +1,237 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::config::CredentialConfig;
+
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+/// A key-encryption key; its id is derived from the key so it can be matched on rotation.
+struct MasterKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MasterKey")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MasterKey {
+    fn from_base64(value: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(value.trim())
+            .map_err(|_| anyhow!("master key is not valid base64"))?;
+        if bytes.len() != KEY_LEN {
+            return Err(anyhow!(
+                "master key must be {} bytes, got {}",
+                KEY_LEN,
+                bytes.len()
+            ));
+        }
+        let digest = Sha256::digest(&bytes);
+        let id = digest[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes));
+        Ok(Self { id, cipher })
+    }
+}
+
+/// Output of envelope encryption: a fresh data key encrypts the payload and
+/// the master key encrypts the data key. Both blobs are `nonce || ciphertext`.
+#[derive(Clone)]
+pub struct SealedSecret {
+    pub key_id: String,
+    pub wrapped_key: Vec<u8>,
+    pub ciphertext: Vec<u8>,
+}
+
+impl std::fmt::Debug for SealedSecret {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SealedSecret")
+            .field("key_id", &self.key_id)
+            .finish_non_exhaustive()
+    }
+}
+
+fn encrypt(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| anyhow!("encryption failed"))?;
+    let mut out = nonce.to_vec();
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn decrypt(cipher: &Aes256Gcm, blob: &[u8], aad: &[u8]) -> Result<Vec<u8>> {
+    if blob.len() < NONCE_LEN {
+        return Err(anyhow!("encrypted value is truncated"));
+    }
+    let (nonce, ciphertext) = blob.split_at(NONCE_LEN);
+    cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map_err(|_| anyhow!("decryption failed: wrong key or tampered value"))
+}
+
+/// The current master key plus older keys kept around to read until rotation.
+#[derive(Debug)]
+pub struct Keyring {
+    current: MasterKey,
+    previous: Vec<MasterKey>,
+}
+
+impl Keyring {
+    /// Returns `None` when no master key is configured.
+    pub fn from_config(config: &CredentialConfig) -> Result<Option<Self>> {
+        let current = match (&config.master_key, &config.master_key_file) {
+            (Some(key), _) => key.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .with_context(|| format!("failed to read master key file '{}'", path))?,
+            (None, None) => return Ok(None),
+        };
+        let current = MasterKey::from_base64(&current).context("CREDENTIAL_MASTER_KEY")?;
+        let previous = config
+            .previous_master_keys
+            .iter()
+            .map(|key| MasterKey::from_base64(key).context("CREDENTIAL_PREVIOUS_MASTER_KEYS"))
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|key| key.id != current.id)
+            .collect();
+        Ok(Some(Self { current, previous }))
+    }
+
+    pub fn current_key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    fn key(&self, id: &str) -> Option<&MasterKey> {
+        std::iter::once(&self.current)
+            .chain(self.previous.iter())
+            .find(|key| key.id == id)
+    }
+
+    /// Encrypts under the current master key. `aad` binds the value to its owner
+    /// so a ciphertext copied to another row does not decrypt.
+    pub fn seal(&self, plaintext: &[u8], aad: &[u8]) -> Result<SealedSecret> {
+        let data_key = Aes256Gcm::generate_key(&mut OsRng);
+        let data_cipher = Aes256Gcm::new(&data_key);
+        Ok(SealedSecret {
+            key_id: self.current.id.clone(),
+            wrapped_key: encrypt(&self.current.cipher, data_key.as_slice(), aad)?,
+            ciphertext: encrypt(&data_cipher, plaintext, aad)?,
+        })
+    }
+
+    pub fn open(&self, sealed: &SealedSecret, aad: &[u8]) -> Result<Vec<u8>> {
+        let master = self
+            .key(&sealed.key_id)
+            .ok_or_else(|| anyhow!("master key '{}' is not configured", sealed.key_id))?;
+        let data_key = decrypt(&master.cipher, &sealed.wrapped_key, aad)?;
+        if data_key.len() != KEY_LEN {
+            return Err(anyhow!("unwrapped data key has the wrong length"));
+        }
+        let data_cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&data_key));
+        decrypt(&data_cipher, &sealed.ciphertext, aad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(byte: u8) -> String {
+        BASE64.encode([byte; KEY_LEN])
+    }
+
+    fn keyring(current: u8, previous: &[u8]) -> Keyring {
+        let config = CredentialConfig {
+            master_key: Some(key(current)),
+            master_key_file: None,
+            previous_master_keys: previous.iter().map(|b| key(*b)).collect(),
+        };
+        Keyring::from_config(&config).unwrap().unwrap()
+    }
+
+    #[test]
+    fn seal_and_open_round_trip() {
+        let keyring = keyring(1, &[]);
+        let sealed = keyring.seal(b"secret token", b"cluster-a").unwrap();
+        assert_eq!(sealed.key_id, keyring.current_key_id());
+        assert_ne!(sealed.ciphertext, b"secret token");
+        assert_eq!(keyring.open(&sealed, b"cluster-a").unwrap(), b"secret token");
+    }
+
+    #[test]
+    fn seal_uses_fresh_nonces_and_data_keys() {
+        let keyring = keyring(1, &[]);
+        let a = keyring.seal(b"secret", b"aad").unwrap();
+        let b = keyring.seal(b"secret", b"aad").unwrap();
+        assert_ne!(a.wrapped_key, b.wrapped_key);
+        assert_ne!(a.ciphertext, b.ciphertext);
+    }
+
+    #[test]
+    fn open_rejects_tampered_ciphertext_and_wrapped_key() {
+        let keyring = keyring(1, &[]);
+        let sealed = keyring.seal(b"secret", b"aad").unwrap();
+
+        let mut tampered = sealed.clone();
+        let last = tampered.ciphertext.len() - 1;
+        tampered.ciphertext[last] ^= 1;
+        assert!(keyring.open(&tampered, b"aad").is_err());
+
+        let mut tampered = sealed.clone();
+        tampered.wrapped_key[NONCE_LEN] ^= 1;
+        assert!(keyring.open(&tampered, b"aad").is_err());
+
+        let mut truncated = sealed;
+        truncated.ciphertext.truncate(NONCE_LEN - 1);
+        assert!(keyring.open(&truncated, b"aad").is_err());
+    }
+
+    #[test]
+    fn open_rejects_other_owner() {
+        let keyring = keyring(1, &[]);
+        let sealed = keyring.seal(b"secret", b"cluster-a").unwrap();
+        assert!(keyring.open(&sealed, b"cluster-b").is_err());
+    }
+
+    #[test]
+    fn previous_keys_still_open_until_dropped() {
+        let old = keyring(1, &[]);
+        let sealed = old.seal(b"secret", b"aad").unwrap();
+
+        let rotated = keyring(2, &[1]);
+        assert_ne!(rotated.current_key_id(), sealed.key_id);
+        assert_eq!(rotated.open(&sealed, b"aad").unwrap(), b"secret");
+
+        let dropped = keyring(2, &[]);
+        assert!(dropped.open(&sealed, b"aad").is_err());
+    }
+
+    #[test]
+    fn from_config_validates_keys() {
+        let config = CredentialConfig {
+            master_key: Some(BASE64.encode([0u8; 16])),
+            master_key_file: None,
+            previous_master_keys: Vec::new(),
+        };
+        assert!(Keyring::from_config(&config).is_err());
+
+        let config = CredentialConfig {
+            master_key: None,
+            master_key_file: None,
+            previous_master_keys: Vec::new(),
+        };
+        assert!(Keyring::from_config(&config).unwrap().is_none());
+    }
+}
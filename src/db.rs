@@ -0,0 +1,48 @@
+use anyhow::{anyhow, Result};
+use sqlx::{postgres::PgPoolOptions, PgPool};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::config::Config;
+
+/// Builds the pool from `Config::database`, retrying until Postgres accepts
+/// connections or `connect_wait_secs` runs out.
+pub async fn connect(config: &Config) -> Result<PgPool> {
+    let db = &config.database;
+    let options = PgPoolOptions::new()
+        .max_connections(db.max_connections)
+        .min_connections(db.min_connections)
+        .acquire_timeout(Duration::from_secs(db.acquire_timeout_secs))
+        .idle_timeout(Some(Duration::from_secs(db.idle_timeout_secs)))
+        .max_lifetime(Some(Duration::from_secs(db.max_lifetime_secs)));
+
+    let start = Instant::now();
+    loop {
+        match options.clone().connect(&config.database_url).await {
+            Ok(pool) => return Ok(pool),
+            Err(e) if start.elapsed() < Duration::from_secs(db.connect_wait_secs) => {
+                warn!("Database not ready yet: {}", e);
+                tokio::time::sleep(Duration::from_secs(2)).await;
+            }
+            Err(e) => {
+                return Err(anyhow!(
+                    "Database not reachable after {}s: {}",
+                    db.connect_wait_secs,
+                    e
+                ))
+            }
+        }
+    }
+}
+
+/// Applies the migrations embedded from `./migrations` at build time.
+pub async fn run_migrations(pool: &PgPool) -> Result<()> {
+    sqlx::migrate!("./migrations").run(pool).await?;
+    info!("Database migrations applied");
+    Ok(())
+}
+
+pub async fn ping(pool: &PgPool) -> Result<()> {
+    sqlx::query("SELECT 1").execute(pool).await?;
+    Ok(())
+}
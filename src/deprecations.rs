@@ -0,0 +1,423 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::inventory_collector::{array_at, str_at};
+use crate::models::{
+    AppliedApiVersion, DeprecatedApi, DeprecationFinding, DeprecationStatus, DeprecationSummary, FindingOrigin,
+    InventoryApiVersions,
+};
+
+const LAST_APPLIED: &str = "kubectl.kubernetes.io/last-applied-configuration";
+
+const fn api(
+    api_version: &'static str,
+    kind: &'static str,
+    deprecated_in: &'static str,
+    removed_in: Option<&'static str>,
+    replacement: Option<&'static str>,
+) -> DeprecatedApi {
+    DeprecatedApi { api_version, kind, deprecated_in, removed_in, replacement }
+}
+
+/// Deprecated and removed API versions of GA releases, from the Kubernetes
+/// deprecated API migration guide. Alpha APIs are left out.
+pub static DEPRECATED_APIS: [DeprecatedApi; 50] = [
+    // 1.16
+    api("extensions/v1beta1", "DaemonSet", "1.9", Some("1.16"), Some("apps/v1")),
+    api("extensions/v1beta1", "Deployment", "1.9", Some("1.16"), Some("apps/v1")),
+    api("extensions/v1beta1", "ReplicaSet", "1.9", Some("1.16"), Some("apps/v1")),
+    api("extensions/v1beta1", "NetworkPolicy", "1.9", Some("1.16"), Some("networking.k8s.io/v1")),
+    api("extensions/v1beta1", "PodSecurityPolicy", "1.10", Some("1.16"), Some("policy/v1beta1")),
+    api("apps/v1beta1", "Deployment", "1.9", Some("1.16"), Some("apps/v1")),
+    api("apps/v1beta1", "StatefulSet", "1.9", Some("1.16"), Some("apps/v1")),
+    api("apps/v1beta2", "DaemonSet", "1.9", Some("1.16"), Some("apps/v1")),
+    api("apps/v1beta2", "Deployment", "1.9", Some("1.16"), Some("apps/v1")),
+    api("apps/v1beta2", "ReplicaSet", "1.9", Some("1.16"), Some("apps/v1")),
+    api("apps/v1beta2", "StatefulSet", "1.9", Some("1.16"), Some("apps/v1")),
+    // 1.22
+    api("admissionregistration.k8s.io/v1beta1", "MutatingWebhookConfiguration", "1.16", Some("1.22"), Some("admissionregistration.k8s.io/v1")),
+    api("admissionregistration.k8s.io/v1beta1", "ValidatingWebhookConfiguration", "1.16", Some("1.22"), Some("admissionregistration.k8s.io/v1")),
+    api("apiextensions.k8s.io/v1beta1", "CustomResourceDefinition", "1.16", Some("1.22"), Some("apiextensions.k8s.io/v1")),
+    api("apiregistration.k8s.io/v1beta1", "APIService", "1.19", Some("1.22"), Some("apiregistration.k8s.io/v1")),
+    api("authentication.k8s.io/v1beta1", "TokenReview", "1.19", Some("1.22"), Some("authentication.k8s.io/v1")),
+    api("authorization.k8s.io/v1beta1", "LocalSubjectAccessReview", "1.19", Some("1.22"), Some("authorization.k8s.io/v1")),
+    api("authorization.k8s.io/v1beta1", "SelfSubjectAccessReview", "1.19", Some("1.22"), Some("authorization.k8s.io/v1")),
+    api("authorization.k8s.io/v1beta1", "SubjectAccessReview", "1.19", Some("1.22"), Some("authorization.k8s.io/v1")),
+    api("certificates.k8s.io/v1beta1", "CertificateSigningRequest", "1.19", Some("1.22"), Some("certificates.k8s.io/v1")),
+    api("coordination.k8s.io/v1beta1", "Lease", "1.19", Some("1.22"), Some("coordination.k8s.io/v1")),
+    api("extensions/v1beta1", "Ingress", "1.14", Some("1.22"), Some("networking.k8s.io/v1")),
+    api("networking.k8s.io/v1beta1", "Ingress", "1.19", Some("1.22"), Some("networking.k8s.io/v1")),
+    api("networking.k8s.io/v1beta1", "IngressClass", "1.19", Some("1.22"), Some("networking.k8s.io/v1")),
+    api("rbac.authorization.k8s.io/v1beta1", "ClusterRole", "1.17", Some("1.22"), Some("rbac.authorization.k8s.io/v1")),
+    api("rbac.authorization.k8s.io/v1beta1", "ClusterRoleBinding", "1.17", Some("1.22"), Some("rbac.authorization.k8s.io/v1")),
+    api("rbac.authorization.k8s.io/v1beta1", "Role", "1.17", Some("1.22"), Some("rbac.authorization.k8s.io/v1")),
+    api("rbac.authorization.k8s.io/v1beta1", "RoleBinding", "1.17", Some("1.22"), Some("rbac.authorization.k8s.io/v1")),
+    api("scheduling.k8s.io/v1beta1", "PriorityClass", "1.14", Some("1.22"), Some("scheduling.k8s.io/v1")),
+    api("storage.k8s.io/v1beta1", "CSIDriver", "1.19", Some("1.22"), Some("storage.k8s.io/v1")),
+    api("storage.k8s.io/v1beta1", "CSINode", "1.17", Some("1.22"), Some("storage.k8s.io/v1")),
+    api("storage.k8s.io/v1beta1", "StorageClass", "1.19", Some("1.22"), Some("storage.k8s.io/v1")),
+    api("storage.k8s.io/v1beta1", "VolumeAttachment", "1.19", Some("1.22"), Some("storage.k8s.io/v1")),
+    // 1.25
+    api("batch/v1beta1", "CronJob", "1.21", Some("1.25"), Some("batch/v1")),
+    api("discovery.k8s.io/v1beta1", "EndpointSlice", "1.21", Some("1.25"), Some("discovery.k8s.io/v1")),
+    api("events.k8s.io/v1beta1", "Event", "1.19", Some("1.25"), Some("events.k8s.io/v1")),
+    api("autoscaling/v2beta1", "HorizontalPodAutoscaler", "1.23", Some("1.25"), Some("autoscaling/v2")),
+    api("policy/v1beta1", "PodDisruptionBudget", "1.21", Some("1.25"), Some("policy/v1")),
+    api("policy/v1beta1", "PodSecurityPolicy", "1.21", Some("1.25"), None),
+    api("node.k8s.io/v1beta1", "RuntimeClass", "1.20", Some("1.25"), Some("node.k8s.io/v1")),
+    // 1.26
+    api("flowcontrol.apiserver.k8s.io/v1beta1", "FlowSchema", "1.23", Some("1.26"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    api("flowcontrol.apiserver.k8s.io/v1beta1", "PriorityLevelConfiguration", "1.23", Some("1.26"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    api("autoscaling/v2beta2", "HorizontalPodAutoscaler", "1.23", Some("1.26"), Some("autoscaling/v2")),
+    // 1.27
+    api("storage.k8s.io/v1beta1", "CSIStorageCapacity", "1.24", Some("1.27"), Some("storage.k8s.io/v1")),
+    // 1.29
+    api("flowcontrol.apiserver.k8s.io/v1beta2", "FlowSchema", "1.26", Some("1.29"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    api("flowcontrol.apiserver.k8s.io/v1beta2", "PriorityLevelConfiguration", "1.26", Some("1.29"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    // 1.32
+    api("flowcontrol.apiserver.k8s.io/v1beta3", "FlowSchema", "1.29", Some("1.32"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    api("flowcontrol.apiserver.k8s.io/v1beta3", "PriorityLevelConfiguration", "1.29", Some("1.32"), Some("flowcontrol.apiserver.k8s.io/v1")),
+    // Deprecated without a removal release
+    api("v1", "ComponentStatus", "1.19", None, None),
+    api("v1", "Endpoints", "1.33", None, Some("discovery.k8s.io/v1")),
+];
+
+/// A Kubernetes minor release.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct KubeVersion {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl KubeVersion {
+    /// Accepts `1.29`, `v1.29.3` and distribution versions such as
+    /// `v1.29.3-gke.1200`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.trim().trim_start_matches('v').split('.');
+        let major = parts.next()?.parse().ok()?;
+        let minor: String = parts.next()?.chars().take_while(|c| c.is_ascii_digit()).collect();
+        Some(Self { major, minor: minor.parse().ok()? })
+    }
+}
+
+impl fmt::Display for KubeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+fn release(value: &str) -> KubeVersion {
+    // The table only holds valid releases
+    KubeVersion::parse(value).unwrap_or(KubeVersion { major: 0, minor: 0 })
+}
+
+fn lookup(api_version: &str, kind: &str) -> Option<&'static DeprecatedApi> {
+    DEPRECATED_APIS.iter().find(|a| a.api_version == api_version && a.kind == kind)
+}
+
+/// APIs removed after `current` and by `target`.
+pub fn removals(current: KubeVersion, target: KubeVersion) -> Vec<DeprecatedApi> {
+    DEPRECATED_APIS
+        .iter()
+        .filter(|a| a.removed_in.map(release).is_some_and(|r| r > current && r <= target))
+        .cloned()
+        .collect()
+}
+
+/// The versions an object records it was written with: kubectl's
+/// last-applied configuration and each field manager's entry.
+pub fn applied_api_versions(item: &Value) -> Vec<AppliedApiVersion> {
+    let mut versions = BTreeSet::new();
+    let last_applied = item
+        .pointer("/metadata/annotations")
+        .and_then(|a| a.get(LAST_APPLIED))
+        .and_then(Value::as_str)
+        .and_then(|a| serde_json::from_str::<Value>(a).ok())
+        .and_then(|a| str_at(&a, "/apiVersion"));
+    if let Some(api_version) = last_applied {
+        versions.insert(AppliedApiVersion { api_version, source: "last-applied-configuration".to_string() });
+    }
+    for entry in array_at(item, "/metadata/managedFields") {
+        if let Some(api_version) = str_at(entry, "/apiVersion") {
+            let source = str_at(entry, "/manager").unwrap_or_else(|| "unknown".to_string());
+            versions.insert(AppliedApiVersion { api_version, source });
+        }
+    }
+    versions.into_iter().collect()
+}
+
+/// Manifest documents: multi-document YAML or JSON, with `List` objects
+/// expanded. Each object is returned with its 1-based document number.
+pub fn parse_manifests(data: &[u8]) -> Result<Vec<(usize, Value)>> {
+    let mut objects = Vec::new();
+    for (i, document) in serde_yaml::Deserializer::from_slice(data).enumerate() {
+        let value = Value::deserialize(document).with_context(|| format!("Invalid YAML in document {}", i + 1))?;
+        match value {
+            Value::Null => {}
+            Value::Object(_) if value.get("items").is_some_and(Value::is_array) => {
+                objects.extend(array_at(&value, "/items").iter().map(|item| (i + 1, item.clone())));
+            }
+            other => objects.push((i + 1, other)),
+        }
+    }
+    Ok(objects)
+}
+
+/// Removed APIs by release, then those only deprecated.
+fn removal_order(finding: &DeprecationFinding) -> (bool, Option<KubeVersion>) {
+    (finding.removed_in.is_none(), finding.removed_in.map(release))
+}
+
+/// Compares objects against the table for an upgrade from `current` to
+/// `target`.
+pub struct Scanner {
+    current: KubeVersion,
+    target: KubeVersion,
+    pub findings: Vec<DeprecationFinding>,
+    pub summary: DeprecationSummary,
+}
+
+/// Where a checked object came from.
+struct Origin<'a> {
+    origin: FindingOrigin,
+    file: Option<&'a str>,
+    document: Option<usize>,
+    recorded_by: Vec<&'a str>,
+}
+
+impl Scanner {
+    pub fn new(current: KubeVersion, target: KubeVersion) -> Self {
+        Self { current, target, findings: Vec::new(), summary: DeprecationSummary::default() }
+    }
+
+    fn check(&mut self, origin: Origin<'_>, api_version: &str, kind: &str, namespace: Option<String>, name: String) {
+        let Some(api) = lookup(api_version, kind) else {
+            return;
+        };
+        let removed_in = api.removed_in.map(release);
+        let status = if removed_in.is_some_and(|r| r <= self.target) {
+            DeprecationStatus::Removed
+        } else if release(api.deprecated_in) <= self.target {
+            DeprecationStatus::Deprecated
+        } else {
+            return;
+        };
+        let blocks_upgrade = removed_in.is_some_and(|r| r > self.current && r <= self.target);
+        match status {
+            DeprecationStatus::Removed => self.summary.removed += 1,
+            DeprecationStatus::Deprecated => self.summary.deprecated += 1,
+        }
+        if blocks_upgrade {
+            self.summary.blocking += 1;
+        }
+        self.findings.push(DeprecationFinding {
+            origin: origin.origin,
+            file: origin.file.map(str::to_string),
+            document: origin.document,
+            kind: kind.to_string(),
+            namespace,
+            name,
+            api_version: api_version.to_string(),
+            recorded_by: origin.recorded_by.into_iter().map(str::to_string).collect(),
+            status,
+            deprecated_in: api.deprecated_in,
+            removed_in: api.removed_in,
+            replacement: api.replacement,
+            blocks_upgrade,
+        });
+    }
+
+    /// Collected objects; `unrecorded` is how many more the cluster has
+    /// without recorded API versions, which cannot be checked.
+    pub fn scan_inventory(&mut self, unrecorded: usize, objects: &[InventoryApiVersions]) {
+        self.summary.objects_scanned += objects.len();
+        self.summary.objects_not_scanned += unrecorded;
+        for object in objects {
+            // One finding per version, however many places record it
+            let mut sources: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+            for applied in object.api_versions.iter() {
+                sources.entry(&applied.api_version).or_default().push(&applied.source);
+            }
+            for (api_version, recorded_by) in sources {
+                let origin = Origin {
+                    origin: FindingOrigin::Cluster,
+                    file: None,
+                    document: None,
+                    recorded_by,
+                };
+                let namespace = (!object.namespace.is_empty()).then(|| object.namespace.clone());
+                self.check(origin, api_version, object.kind.as_str(), namespace, object.name.clone());
+            }
+        }
+    }
+
+    pub fn scan_manifests(&mut self, file: &str, objects: &[(usize, Value)]) {
+        self.summary.manifests_scanned += objects.len();
+        for (document, object) in objects {
+            let (Some(api_version), Some(kind)) = (str_at(object, "/apiVersion"), str_at(object, "/kind")) else {
+                continue;
+            };
+            let origin = Origin {
+                origin: FindingOrigin::Manifest,
+                file: Some(file),
+                document: Some(*document),
+                recorded_by: Vec::new(),
+            };
+            let name = str_at(object, "/metadata/name").unwrap_or_default();
+            self.check(origin, &api_version, &kind, str_at(object, "/metadata/namespace"), name);
+        }
+    }
+
+    /// Findings that block the upgrade first, then by removal release.
+    pub fn finish(mut self) -> (DeprecationSummary, Vec<DeprecationFinding>) {
+        self.findings.sort_by(|a, b| {
+            b.blocks_upgrade
+                .cmp(&a.blocks_upgrade)
+                .then_with(|| removal_order(a).cmp(&removal_order(b)))
+                .then_with(|| a.kind.cmp(&b.kind))
+                .then_with(|| a.namespace.cmp(&b.namespace))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        (self.summary, self.findings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::InventoryKind;
+    use serde_json::json;
+
+    fn version(major: u32, minor: u32) -> KubeVersion {
+        KubeVersion { major, minor }
+    }
+
+    fn applied(api_version: &str, source: &str) -> AppliedApiVersion {
+        AppliedApiVersion { api_version: api_version.to_string(), source: source.to_string() }
+    }
+
+    #[test]
+    fn kube_version_parse() {
+        assert_eq!(KubeVersion::parse("1.29"), Some(version(1, 29)));
+        assert_eq!(KubeVersion::parse(" v1.29.3 "), Some(version(1, 29)));
+        assert_eq!(KubeVersion::parse("v1.27.8-gke.1067004"), Some(version(1, 27)));
+        assert_eq!(KubeVersion::parse("v1.30+k3s1"), Some(version(1, 30)));
+        assert_eq!(KubeVersion::parse("1"), None);
+        assert_eq!(KubeVersion::parse("1.x"), None);
+        assert_eq!(KubeVersion::parse("latest"), None);
+        assert!(version(1, 9) < version(1, 16));
+        assert_eq!(version(1, 29).to_string(), "1.29");
+    }
+
+    #[test]
+    fn deprecated_api_table_is_consistent() {
+        for api in &DEPRECATED_APIS {
+            let deprecated_in = KubeVersion::parse(api.deprecated_in).unwrap();
+            if let Some(removed_in) = api.removed_in {
+                assert!(KubeVersion::parse(removed_in).unwrap() > deprecated_in, "{} {}", api.api_version, api.kind);
+            }
+        }
+        let mut keys: Vec<_> = DEPRECATED_APIS.iter().map(|a| (a.api_version, a.kind)).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), DEPRECATED_APIS.len());
+    }
+
+    #[test]
+    fn check_classifies_by_current_and_target() {
+        let manifests = [
+            (1, json!({"apiVersion": "batch/v1beta1", "kind": "CronJob", "metadata": {"name": "backup", "namespace": "shop"}})),
+            (2, json!({"apiVersion": "extensions/v1beta1", "kind": "Deployment", "metadata": {"name": "old"}})),
+            (3, json!({"apiVersion": "v1", "kind": "ComponentStatus", "metadata": {"name": "scheduler"}})),
+            (4, json!({"apiVersion": "flowcontrol.apiserver.k8s.io/v1beta3", "kind": "FlowSchema", "metadata": {"name": "later"}})),
+            (5, json!({"apiVersion": "batch/v1", "kind": "CronJob", "metadata": {"name": "current"}})),
+            (6, json!({"kind": "CronJob"})),
+        ];
+        let mut scanner = Scanner::new(version(1, 24), version(1, 26));
+        scanner.scan_manifests("app.yaml", &manifests);
+        let (summary, findings) = scanner.finish();
+
+        assert_eq!(summary.manifests_scanned, 6);
+        assert_eq!((summary.removed, summary.deprecated, summary.blocking), (2, 1, 1));
+        let found: Vec<_> = findings.iter().map(|f| (f.name.as_str(), f.status, f.blocks_upgrade)).collect();
+        assert_eq!(
+            found,
+            [
+                ("backup", DeprecationStatus::Removed, true),
+                ("old", DeprecationStatus::Removed, false),
+                ("scheduler", DeprecationStatus::Deprecated, false),
+            ]
+        );
+        let backup = &findings[0];
+        assert_eq!(backup.origin, FindingOrigin::Manifest);
+        assert_eq!(backup.file.as_deref(), Some("app.yaml"));
+        assert_eq!(backup.document, Some(1));
+        assert_eq!(backup.namespace.as_deref(), Some("shop"));
+        assert_eq!(backup.replacement, Some("batch/v1"));
+    }
+
+    #[test]
+    fn scan_inventory_counts_unrecorded_objects_separately() {
+        let objects = [InventoryApiVersions {
+            kind: InventoryKind::CronJob,
+            namespace: "shop".to_string(),
+            name: "backup".to_string(),
+            api_versions: sqlx::types::Json(vec![
+                applied("batch/v1", "kube-controller-manager"),
+                applied("batch/v1beta1", "kubectl-client-side-apply"),
+                applied("batch/v1beta1", "last-applied-configuration"),
+            ]),
+        }];
+        let mut scanner = Scanner::new(version(1, 24), version(1, 25));
+        scanner.scan_inventory(3, &objects);
+        let (summary, findings) = scanner.finish();
+
+        assert_eq!((summary.objects_scanned, summary.objects_not_scanned), (1, 3));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].origin, FindingOrigin::Cluster);
+        assert_eq!(findings[0].recorded_by, ["kubectl-client-side-apply", "last-applied-configuration"]);
+        assert!(findings[0].blocks_upgrade);
+    }
+
+    #[test]
+    fn applied_api_versions_reads_last_applied_and_managed_fields() {
+        let item = json!({
+            "metadata": {
+                "annotations": {LAST_APPLIED: r#"{"apiVersion":"apps/v1beta2","kind":"Deployment"}"#},
+                "managedFields": [
+                    {"manager": "helm", "apiVersion": "apps/v1"},
+                    {"apiVersion": "apps/v1beta2"},
+                    {"manager": "helm", "apiVersion": "apps/v1"}
+                ]
+            }
+        });
+        assert_eq!(
+            applied_api_versions(&item),
+            [
+                applied("apps/v1", "helm"),
+                applied("apps/v1beta2", "last-applied-configuration"),
+                applied("apps/v1beta2", "unknown"),
+            ]
+        );
+        assert!(applied_api_versions(&json!({"metadata": {}})).is_empty());
+    }
+
+    #[test]
+    fn parse_manifests_splits_documents_and_expands_lists() {
+        let yaml = b"apiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: a\n---\n---\n\
+apiVersion: v1\nkind: List\nitems:\n- apiVersion: batch/v1beta1\n  kind: CronJob\n  metadata:\n    name: b\n\
+- apiVersion: policy/v1beta1\n  kind: PodDisruptionBudget\n  metadata:\n    name: c\n";
+        let objects = parse_manifests(yaml).unwrap();
+        let parsed: Vec<_> = objects.iter().map(|(document, o)| (*document, str_at(o, "/metadata/name").unwrap())).collect();
+        assert_eq!(parsed, [(1, "a".to_string()), (3, "b".to_string()), (3, "c".to_string())]);
+
+        let json = br#"{"apiVersion": "v1", "kind": "ConfigMap", "metadata": {"name": "d"}}"#;
+        assert_eq!(parse_manifests(json).unwrap().len(), 1);
+
+        let error = parse_manifests(b"kind: ConfigMap\n---\nkind: [unclosed\n").unwrap_err();
+        assert!(format!("{:#}", error).contains("document 2"), "{:#}", error);
+    }
+}
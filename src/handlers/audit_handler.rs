@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::AuditPage,
+    repositories::AuditFilter,
+    validation::ValidationErrors,
+    AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery {
+    pub cluster_id: Option<Uuid>,
+    pub user: Option<String>,
+    pub verb: Option<String>,
+    pub allowed: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Audited proxy calls, newest first.
+pub async fn list_audit_log(
+    State(state): State<AppState>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        errors.add("limit", format!("must be between 1 and {}", MAX_LIMIT));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if offset < 0 {
+        errors.add("offset", "must not be negative");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid audit query"))?;
+
+    let filter = AuditFilter {
+        cluster_id: query.cluster_id,
+        username: query.user.filter(|u| !u.trim().is_empty()),
+        verb: query.verb.filter(|v| !v.trim().is_empty()),
+        allowed: query.allowed,
+        limit,
+        offset,
+    };
+    let (total, items) = state.repos.audit.list(&filter).await.map_err(repository_error)?;
+    Ok(Json(AuditPage { total, items }))
+}
@@ -0,0 +1,356 @@
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    cluster_import,
+    credentials::CredentialError,
+    impersonation,
+    kube_client::{KubeApiError, KubeClient},
+    kubeconfig,
+    models::{
+        Cluster, ClusterCredential, ClusterCredentialInfo, ClusterImportResponse,
+        ClusterImportSelection, CreateClusterRequest, RotateCredentialsResponse,
+        UpdateClusterRequest,
+    },
+    repositories::{ClusterChanges, RepositoryError},
+    validation::{self, ValidationErrors},
+    AppState,
+};
+
+pub(crate) type ApiError = (StatusCode, Json<Value>);
+
+/// Maps repository failures: not found → 404, unique conflict → 409 with the field,
+/// missing credential master key → 503.
+pub(crate) fn repository_error(e: anyhow::Error) -> ApiError {
+    if let Some(CredentialError::NotConfigured) = e.downcast_ref::<CredentialError>() {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Service Unavailable",
+                "message": e.to_string()
+            })),
+        );
+    }
+    match e.downcast_ref::<RepositoryError>() {
+        Some(RepositoryError::NotFound(message)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Not Found",
+                "message": format!("{} not found", message)
+            })),
+        ),
+        Some(RepositoryError::Conflict { field, .. }) => {
+            ValidationErrors::single(field, "is already taken")
+                .into_error(StatusCode::CONFLICT, &e.to_string())
+        }
+        None => {
+            warn!("Cluster storage error: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": "Internal Server Error",
+                    "message": "Database request failed"
+                })),
+            )
+        }
+    }
+}
+
+/// API server rejections keep their status (unknown pod, container required);
+/// anything else means the cluster could not be reached.
+pub(crate) fn kube_api_error(e: anyhow::Error) -> ApiError {
+    match e.downcast_ref::<KubeApiError>() {
+        Some(api) if api.status.is_client_error() => {
+            let status = StatusCode::from_u16(api.status.as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+            (
+                status,
+                Json(json!({
+                    "error": status.canonical_reason().unwrap_or("Error"),
+                    "message": api.message
+                })),
+            )
+        }
+        _ => {
+            warn!("Cluster request failed: {:#}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": "Bad Gateway",
+                    "message": format!("Cluster request failed: {:#}", e)
+                })),
+            )
+        }
+    }
+}
+
+pub(crate) fn forbidden(message: String) -> ApiError {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": "Forbidden",
+            "message": message
+        })),
+    )
+}
+
+/// A client acting for `user` on `cluster`. Impersonating clusters get the
+/// user's identity and enforce their own RBAC; otherwise every request in
+/// `checks` must be allowed by the access policy.
+pub(crate) async fn authorized_client(
+    state: &AppState,
+    user: &KeycloakUser,
+    cluster: &Cluster,
+    checks: &[KubeRequestInfo],
+) -> Result<KubeClient, ApiError> {
+    let roles = state.auth_service.get_user_roles(user);
+    let impersonation = impersonation::identity(&cluster.impersonation, user, &roles)
+        .map_err(|e| forbidden(e.to_string()))?;
+    if impersonation.is_none() {
+        if let Some(denied) = checks
+            .iter()
+            .find(|info| !state.policy.allows(&roles, &cluster.name, info))
+        {
+            return Err(forbidden(denied.denied_message(&user.preferred_username)));
+        }
+    }
+    let client = state.clients.get(cluster).await.map_err(repository_error)?;
+    Ok(match impersonation {
+        Some(impersonation) => client.impersonating(impersonation),
+        None => client,
+    })
+}
+
+pub async fn list_clusters(State(state): State<AppState>) -> Result<Json<Vec<Cluster>>, ApiError> {
+    let clusters = state.repos.clusters.list().await.map_err(repository_error)?;
+    Ok(Json(clusters))
+}
+
+pub async fn get_cluster(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Cluster>, ApiError> {
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    Ok(Json(cluster))
+}
+
+pub async fn create_cluster(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateClusterRequest>,
+) -> Result<(StatusCode, Json<Cluster>), ApiError> {
+    info!("Admin: register cluster '{}' at {}", payload.name, payload.endpoint);
+    let endpoint = validation::validate_create_cluster(&payload)
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+    if payload.credential.is_some() && !state.credentials.is_configured() {
+        return Err(repository_error(CredentialError::NotConfigured.into()));
+    }
+    let description = payload.description.as_deref().filter(|d| !d.trim().is_empty());
+    let cluster = state
+        .repos
+        .clusters
+        .create(
+            &payload.name,
+            description,
+            &endpoint,
+            &payload.impersonation.clone().unwrap_or_default(),
+        )
+        .await
+        .map_err(repository_error)?;
+    if let Some(credential) = &payload.credential {
+        if let Err(e) = state.credentials.store(cluster.id, credential).await {
+            // Keep registration and credential all-or-nothing
+            if let Err(cleanup) = state.repos.clusters.delete(cluster.id).await {
+                warn!("Failed to remove cluster '{}' after credential error: {}", cluster.id, cleanup);
+            }
+            return Err(repository_error(e));
+        }
+    }
+    Ok((StatusCode::CREATED, Json(cluster)))
+}
+
+pub async fn update_cluster(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<UpdateClusterRequest>,
+) -> Result<Json<Cluster>, ApiError> {
+    info!("Admin: update cluster '{}'", id);
+    let endpoint = validation::validate_update_cluster(&payload)
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+    let changes = ClusterChanges {
+        name: payload.name,
+        // An empty description clears it
+        description: payload
+            .description
+            .map(|d| Some(d).filter(|d| !d.trim().is_empty())),
+        endpoint,
+        status: payload.status,
+        impersonation: payload.impersonation,
+    };
+    let cluster = state
+        .repos
+        .clusters
+        .update(id, changes)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(cluster))
+}
+
+pub async fn delete_cluster(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    info!("Admin: delete cluster '{}'", id);
+    state.repos.clusters.delete(id).await.map_err(repository_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn bad_request(message: String) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Bad Request",
+            "message": message
+        })),
+    )
+}
+
+/// Multipart upload: `kubeconfig` (file) and optional `selection` (JSON array).
+/// Without a selection only the preview is returned.
+pub async fn import_kubeconfig(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<Json<ClusterImportResponse>, ApiError> {
+    let mut data: Option<Vec<u8>> = None;
+    let mut selection: Option<Vec<ClusterImportSelection>> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        match field.name() {
+            Some("kubeconfig") => {
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| bad_request(format!("Failed to read upload: {}", e)))?;
+                data = Some(bytes.to_vec());
+            }
+            Some("selection") => {
+                let text = field
+                    .text()
+                    .await
+                    .map_err(|e| bad_request(format!("Failed to read selection: {}", e)))?;
+                let parsed = serde_json::from_str(&text)
+                    .map_err(|e| bad_request(format!("Invalid selection: {}", e)))?;
+                selection = Some(parsed);
+            }
+            _ => {}
+        }
+    }
+
+    let data = data.ok_or_else(|| bad_request("Missing 'kubeconfig' part".to_string()))?;
+    let contexts = kubeconfig::parse(&data).map_err(|e| bad_request(e.to_string()))?;
+    let existing = state.repos.clusters.list().await.map_err(repository_error)?;
+    let preview = cluster_import::preview(&contexts, &existing);
+
+    let results = match selection {
+        Some(selection) => {
+            info!(
+                "Admin: import {} of {} kubeconfig contexts",
+                selection.len(),
+                contexts.len()
+            );
+            Some(
+                cluster_import::import_selected(
+                    &state.repos.clusters,
+                    &state.credentials,
+                    &contexts,
+                    &existing,
+                    selection,
+                )
+                .await,
+            )
+        }
+        None => None,
+    };
+    Ok(Json(ClusterImportResponse {
+        contexts: preview,
+        results,
+    }))
+}
+
+/// Metadata of the stored credential; secret material is never returned.
+pub async fn get_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ClusterCredentialInfo>, ApiError> {
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let info = state.credentials.info(id).await.map_err(repository_error)?;
+    info.map(Json).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Not Found",
+                "message": format!("Cluster '{}' has no credential", id)
+            })),
+        )
+    })
+}
+
+pub async fn put_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ClusterCredential>,
+) -> Result<Json<ClusterCredentialInfo>, ApiError> {
+    info!("Admin: set credential of cluster '{}' ({:?})", id, payload.auth.method());
+    let mut errors = ValidationErrors::new();
+    validation::validate_cluster_credential(&mut errors, "credential", &payload);
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+    let info = state
+        .credentials
+        .store(id, &payload)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(info))
+}
+
+pub async fn delete_credential(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, ApiError> {
+    info!("Admin: delete credential of cluster '{}'", id);
+    state.credentials.remove(id).await.map_err(repository_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn rotate_credentials(
+    State(state): State<AppState>,
+) -> Result<Json<RotateCredentialsResponse>, ApiError> {
+    info!("Admin: rotate cluster credential encryption");
+    let report = state.credentials.rotate().await.map_err(repository_error)?;
+    Ok(Json(report))
+}
+
+/// Runs a health probe right away instead of waiting for the next round.
+pub async fn probe_cluster(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Cluster>, ApiError> {
+    info!("Admin: probe cluster '{}'", id);
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let cluster = state
+        .prober
+        .probe_cluster(&cluster)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(cluster))
+}
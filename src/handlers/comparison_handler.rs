@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    comparison::{self, ComparedKind, COMPARED_KINDS},
+    handlers::cluster_handler::{authorized_client, kube_api_error, repository_error, ApiError},
+    kube_client::collection_path,
+    models::{Cluster, ComparedCluster, ComparisonReport, ComparisonStatus},
+    validation::{is_dns_label, ValidationErrors},
+    AppState,
+};
+
+const LIST_PAGE_SIZE: usize = 500;
+const MAX_CLUSTERS: usize = 10;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ComparisonQuery {
+    /// Comma separated cluster ids or names, e.g. `staging,prod`
+    pub clusters: Option<String>,
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    /// Leave out resources that are identical everywhere
+    #[serde(default)]
+    pub drift_only: bool,
+}
+
+/// Compares one kind in one namespace across clusters, read live from each.
+/// Needs `list` on the kind in the namespace of every cluster.
+pub async fn compare_clusters(
+    State(state): State<AppState>,
+    Query(query): Query<ComparisonQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Json<ComparisonReport>, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let kind = match query.kind.as_deref().filter(|k| !k.trim().is_empty()) {
+        Some(raw) => {
+            let kind = ComparedKind::parse(raw);
+            if kind.is_none() {
+                errors.add(
+                    "kind",
+                    format!(
+                        "'{}' is not one of {}",
+                        raw.trim(),
+                        COMPARED_KINDS.iter().map(|k| k.kind).collect::<Vec<_>>().join(", ")
+                    ),
+                );
+            }
+            kind
+        }
+        None => {
+            errors.add("kind", "is required");
+            None
+        }
+    };
+    let namespace = query.namespace.map(|n| n.trim().to_string()).filter(|n| !n.is_empty());
+    match namespace.as_deref() {
+        None => errors.add("namespace", "is required"),
+        Some(n) if !is_dns_label(n) => errors.add("namespace", "must be a DNS-1123 label"),
+        Some(_) => {}
+    }
+
+    let all = state.repos.clusters.list().await.map_err(repository_error)?;
+    let mut clusters = Vec::new();
+    for raw in query.clusters.as_deref().unwrap_or_default().split(',').map(str::trim) {
+        if raw.is_empty() {
+            continue;
+        }
+        match all.iter().find(|c| c.id.to_string() == raw || c.name == raw) {
+            Some(cluster) if !clusters.iter().any(|c: &&Cluster| c.id == cluster.id) => clusters.push(cluster),
+            Some(_) => {}
+            None => errors.add("clusters", format!("cluster '{}' not found", raw)),
+        }
+    }
+    if !(2..=MAX_CLUSTERS).contains(&clusters.len()) {
+        errors.add("clusters", format!("must name between 2 and {} clusters", MAX_CLUSTERS));
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid comparison query"))?;
+    let (Some(kind), Some(namespace)) = (kind, namespace) else {
+        unreachable!("kind and namespace are validated above");
+    };
+
+    let path = collection_path(kind.api_group, kind.resource, Some(&namespace)).map_err(kube_api_error)?;
+    let check = KubeRequestInfo::resource_request("list", kind.api_group, kind.resource, None, Some(&namespace));
+    let mut clients = Vec::new();
+    for cluster in &clusters {
+        clients.push(authorized_client(&state, &user, cluster, std::slice::from_ref(&check)).await?);
+    }
+    let lists = futures::future::join_all(clients.iter().map(|client| client.list_all(&path, LIST_PAGE_SIZE))).await;
+
+    let mut listed = Vec::new();
+    for (cluster, result) in clusters.iter().zip(lists) {
+        let items: Vec<Value> = result.map_err(|e| {
+            let (status, Json(mut body)) = kube_api_error(e);
+            let message = body["message"].as_str().unwrap_or_default().to_string();
+            body["message"] = Value::String(format!("Cluster '{}': {}", cluster.name, message));
+            (status, Json(body))
+        })?;
+        listed.push((
+            ComparedCluster {
+                id: cluster.id,
+                name: cluster.name.clone(),
+            },
+            items,
+        ));
+    }
+
+    let mut report = comparison::compare(&kind, &namespace, listed);
+    info!(
+        "{} compared {} in '{}' across {}: {} resources, {} drifted",
+        user.preferred_username,
+        kind.resource,
+        namespace,
+        clusters.iter().map(|c| c.name.as_str()).collect::<Vec<_>>().join(", "),
+        report.summary.resources,
+        report.summary.different + report.summary.missing
+    );
+    if query.drift_only {
+        report.resources.retain(|r| r.status != ComparisonStatus::Identical);
+    }
+    Ok(Json(report))
+}
@@ -0,0 +1,173 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    deprecations::{self, KubeVersion, Scanner, DEPRECATED_APIS},
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::{DeprecatedApi, DeprecationReport},
+    validation::ValidationErrors,
+    AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeprecationQuery {
+    /// Release to upgrade to, e.g. `1.29`
+    pub target: Option<String>,
+    /// Overrides the version reported by the health probe
+    pub current: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DeprecatedApiQuery {
+    /// Only APIs deprecated or removed by this release
+    pub target: Option<String>,
+}
+
+fn bad_request(message: String) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Bad Request",
+            "message": message
+        })),
+    )
+}
+
+/// The built-in table of deprecated and removed API versions.
+pub async fn list_deprecated_apis(
+    Query(query): Query<DeprecatedApiQuery>,
+) -> Result<Json<Vec<DeprecatedApi>>, ApiError> {
+    let target = match query.target.as_deref() {
+        Some(target) => Some(
+            KubeVersion::parse(target).ok_or_else(|| bad_request(format!("Invalid target version '{}'", target)))?,
+        ),
+        None => None,
+    };
+    let apis = DEPRECATED_APIS
+        .iter()
+        .filter(|api| {
+            target.is_none_or(|t| KubeVersion::parse(api.deprecated_in).is_some_and(|d| d <= t))
+        })
+        .cloned()
+        .collect();
+    Ok(Json(apis))
+}
+
+/// Checks the cluster's collected objects, and the uploaded manifests if any,
+/// for an upgrade from the cluster's version to `target`.
+async fn scan(
+    state: &AppState,
+    id: Uuid,
+    query: DeprecationQuery,
+    manifests: Vec<(String, Vec<u8>)>,
+) -> Result<Json<DeprecationReport>, ApiError> {
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let mut errors = ValidationErrors::new();
+    let target = match query.target.as_deref() {
+        None => {
+            errors.add("target", "is required");
+            None
+        }
+        Some(target) => {
+            let parsed = KubeVersion::parse(target);
+            if parsed.is_none() {
+                errors.add("target", "must be a Kubernetes release such as 1.29");
+            }
+            parsed
+        }
+    };
+    let current = match query.current.as_deref() {
+        Some(current) => {
+            let parsed = KubeVersion::parse(current);
+            if parsed.is_none() {
+                errors.add("current", "must be a Kubernetes release such as 1.27");
+            }
+            parsed
+        }
+        None => cluster.health.kubernetes_version.as_deref().and_then(KubeVersion::parse),
+    };
+    if let (Some(current), Some(target)) = (current, target) {
+        if target < current {
+            errors.add("target", format!("must not be older than the current version {}", current));
+        }
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid deprecation query"))?;
+    let Some(current) = current else {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Conflict",
+                "message": "Kubernetes version of the cluster is not known yet; pass 'current'"
+            })),
+        ));
+    };
+    // Validated above
+    let target = target.unwrap_or(current);
+
+    let mut scanner = Scanner::new(current, target);
+    let (unrecorded, objects) = state.repos.inventory.api_versions(id).await.map_err(repository_error)?;
+    scanner.scan_inventory(unrecorded as usize, &objects);
+    for (file, data) in &manifests {
+        let objects = deprecations::parse_manifests(data).map_err(|e| bad_request(format!("{}: {:#}", file, e)))?;
+        scanner.scan_manifests(file, &objects);
+    }
+    let (summary, findings) = scanner.finish();
+    Ok(Json(DeprecationReport {
+        cluster_id: cluster.id,
+        cluster_name: cluster.name,
+        current_version: current.to_string(),
+        target_version: target.to_string(),
+        summary,
+        removals: deprecations::removals(current, target),
+        findings,
+    }))
+}
+
+pub async fn scan_cluster(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeprecationQuery>,
+) -> Result<Json<DeprecationReport>, ApiError> {
+    scan(&state, id, query, Vec::new()).await
+}
+
+/// The same, with manifests uploaded as multipart `file` parts (YAML, several
+/// documents each, or JSON).
+pub async fn scan_cluster_with_manifests(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DeprecationQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<DeprecationReport>, ApiError> {
+    let mut manifests = Vec::new();
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = field
+            .file_name()
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("file-{}", manifests.len() + 1));
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| bad_request(format!("Failed to read upload: {}", e)))?;
+        manifests.push((file_name, data.to_vec()));
+    }
+    if manifests.is_empty() {
+        return Err(bad_request("Missing 'file' part".to_string()));
+    }
+    scan(&state, id, query, manifests).await
+}
@@ -0,0 +1,366 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::{Duration, Instant};
+use tokio_tungstenite::tungstenite;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    config::ExecConfig,
+    handlers::cluster_handler::{authorized_client, kube_api_error, repository_error, ApiError},
+    models::Cluster,
+    recording::{Recorder, SessionEvent, SessionInfo},
+    pod_exec::{self, ClientMessage, ExecOutput, ExecRequest, ExecStream, ServerMessage, Utf8Decoder},
+    repositories::NewAuditEntry,
+    validation::{validate_pod_ref, ValidationErrors},
+    AppState,
+};
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+const DEFAULT_COMMAND: &str = "/bin/sh";
+const MAX_COMMAND_ARGS: usize = 64;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ExecQuery {
+    pub container: Option<String>,
+    /// Defaults to `true`
+    pub tty: Option<bool>,
+    /// Initial terminal size
+    pub cols: Option<u16>,
+    pub rows: Option<u16>,
+}
+
+/// `command` may repeat, one parameter per argument, as in the Kubernetes API.
+fn command(params: &[(String, String)]) -> Vec<String> {
+    let command: Vec<String> = params
+        .iter()
+        .filter(|(key, _)| key == "command")
+        .map(|(_, value)| value.clone())
+        .collect();
+    if command.is_empty() {
+        vec![DEFAULT_COMMAND.to_string()]
+    } else {
+        command
+    }
+}
+
+/// One audit record per session attempt, allowed or not.
+struct ExecAudit<'a> {
+    state: &'a AppState,
+    user: &'a KeycloakUser,
+    cluster: &'a Cluster,
+    namespace: &'a str,
+    pod: &'a str,
+    request: &'a ExecRequest,
+}
+
+impl ExecAudit<'_> {
+    async fn record(&self, allowed: bool, status_code: Option<StatusCode>, error: Option<String>) -> Option<Uuid> {
+        let path = format!(
+            "/api/v1/namespaces/{}/pods/{}/exec?{}",
+            self.namespace,
+            self.pod,
+            self.request.encoded_query()
+        );
+        info!(
+            "Exec audit: {} into {}/{} on cluster '{}' by {} (allowed: {})",
+            self.request.command.join(" "),
+            self.namespace,
+            self.pod,
+            self.cluster.name,
+            self.user.preferred_username,
+            allowed
+        );
+        let entry = NewAuditEntry {
+            user_id: self.user.sub.clone(),
+            username: self.user.preferred_username.clone(),
+            cluster_id: self.cluster.id,
+            cluster_name: self.cluster.name.clone(),
+            verb: "create".to_string(),
+            api_group: String::new(),
+            resource: "pods/exec".to_string(),
+            namespace: Some(self.namespace.to_string()),
+            name: Some(self.pod.to_string()),
+            path,
+            allowed,
+            status_code: status_code.map(|s| s.as_u16() as i32),
+            error,
+        };
+        match self.state.repos.audit.record(&entry).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                warn!("Failed to write exec audit entry: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// Opens an interactive exec session into a pod and bridges it to the
+/// browser's WebSocket. Needs `create` on `pods/exec` (access policy, or
+/// cluster RBAC when impersonating). The session is opened upstream before
+/// the upgrade, so a missing pod or container is an ordinary HTTP error.
+/// When recording is enabled a session that cannot be recorded is refused.
+pub async fn exec(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path((id, namespace, pod)): Path<(Uuid, String, String)>,
+    Query(query): Query<ExecQuery>,
+    Query(params): Query<Vec<(String, String)>>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Response, ApiError> {
+    let request = ExecRequest {
+        container: query.container.clone().filter(|c| !c.trim().is_empty()),
+        command: command(&params),
+        tty: query.tty.unwrap_or(true),
+    };
+    let mut errors = ValidationErrors::new();
+    validate_pod_ref(&mut errors, &namespace, Some(&pod));
+    if request.command.len() > MAX_COMMAND_ARGS {
+        errors.add("command", format!("at most {} arguments", MAX_COMMAND_ARGS));
+    }
+    if request.command[0].trim().is_empty() {
+        errors.add("command", "must not be empty");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid exec request"))?;
+
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let audit = ExecAudit {
+        state: &state,
+        user: &user,
+        cluster: &cluster,
+        namespace: &namespace,
+        pod: &pod,
+        request: &request,
+    };
+    let checks = [KubeRequestInfo::resource_request(
+        "create",
+        "",
+        "pods",
+        Some("exec"),
+        Some(&namespace),
+    )];
+    let client = match authorized_client(&state, &user, &cluster, &checks).await {
+        Ok(client) => client,
+        Err(e) => {
+            if e.0 == StatusCode::FORBIDDEN {
+                audit.record(false, None, None).await;
+            }
+            return Err(e);
+        }
+    };
+    let mut upstream = match pod_exec::connect(&client, &namespace, &pod, &request).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let error = kube_api_error(e);
+            let message = error.1["message"].as_str().map(str::to_string);
+            audit.record(true, Some(error.0), message).await;
+            return Err(error);
+        }
+    };
+    let size = query.cols.zip(query.rows);
+    let recorder = if state.recordings.enabled() {
+        let session = SessionInfo {
+            user_id: user.sub.clone(),
+            username: user.preferred_username.clone(),
+            cluster_id: cluster.id,
+            cluster_name: cluster.name.clone(),
+            namespace: namespace.clone(),
+            pod: pod.clone(),
+            container: request.container.clone(),
+            command: request.command.clone(),
+            size,
+        };
+        match state.recordings.start(&session).await {
+            Ok(recorder) => Some(recorder),
+            Err(e) => {
+                warn!("Failed to start exec recording: {:#}", e);
+                let message = "session recording is unavailable".to_string();
+                audit
+                    .record(true, Some(StatusCode::SERVICE_UNAVAILABLE), Some(message.clone()))
+                    .await;
+                let _ = upstream.close(None).await;
+                return Err((
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    Json(json!({ "error": "Service Unavailable", "message": message })),
+                ));
+            }
+        }
+    } else {
+        None
+    };
+    let audit_id = audit.record(true, Some(StatusCode::SWITCHING_PROTOCOLS), None).await;
+    if let (Some(recorder), Some(audit_id)) = (&recorder, audit_id) {
+        if let Err(e) = recorder.link_audit(audit_id).await {
+            warn!("Failed to link recording {} to audit entry {}: {}", recorder.id(), audit_id, e);
+        }
+    }
+
+    let limits = state.config.exec.clone();
+    let label = format!(
+        "{}/{} on cluster '{}' by {}",
+        namespace, pod, cluster.name, user.preferred_username
+    );
+    Ok(ws
+        .on_upgrade(move |socket| run(socket, upstream, size, recorder, limits, label))
+        .into_response())
+}
+
+async fn send(socket: &mut WebSocket, message: &ServerMessage) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => socket.send(Message::Text(text)).await.is_ok(),
+        Err(e) => {
+            warn!("Failed to encode exec message: {}", e);
+            true
+        }
+    }
+}
+
+/// Forwards browser input to the pod and pod output to the browser until
+/// either side closes, the command exits, or a time limit is hit.
+async fn run(
+    mut socket: WebSocket,
+    mut upstream: ExecStream,
+    size: Option<(u16, u16)>,
+    mut recorder: Option<Recorder>,
+    limits: ExecConfig,
+    label: String,
+) {
+    let started = Instant::now();
+    info!("Exec session into {} started", label);
+    if let Some((cols, rows)) = size {
+        let _ = upstream.send(pod_exec::resize_frame(cols, rows)).await;
+    }
+
+    let idle_timeout = Duration::from_secs(limits.idle_timeout_secs);
+    let idle = tokio::time::sleep(idle_timeout);
+    let deadline = tokio::time::sleep(Duration::from_secs(limits.max_session_secs));
+    tokio::pin!(idle, deadline);
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    let (mut stdout, mut stderr) = (Utf8Decoder::default(), Utf8Decoder::default());
+    let mut exit_code = None;
+
+    let reason = loop {
+        tokio::select! {
+            incoming = socket.recv() => {
+                let (frame, event) = match incoming {
+                    Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                        Ok(ClientMessage::Stdin { data }) => {
+                            (pod_exec::stdin_frame(data.as_bytes()), SessionEvent::Input(data))
+                        }
+                        Ok(ClientMessage::Resize { cols, rows }) => {
+                            (pod_exec::resize_frame(cols, rows), SessionEvent::Resize(cols, rows))
+                        }
+                        Err(e) => {
+                            let message = ServerMessage::Error { message: format!("invalid message: {}", e) };
+                            if !send(&mut socket, &message).await {
+                                break "browser disconnected";
+                            }
+                            continue;
+                        }
+                    },
+                    Some(Ok(Message::Binary(data))) => {
+                        let text = String::from_utf8_lossy(&data).into_owned();
+                        (pod_exec::stdin_frame(&data), SessionEvent::Input(text))
+                    }
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break "browser disconnected",
+                    Some(Ok(_)) => continue,
+                };
+                idle.as_mut().reset(tokio::time::Instant::now() + idle_timeout);
+                if !record(&mut recorder, event).await {
+                    let _ = send(&mut socket, &ServerMessage::Error { message: "session recording failed".to_string() }).await;
+                    break "recording failed";
+                }
+                if upstream.send(frame).await.is_err() {
+                    break "cluster connection lost";
+                }
+            }
+            outgoing = upstream.next() => {
+                let output = match outgoing {
+                    Some(Ok(tungstenite::Message::Close(_))) | None => break "command finished",
+                    Some(Ok(message)) => pod_exec::decode(message),
+                    Some(Err(e)) => {
+                        warn!("Exec session into {} failed: {}", label, e);
+                        let _ = send(&mut socket, &ServerMessage::Error { message: "cluster connection lost".to_string() }).await;
+                        break "cluster connection lost";
+                    }
+                };
+                let message = match output {
+                    Some(ExecOutput::Stdout(data)) => ServerMessage::Stdout { data: stdout.decode(&data) },
+                    Some(ExecOutput::Stderr(data)) => ServerMessage::Stderr { data: stderr.decode(&data) },
+                    Some(ExecOutput::Exit(status)) => {
+                        exit_code = status.code;
+                        ServerMessage::Exit(status)
+                    }
+                    None => continue,
+                };
+                idle.as_mut().reset(tokio::time::Instant::now() + idle_timeout);
+                if let ServerMessage::Stdout { data } | ServerMessage::Stderr { data } = &message {
+                    if !record(&mut recorder, SessionEvent::Output(data.clone())).await {
+                        let _ = send(&mut socket, &ServerMessage::Error { message: "session recording failed".to_string() }).await;
+                        break "recording failed";
+                    }
+                }
+                if !send(&mut socket, &message).await {
+                    break "browser disconnected";
+                }
+            }
+            _ = &mut idle => {
+                let message = format!("session closed after {} seconds without activity", limits.idle_timeout_secs);
+                let _ = send(&mut socket, &ServerMessage::Error { message }).await;
+                break "idle timeout";
+            }
+            _ = &mut deadline => {
+                let message = format!("session closed after the maximum of {} seconds", limits.max_session_secs);
+                let _ = send(&mut socket, &ServerMessage::Error { message }).await;
+                break "maximum session length";
+            }
+            _ = heartbeat.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break "browser disconnected";
+                }
+            }
+        }
+    };
+
+    let _ = upstream.close(None).await;
+    let _ = socket.send(Message::Close(None)).await;
+    info!(
+        "Exec session into {} ended after {}s: {}",
+        label,
+        started.elapsed().as_secs(),
+        reason
+    );
+    if let Some(recorder) = recorder {
+        recorder.finish(exit_code, reason).await;
+    }
+}
+
+/// Appends an event to the session's recording, if any; `false` means the
+/// recording broke and the session has to end.
+async fn record(recorder: &mut Option<Recorder>, event: SessionEvent) -> bool {
+    let Some(recorder) = recorder else {
+        return true;
+    };
+    match recorder.record(event).await {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to write recording {}: {:#}", recorder.id(), e);
+            false
+        }
+    }
+}
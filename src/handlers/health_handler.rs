@@ -1,21 +1,34 @@
 use axum::{
+    extract::State,
     http::StatusCode,
     response::Json,
 };
 use serde_json::{json, Value};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
 
-pub async fn health_check() -> Result<Json<Value>, StatusCode> {
+use crate::{db, AppState};
+
+pub async fn health_check(State(state): State<AppState>) -> Result<Json<Value>, StatusCode> {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
+    let database = match db::ping(&state.db).await {
+        Ok(()) => "up",
+        Err(e) => {
+            warn!("Health check: database unavailable: {}", e);
+            "down"
+        }
+    };
+
     let health_data = json!({
-        "status": "healthy",
+        "status": if database == "up" { "healthy" } else { "degraded" },
         "timestamp": timestamp,
         "service": "kubeatlas-backend",
-        "version": env!("CARGO_PKG_VERSION")
+        "version": env!("CARGO_PKG_VERSION"),
+        "database": database
     });
 
     Ok(Json(health_data))
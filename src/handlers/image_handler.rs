@@ -0,0 +1,141 @@
+use axum::{
+    extract::{Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::{ContainerImage, ContainerImagePage},
+    repositories::ImageFilter,
+    validation::ValidationErrors,
+    AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+const CSV_HEADER: [&str; 16] = [
+    "image",
+    "registry",
+    "repository",
+    "tag",
+    "digest",
+    "latest_tag",
+    "mutable_tag",
+    "cluster_id",
+    "cluster",
+    "namespace",
+    "workload_kind",
+    "workload_name",
+    "container",
+    "first_seen",
+    "last_seen",
+    "running",
+];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImageQuery {
+    /// Substring of the image name, e.g. `nginx` or `ghcr.io/acme`
+    pub search: Option<String>,
+    pub cluster_id: Option<Uuid>,
+    pub namespace: Option<String>,
+    /// Only images tagged `latest`
+    #[serde(default)]
+    pub latest: bool,
+    /// Only images referenced by tag without a digest
+    #[serde(default)]
+    pub mutable: bool,
+    /// Only images running now
+    #[serde(default)]
+    pub running: bool,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+fn build_filter(query: ImageQuery, paged: bool) -> Result<ImageFilter, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if paged && !(1..=MAX_LIMIT).contains(&limit) {
+        errors.add("limit", format!("must be between 1 and {}", MAX_LIMIT));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if paged && offset < 0 {
+        errors.add("offset", "must not be negative");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid image query"))?;
+    Ok(ImageFilter {
+        search: query.search.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()),
+        cluster_id: query.cluster_id,
+        namespace: query.namespace.filter(|n| !n.trim().is_empty()),
+        latest: query.latest,
+        mutable: query.mutable,
+        running: query.running,
+        limit: paged.then_some(limit),
+        offset: if paged { offset } else { 0 },
+    })
+}
+
+/// Container images seen across all clusters, with where each one runs.
+pub async fn list_images(
+    State(state): State<AppState>,
+    Query(query): Query<ImageQuery>,
+) -> Result<Json<ContainerImagePage>, ApiError> {
+    let filter = build_filter(query, true)?;
+    let (total, items) = state.repos.images.list(&filter).await.map_err(repository_error)?;
+    Ok(Json(ContainerImagePage { total, items }))
+}
+
+fn csv_rows(images: &[ContainerImage]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing into a Vec cannot fail
+    let _ = writer.write_record(CSV_HEADER);
+    for image in images {
+        for location in &image.locations {
+            let _ = writer.write_record([
+                image.image.as_str(),
+                &image.registry,
+                &image.repository,
+                image.tag.as_deref().unwrap_or_default(),
+                image.digest.as_deref().unwrap_or_default(),
+                &image.latest_tag.to_string(),
+                &image.mutable_tag.to_string(),
+                &location.cluster_id.to_string(),
+                &location.cluster_name,
+                &location.namespace,
+                &location.workload_kind,
+                &location.workload_name,
+                &location.container,
+                &location.first_seen.to_rfc3339(),
+                &location.last_seen.to_rfc3339(),
+                &location.running.to_string(),
+            ]);
+        }
+    }
+    writer.into_inner().unwrap_or_default()
+}
+
+/// The same images as CSV, one row per place an image runs; filters as for
+/// the list, without paging.
+pub async fn export_images(
+    State(state): State<AppState>,
+    Query(query): Query<ImageQuery>,
+) -> Result<Response, ApiError> {
+    let filter = build_filter(query, false)?;
+    let (_, images) = state.repos.images.list(&filter).await.map_err(repository_error)?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"images.csv\"".to_string(),
+            ),
+        ],
+        csv_rows(&images),
+    )
+        .into_response())
+}
@@ -0,0 +1,170 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    handlers::cluster_handler::{repository_error, ApiError},
+    label_selector::LabelSelector,
+    models::{InventoryKind, InventoryPage, InventoryStatus},
+    repositories::InventoryFilter,
+    validation::ValidationErrors,
+    AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InventoryQuery {
+    /// Comma separated kinds, e.g. `Deployment,StatefulSet` or `pods`
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    #[serde(alias = "labelSelector")]
+    pub label_selector: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Builds the filter, restricting kinds to `allowed` (the endpoint's scope).
+fn build_filter(query: InventoryQuery, allowed: &[InventoryKind]) -> Result<InventoryFilter, ApiError> {
+    let mut errors = ValidationErrors::new();
+
+    let mut kinds = Vec::new();
+    for raw in query.kind.as_deref().unwrap_or_default().split(',').filter(|k| !k.trim().is_empty()) {
+        match InventoryKind::parse(raw).filter(|k| allowed.contains(k)) {
+            Some(kind) => kinds.push(kind),
+            None => errors.add(
+                "kind",
+                format!(
+                    "'{}' is not one of {}",
+                    raw.trim(),
+                    allowed.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            ),
+        }
+    }
+    if kinds.is_empty() {
+        kinds = allowed.to_vec();
+    }
+
+    let selector = match LabelSelector::parse(query.label_selector.as_deref().unwrap_or_default()) {
+        Ok(selector) => selector,
+        Err(e) => {
+            errors.add("label_selector", e.to_string());
+            LabelSelector::default()
+        }
+    };
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        errors.add("limit", format!("must be between 1 and {}", MAX_LIMIT));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if offset < 0 {
+        errors.add("offset", "must not be negative");
+    }
+
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid inventory query"))?;
+    Ok(InventoryFilter {
+        kinds,
+        namespace: query.namespace.filter(|n| !n.trim().is_empty()),
+        selector,
+        limit,
+        offset,
+    })
+}
+
+async fn list(
+    state: &AppState,
+    id: Uuid,
+    query: InventoryQuery,
+    allowed: &[InventoryKind],
+) -> Result<Json<InventoryPage>, ApiError> {
+    let filter = build_filter(query, allowed)?;
+    // 404 for unknown clusters rather than an empty page
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let (total, items) = state
+        .repos
+        .inventory
+        .list(id, &filter)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(InventoryPage { total, items }))
+}
+
+pub async fn list_inventory(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InventoryQuery>,
+) -> Result<Json<InventoryPage>, ApiError> {
+    list(&state, id, query, &InventoryKind::ALL).await
+}
+
+pub async fn list_nodes(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InventoryQuery>,
+) -> Result<Json<InventoryPage>, ApiError> {
+    list(&state, id, query, &[InventoryKind::Node]).await
+}
+
+pub async fn list_namespaces(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InventoryQuery>,
+) -> Result<Json<InventoryPage>, ApiError> {
+    list(&state, id, query, &[InventoryKind::Namespace]).await
+}
+
+pub async fn list_workloads(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<InventoryQuery>,
+) -> Result<Json<InventoryPage>, ApiError> {
+    list(&state, id, query, &InventoryKind::WORKLOADS).await
+}
+
+pub async fn get_inventory_status(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InventoryStatus>, ApiError> {
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let status = state
+        .repos
+        .inventory
+        .status(id)
+        .await
+        .map_err(repository_error)?;
+    status.map(Json).ok_or_else(|| {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Not Found",
+                "message": format!("Inventory of cluster '{}' has not been collected yet", id)
+            })),
+        )
+    })
+}
+
+/// Collects the inventory now, regardless of the cluster's health status.
+pub async fn refresh_inventory(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<InventoryStatus>, ApiError> {
+    info!("Admin: refresh inventory of cluster '{}'", id);
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let status = state
+        .collector
+        .collect_cluster(&cluster)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(status))
+}
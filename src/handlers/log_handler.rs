@@ -0,0 +1,214 @@
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use std::{convert::Infallible, time::Duration};
+use tokio::sync::mpsc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    handlers::cluster_handler::{authorized_client, kube_api_error, repository_error, ApiError},
+    label_selector::LabelSelector,
+    pod_logs::{self, LogOptions, MAX_AGGREGATED_STREAMS},
+    validation::{validate_pod_ref, ValidationErrors},
+    AppState,
+};
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+/// Lines buffered for a single pod before reading from the cluster pauses
+const BUFFER: usize = 256;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LogQuery {
+    pub container: Option<String>,
+    #[serde(default)]
+    pub follow: bool,
+    #[serde(alias = "tailLines")]
+    pub tail_lines: Option<i64>,
+    #[serde(alias = "sinceSeconds")]
+    pub since_seconds: Option<i64>,
+    #[serde(default)]
+    pub timestamps: bool,
+    #[serde(default)]
+    pub previous: bool,
+    /// Aggregated logs only: which pods to follow
+    #[serde(alias = "labelSelector")]
+    pub label_selector: Option<String>,
+}
+
+impl LogQuery {
+    fn container(&self) -> Option<&str> {
+        self.container.as_deref().filter(|c| !c.trim().is_empty())
+    }
+
+    fn options(&self, errors: &mut ValidationErrors) -> LogOptions {
+        if self.tail_lines.is_some_and(|n| n < 0) {
+            errors.add("tail_lines", "must not be negative");
+        }
+        if self.since_seconds.is_some_and(|n| n <= 0) {
+            errors.add("since_seconds", "must be positive");
+        }
+        LogOptions {
+            follow: self.follow,
+            tail_lines: self.tail_lines,
+            since_seconds: self.since_seconds,
+            timestamps: self.timestamps,
+            previous: self.previous,
+        }
+    }
+}
+
+fn log_check(verb: &str, resource: &str, subresource: Option<&str>, namespace: &str) -> KubeRequestInfo {
+    KubeRequestInfo::resource_request(verb, "", resource, subresource, Some(namespace))
+}
+
+/// Logs of one pod (`pods/log` with the Kubernetes options). Served as a
+/// chunked `text/plain` body, or one text message per line over a WebSocket
+/// when the request is an upgrade. Needs `get` on `pods/log`.
+pub async fn pod_logs(
+    ws: Option<WebSocketUpgrade>,
+    State(state): State<AppState>,
+    Path((id, namespace, pod)): Path<(Uuid, String, String)>,
+    Query(query): Query<LogQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Response, ApiError> {
+    let mut errors = ValidationErrors::new();
+    validate_pod_ref(&mut errors, &namespace, Some(&pod));
+    let options = query.options(&mut errors);
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid log request"))?;
+
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let checks = [log_check("get", "pods", Some("log"), &namespace)];
+    let client = authorized_client(&state, &user, &cluster, &checks).await?;
+
+    // Opened before answering so a missing pod or container is a proper error
+    let stream = pod_logs::open(&client, &namespace, &pod, query.container(), &options)
+        .await
+        .map_err(kube_api_error)?;
+    info!(
+        "Logs of pod {}/{} on cluster '{}' opened by {}",
+        namespace, pod, cluster.name, user.preferred_username
+    );
+    let (tx, rx) = mpsc::channel(BUFFER);
+    tokio::spawn(async move {
+        if let Err(e) = pod_logs::forward_lines(Box::pin(stream), "", &tx).await {
+            let _ = tx.send(format!("error: {:#}\n", e)).await;
+        }
+    });
+    Ok(respond(ws, rx))
+}
+
+/// Logs of every pod matching `label_selector` in a namespace, merged with a
+/// `[pod/container] ` prefix per line. Needs `list` on `pods` and `get` on
+/// `pods/log`.
+pub async fn namespace_logs(
+    ws: Option<WebSocketUpgrade>,
+    State(state): State<AppState>,
+    Path((id, namespace)): Path<(Uuid, String)>,
+    Query(query): Query<LogQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Response, ApiError> {
+    let mut errors = ValidationErrors::new();
+    validate_pod_ref(&mut errors, &namespace, None);
+    let options = query.options(&mut errors);
+    let selector = query.label_selector.as_deref().unwrap_or_default().trim();
+    if selector.is_empty() {
+        errors.add("label_selector", "is required");
+    } else if let Err(e) = LabelSelector::parse(selector) {
+        errors.add("label_selector", e.to_string());
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid log request"))?;
+
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let checks = [
+        log_check("list", "pods", None, &namespace),
+        log_check("get", "pods", Some("log"), &namespace),
+    ];
+    let client = authorized_client(&state, &user, &cluster, &checks).await?;
+
+    let sources = pod_logs::sources(&client, &namespace, selector, query.container())
+        .await
+        .map_err(kube_api_error)?;
+    if sources.len() > MAX_AGGREGATED_STREAMS {
+        return Err(ValidationErrors::single(
+            "label_selector",
+            format!(
+                "matches {} containers; at most {} can be aggregated",
+                sources.len(),
+                MAX_AGGREGATED_STREAMS
+            ),
+        )
+        .into_error(StatusCode::BAD_REQUEST, "Invalid log request"));
+    }
+    info!(
+        "Logs of {} containers matching '{}' in {} on cluster '{}' opened by {}",
+        sources.len(),
+        selector,
+        namespace,
+        cluster.name,
+        user.preferred_username
+    );
+    Ok(respond(ws, pod_logs::aggregate(client, namespace, sources, options)))
+}
+
+fn respond(ws: Option<WebSocketUpgrade>, rx: mpsc::Receiver<String>) -> Response {
+    match ws {
+        Some(ws) => ws.on_upgrade(move |socket| run(socket, rx)).into_response(),
+        None => {
+            let body = futures::stream::unfold(rx, |mut rx| async move {
+                rx.recv().await.map(|line| (Ok::<_, Infallible>(line), rx))
+            });
+            (
+                [
+                    (header::CONTENT_TYPE, "text/plain; charset=utf-8"),
+                    (header::CACHE_CONTROL, "no-cache"),
+                ],
+                Body::from_stream(body),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Sends each line as a text message (without the newline) until the logs
+/// end or the socket closes.
+async fn run(mut socket: WebSocket, mut rx: mpsc::Receiver<String>) {
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    loop {
+        tokio::select! {
+            line = rx.recv() => {
+                let Some(line) = line else {
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                };
+                let text = line.strip_suffix('\n').unwrap_or(&line).to_string();
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+            _ = heartbeat.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
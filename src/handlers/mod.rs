@@ -1,4 +1,20 @@
+pub mod audit_handler;
 pub mod auth_handler;
+pub mod cluster_handler;
+pub mod comparison_handler;
+pub mod deprecation_handler;
+pub mod exec_handler;
 pub mod health_handler;
+pub mod image_handler;
+pub mod inventory_handler;
+pub mod log_handler;
+pub mod preferences_handler;
+pub mod proxy_handler;
+pub mod recording_handler;
+pub mod role_admin_handler;
+pub mod snapshot_handler;
+pub mod topology_handler;
 pub mod user_handler;
 pub mod user_admin_handler;
+pub mod vulnerability_handler;
+pub mod watch_handler;
@@ -0,0 +1,58 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+
+use crate::{auth::KeycloakUser, models::UserPreferences, preferences, AppState};
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn storage_error(e: anyhow::Error) -> ApiError {
+    warn!("Preferences storage error: {}", e);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(json!({
+            "error": "Internal Server Error",
+            "message": "Failed to access preferences"
+        })),
+    )
+}
+
+pub async fn get_preferences(
+    State(state): State<AppState>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Json<UserPreferences>, ApiError> {
+    let prefs = preferences::load(&state.repos.preferences, &user.sub)
+        .await
+        .map_err(storage_error)?;
+    Ok(Json(prefs))
+}
+
+/// JSON merge patch (RFC 7396) over the caller's stored preferences.
+pub async fn patch_preferences(
+    State(state): State<AppState>,
+    Extension(user): Extension<KeycloakUser>,
+    Json(patch): Json<Value>,
+) -> Result<Json<UserPreferences>, ApiError> {
+    info!("Updating preferences for user: {}", user.preferred_username);
+    if !patch.is_object() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Bad Request",
+                "message": "Merge patch must be a JSON object"
+            })),
+        ));
+    }
+    match preferences::apply_patch(&state.repos.preferences, &user.sub, &patch)
+        .await
+        .map_err(storage_error)?
+    {
+        Ok(prefs) => Ok(Json(prefs)),
+        Err(errors) => Err(errors.into_error(StatusCode::BAD_REQUEST, "Invalid preferences")),
+    }
+}
@@ -0,0 +1,244 @@
+use axum::{
+    body::Body,
+    extract::{Path, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Json, Response},
+    Extension,
+};
+use futures::TryStreamExt;
+use serde_json::json;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::{
+    access_policy::{is_watch, CanonicalPath, KubeRequestInfo},
+    auth::KeycloakUser,
+    credentials::CredentialError,
+    impersonation,
+    models::Cluster,
+    repositories::{NewAuditEntry, RepositoryError},
+    AppState,
+};
+
+/// Request headers passed to the API server; everything else, notably the
+/// caller's `Authorization`, `Cookie` and `Impersonate-*`, is dropped.
+const FORWARDED_REQUEST_HEADERS: [&str; 4] =
+    ["accept", "accept-encoding", "content-type", "content-encoding"];
+
+/// Hop-by-hop headers that must not be copied from the upstream response.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// Errors are answered with a Kubernetes `Status` object so clients of the
+/// proxy see the same shape as from a real API server.
+fn status_response(code: StatusCode, reason: &str, message: impl Into<String>) -> Response {
+    (
+        code,
+        Json(json!({
+            "kind": "Status",
+            "apiVersion": "v1",
+            "metadata": {},
+            "status": "Failure",
+            "message": message.into(),
+            "reason": reason,
+            "code": code.as_u16()
+        })),
+    )
+        .into_response()
+}
+
+fn cluster_error(e: anyhow::Error) -> Response {
+    if let Some(CredentialError::NotConfigured) = e.downcast_ref::<CredentialError>() {
+        return status_response(StatusCode::SERVICE_UNAVAILABLE, "ServiceUnavailable", e.to_string());
+    }
+    match e.downcast_ref::<RepositoryError>() {
+        Some(RepositoryError::NotFound(_)) => status_response(StatusCode::NOT_FOUND, "NotFound", e.to_string()),
+        _ => {
+            warn!("Proxy setup failed: {:#}", e);
+            status_response(
+                StatusCode::BAD_GATEWAY,
+                "ServiceUnavailable",
+                format!("Cannot connect to cluster: {:#}", e),
+            )
+        }
+    }
+}
+
+struct AuditContext<'a> {
+    state: &'a AppState,
+    user: &'a KeycloakUser,
+    cluster: &'a Cluster,
+    info: &'a KubeRequestInfo,
+    path: &'a str,
+}
+
+impl AuditContext<'_> {
+    /// Records mutating calls only; audit failures are logged, not surfaced.
+    async fn record(&self, allowed: bool, status_code: Option<StatusCode>, error: Option<String>) {
+        if !self.info.is_mutating() {
+            return;
+        }
+        info!(
+            "Proxy audit: {} {} {} on cluster '{}' by {} (allowed: {}, status: {:?})",
+            self.info.verb,
+            self.info.resource_path(),
+            self.info.name.as_deref().unwrap_or("*"),
+            self.cluster.name,
+            self.user.preferred_username,
+            allowed,
+            status_code.map(|s| s.as_u16())
+        );
+        let entry = NewAuditEntry {
+            user_id: self.user.sub.clone(),
+            username: self.user.preferred_username.clone(),
+            cluster_id: self.cluster.id,
+            cluster_name: self.cluster.name.clone(),
+            verb: self.info.verb.clone(),
+            api_group: self.info.api_group.clone(),
+            resource: self.info.resource_path(),
+            namespace: self.info.namespace.clone(),
+            name: self.info.name.clone(),
+            path: self.path.to_string(),
+            allowed,
+            status_code: status_code.map(|s| s.as_u16() as i32),
+            error,
+        };
+        if let Err(e) = self.state.repos.audit.record(&entry).await {
+            warn!("Failed to write audit entry for {}: {}", self.path, e);
+        }
+    }
+}
+
+/// Forwards `/api/v1/clusters/:id/proxy/<kubernetes path>` to the cluster's
+/// API server with the stored credential, after checking the access policy
+/// (or, on impersonating clusters, as the user so cluster RBAC decides).
+/// Responses, watches included, are streamed back unchanged.
+pub async fn proxy(
+    State(state): State<AppState>,
+    Path((id, _)): Path<(Uuid, String)>,
+    Extension(user): Extension<KeycloakUser>,
+    request: Request,
+) -> Response {
+    if request.headers().contains_key(header::UPGRADE) {
+        return status_response(
+            StatusCode::BAD_REQUEST,
+            "BadRequest",
+            "Upgrade requests (exec, attach, port-forward) are not supported by the proxy",
+        );
+    }
+
+    let uri = request.uri().clone();
+    let raw_path = match uri.path().split_once("/proxy") {
+        Some((_, rest)) if rest.starts_with('/') => rest,
+        _ => return status_response(StatusCode::NOT_FOUND, "NotFound", "Missing Kubernetes API path"),
+    };
+    let query = uri.query().unwrap_or_default();
+    // What is authorized and what is forwarded are both derived from the
+    // decoded segments, so encoding tricks cannot make them differ
+    let canonical = match CanonicalPath::parse(raw_path) {
+        Ok(canonical) => canonical,
+        Err(e) => return status_response(StatusCode::BAD_REQUEST, "BadRequest", e.to_string()),
+    };
+    let path = canonical.path.clone();
+
+    let cluster = match state.repos.clusters.get(id).await {
+        Ok(cluster) => cluster,
+        Err(e) => return cluster_error(e),
+    };
+    let info = match KubeRequestInfo::parse(request.method(), &canonical, is_watch(query)) {
+        Ok(info) => info,
+        Err(e) => return status_response(StatusCode::BAD_REQUEST, "BadRequest", e.to_string()),
+    };
+    let audit = AuditContext {
+        state: &state,
+        user: &user,
+        cluster: &cluster,
+        info: &info,
+        path: &path,
+    };
+
+    let roles = state.auth_service.get_user_roles(&user);
+    let impersonation = match impersonation::identity(&cluster.impersonation, &user, &roles) {
+        Ok(impersonation) => impersonation,
+        Err(e) => {
+            audit.record(false, None, Some(e.to_string())).await;
+            return status_response(StatusCode::FORBIDDEN, "Forbidden", e.to_string());
+        }
+    };
+    // An impersonating cluster enforces its own RBAC instead of the access policy
+    if impersonation.is_none() && !state.policy.allows(&roles, &cluster.name, &info) {
+        audit.record(false, None, None).await;
+        return status_response(StatusCode::FORBIDDEN, "Forbidden", info.denied_message(&user.preferred_username));
+    }
+
+    let client = match state.clients.get(&cluster).await {
+        Ok(client) => match impersonation {
+            Some(impersonation) => client.impersonating(impersonation),
+            None => client,
+        },
+        Err(e) => {
+            audit.record(true, None, Some(format!("{:#}", e))).await;
+            return cluster_error(e);
+        }
+    };
+
+    let method = reqwest::Method::from_bytes(request.method().as_str().as_bytes())
+        .unwrap_or(reqwest::Method::GET);
+    let target = if query.is_empty() {
+        path.clone()
+    } else {
+        format!("{}?{}", path, query)
+    };
+    let mut upstream = client.request(method, &target);
+    for name in FORWARDED_REQUEST_HEADERS {
+        for value in request.headers().get_all(name) {
+            upstream = upstream.header(name, value.as_bytes());
+        }
+    }
+    if !matches!(*request.method(), Method::GET | Method::HEAD) {
+        let body = request.into_body().into_data_stream();
+        upstream = upstream.body(reqwest::Body::wrap_stream(body));
+    }
+
+    let response = match upstream.send().await {
+        Ok(response) => response,
+        Err(e) => {
+            audit.record(true, None, Some(e.to_string())).await;
+            let code = if e.is_timeout() {
+                StatusCode::GATEWAY_TIMEOUT
+            } else {
+                StatusCode::BAD_GATEWAY
+            };
+            return status_response(code, "ServiceUnavailable", format!("Cluster request failed: {}", e));
+        }
+    };
+
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+    audit.record(true, Some(status), None).await;
+    debug!("Proxy: {} {} -> {}", info.verb, path, status);
+
+    let mut builder = Response::builder().status(status);
+    for (name, value) in response.headers() {
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_str().as_bytes()),
+            HeaderValue::from_bytes(value.as_bytes()),
+        ) {
+            builder = builder.header(name, value);
+        }
+    }
+    let body = Body::from_stream(response.bytes_stream().map_err(std::io::Error::other));
+    builder
+        .body(body)
+        .unwrap_or_else(|e| status_response(StatusCode::BAD_GATEWAY, "InternalError", e.to_string()))
+}
@@ -0,0 +1,268 @@
+use axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::{ExecRecording, RecordingPage, RecordingStatus},
+    recording::CastLines,
+    recording_store::CastStream,
+    repositories::RecordingFilter,
+    validation::ValidationErrors,
+    AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 20.0;
+const DEFAULT_IDLE_LIMIT: f64 = 2.0;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RecordingQuery {
+    pub cluster_id: Option<Uuid>,
+    pub user: Option<String>,
+    pub audit_id: Option<Uuid>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReplayQuery {
+    /// Playback speed multiplier, defaults to 1
+    pub speed: Option<f64>,
+    /// Longest pause between events in seconds of the original session
+    pub idle_limit: Option<f64>,
+}
+
+/// Recorded exec sessions, newest first.
+pub async fn list_recordings(
+    State(state): State<AppState>,
+    Query(query): Query<RecordingQuery>,
+) -> Result<Json<RecordingPage>, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        errors.add("limit", format!("must be between 1 and {}", MAX_LIMIT));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if offset < 0 {
+        errors.add("offset", "must not be negative");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid recording query"))?;
+
+    let filter = RecordingFilter {
+        cluster_id: query.cluster_id,
+        username: query.user.filter(|u| !u.trim().is_empty()),
+        audit_id: query.audit_id,
+        limit,
+        offset,
+    };
+    let (total, items) = state.repos.recordings.list(&filter).await.map_err(repository_error)?;
+    Ok(Json(RecordingPage { total, items }))
+}
+
+pub async fn get_recording(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ExecRecording>, ApiError> {
+    let recording = state.repos.recordings.get(id).await.map_err(repository_error)?;
+    Ok(Json(recording))
+}
+
+/// Opens the stored cast; a session that is still running has none yet and
+/// a failed one never got one.
+async fn open_cast(state: &AppState, id: Uuid) -> Result<CastStream, ApiError> {
+    let recording = state.repos.recordings.get(id).await.map_err(repository_error)?;
+    match recording.status {
+        RecordingStatus::Recording => {
+            return Err((
+                StatusCode::CONFLICT,
+                Json(json!({
+                    "error": "Conflict",
+                    "message": "The session is still being recorded"
+                })),
+            ))
+        }
+        RecordingStatus::Failed => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Not Found",
+                    "message": "The recording could not be stored"
+                })),
+            ))
+        }
+        RecordingStatus::Complete | RecordingStatus::Interrupted => {}
+    }
+    state.recordings.read(&recording).await.map_err(|e| {
+        warn!("Failed to read recording {}: {:#}", id, e);
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({
+                "error": "Bad Gateway",
+                "message": "Recording storage request failed"
+            })),
+        )
+    })
+}
+
+/// The asciicast v2 file, playable with `asciinema play`.
+pub async fn download_recording(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Response, ApiError> {
+    let cast = open_cast(&state, id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/x-asciicast".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.cast\"", id),
+            ),
+        ],
+        Body::from_stream(cast),
+    )
+        .into_response())
+}
+
+/// Plays a recording back over a WebSocket with its original timing.
+pub async fn replay_recording(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<ReplayQuery>,
+) -> Result<Response, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let speed = query.speed.unwrap_or(1.0);
+    if !(MIN_SPEED..=MAX_SPEED).contains(&speed) {
+        errors.add("speed", format!("must be between {} and {}", MIN_SPEED, MAX_SPEED));
+    }
+    let idle_limit = query.idle_limit.unwrap_or(DEFAULT_IDLE_LIMIT);
+    if idle_limit.is_nan() || idle_limit <= 0.0 {
+        errors.add("idle_limit", "must be positive");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid replay request"))?;
+
+    let cast = open_cast(&state, id).await?;
+    Ok(ws
+        .on_upgrade(move |socket| replay(socket, cast, speed, idle_limit))
+        .into_response())
+}
+
+/// Turns one cast event line into a replay message and its timestamp.
+fn replay_message(line: &str) -> Option<(f64, Value)> {
+    let event: Value = serde_json::from_str(line).ok()?;
+    let time = event.get(0)?.as_f64()?;
+    let data = event.get(2)?.as_str()?;
+    let message = match event.get(1)?.as_str()? {
+        "o" => json!({ "type": "output", "time": time, "data": data }),
+        "i" => json!({ "type": "input", "time": time, "data": data }),
+        "r" => {
+            let (cols, rows) = data.split_once('x')?;
+            json!({
+                "type": "resize",
+                "time": time,
+                "cols": cols.parse::<u16>().ok()?,
+                "rows": rows.parse::<u16>().ok()?
+            })
+        }
+        _ => return None,
+    };
+    Some((time, message))
+}
+
+/// Sends the header, then every event after its (capped, scaled) delay,
+/// then `end`. Stops early when the viewer closes the socket or the cast
+/// cannot be read further.
+async fn replay(mut socket: WebSocket, cast: CastStream, speed: f64, idle_limit: f64) {
+    let mut lines = CastLines::new(cast);
+    let header = match lines.next_line().await {
+        Ok(line) => line
+            .and_then(|line| serde_json::from_str::<Value>(&line).ok())
+            .unwrap_or_else(|| json!({})),
+        Err(e) => {
+            warn!("Failed to read recording: {}", e);
+            return;
+        }
+    };
+    let message = json!({ "type": "header", "header": header });
+    if socket.send(Message::Text(message.to_string())).await.is_err() {
+        return;
+    }
+
+    let mut previous = 0.0;
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("Failed to read recording: {}", e);
+                return;
+            }
+        };
+        let Some((time, message)) = replay_message(&line) else {
+            continue;
+        };
+        let delay = (time - previous).clamp(0.0, idle_limit) / speed;
+        previous = time;
+        let sleep = tokio::time::sleep(Duration::from_secs_f64(delay));
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                incoming = socket.recv() => match incoming {
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                    _ => {}
+                },
+            }
+        }
+        if socket.send(Message::Text(message.to_string())).await.is_err() {
+            return;
+        }
+    }
+
+    let _ = socket
+        .send(Message::Text(json!({ "type": "end" }).to_string()))
+        .await;
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_messages_from_cast_events() {
+        assert_eq!(
+            replay_message(r#"[0.52, "o", "total 8\r\n"]"#),
+            Some((0.52, json!({ "type": "output", "time": 0.52, "data": "total 8\r\n" })))
+        );
+        assert_eq!(
+            replay_message(r#"[1.3, "i", "ls\r"]"#),
+            Some((1.3, json!({ "type": "input", "time": 1.3, "data": "ls\r" })))
+        );
+        assert_eq!(
+            replay_message(r#"[2.0, "r", "100x30"]"#),
+            Some((2.0, json!({ "type": "resize", "time": 2.0, "cols": 100, "rows": 30 })))
+        );
+        for ignored in [r#"[2.0, "r", "100"]"#, r#"[2.0, "m", "marker"]"#, r#"["x", "o", "a"]"#, "not json", ""] {
+            assert_eq!(replay_message(ignored), None, "{}", ignored);
+        }
+    }
+}
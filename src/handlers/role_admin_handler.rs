@@ -0,0 +1,174 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde_json::json;
+use tracing::{info, warn};
+
+use crate::{
+    auth::KeycloakError,
+    models::{CreateRoleRequest, Role, RoleCompositesRequest, RoleMember, UpdateRoleRequest},
+    AppState,
+};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+fn error_response(e: anyhow::Error) -> ApiError {
+    let (status, error) = match e.downcast_ref::<KeycloakError>() {
+        Some(KeycloakError::NotFound(_)) => (StatusCode::NOT_FOUND, "Not Found"),
+        Some(KeycloakError::Conflict(_)) => (StatusCode::CONFLICT, "Conflict"),
+        Some(KeycloakError::Forbidden(_)) => (StatusCode::FORBIDDEN, "Forbidden"),
+        Some(KeycloakError::BadRequest(_)) => (StatusCode::BAD_REQUEST, "Bad Request"),
+        None => {
+            warn!("Role operation failed: {}", e);
+            (StatusCode::BAD_GATEWAY, "Bad Gateway")
+        }
+    };
+    (
+        status,
+        Json(json!({
+            "error": error,
+            "message": e.to_string()
+        })),
+    )
+}
+
+async fn admin_token(state: &AppState) -> Result<String, ApiError> {
+    state
+        .auth_service
+        .get_admin_access_token()
+        .await
+        .map_err(error_response)
+}
+
+pub async fn list_roles(State(state): State<AppState>) -> Result<Json<Vec<Role>>, ApiError> {
+    let token = admin_token(&state).await?;
+    let roles = state
+        .auth_service
+        .list_realm_roles(&token)
+        .await
+        .map_err(error_response)?;
+    Ok(Json(roles))
+}
+
+pub async fn get_role(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Role>, ApiError> {
+    let token = admin_token(&state).await?;
+    let role = state
+        .auth_service
+        .get_realm_role(&token, &name)
+        .await
+        .map_err(error_response)?;
+    Ok(Json(role))
+}
+
+pub async fn create_role(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateRoleRequest>,
+) -> Result<(StatusCode, Json<Role>), ApiError> {
+    if payload.name.trim().is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "Bad Request",
+                "message": "Role name is required"
+            })),
+        ));
+    }
+    info!("Admin: create role '{}'", payload.name);
+    let token = admin_token(&state).await?;
+    let role = state
+        .auth_service
+        .create_realm_role(&token, payload)
+        .await
+        .map_err(error_response)?;
+    Ok((StatusCode::CREATED, Json(role)))
+}
+
+pub async fn update_role(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<UpdateRoleRequest>,
+) -> Result<Json<Role>, ApiError> {
+    info!("Admin: update role '{}'", name);
+    let token = admin_token(&state).await?;
+    let role = state
+        .auth_service
+        .update_realm_role(&token, &name, payload)
+        .await
+        .map_err(error_response)?;
+    Ok(Json(role))
+}
+
+pub async fn delete_role(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    info!("Admin: delete role '{}'", name);
+    let token = admin_token(&state).await?;
+    state
+        .auth_service
+        .delete_realm_role(&token, &name)
+        .await
+        .map_err(error_response)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_role_members(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<RoleMember>>, ApiError> {
+    let token = admin_token(&state).await?;
+    let members = state
+        .auth_service
+        .list_realm_role_members(&token, &name)
+        .await
+        .map_err(error_response)?;
+    Ok(Json(members))
+}
+
+pub async fn list_role_composites(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+) -> Result<Json<Vec<Role>>, ApiError> {
+    let token = admin_token(&state).await?;
+    let composites = state
+        .auth_service
+        .get_realm_role_composites(&token, &name)
+        .await
+        .map_err(error_response)?;
+    Ok(Json(composites))
+}
+
+pub async fn add_role_composites(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<RoleCompositesRequest>,
+) -> Result<Json<Vec<Role>>, ApiError> {
+    info!("Admin: add composites {:?} to role '{}'", payload.roles, name);
+    let token = admin_token(&state).await?;
+    state
+        .auth_service
+        .add_realm_role_composites(&token, &name, &payload.roles)
+        .await
+        .map_err(error_response)?;
+    list_role_composites(State(state), Path(name)).await
+}
+
+pub async fn remove_role_composites(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    Json(payload): Json<RoleCompositesRequest>,
+) -> Result<Json<Vec<Role>>, ApiError> {
+    info!("Admin: remove composites {:?} from role '{}'", payload.roles, name);
+    let token = admin_token(&state).await?;
+    state
+        .auth_service
+        .remove_realm_role_composites(&token, &name, &payload.roles)
+        .await
+        .map_err(error_response)?;
+    list_role_composites(State(state), Path(name)).await
+}
@@ -0,0 +1,257 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    auth::KeycloakUser,
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::{ClusterSnapshot, InventoryKind, SnapshotDiff, SnapshotPage, SnapshotTrigger},
+    repositories::SnapshotFilter,
+    snapshots,
+    validation::ValidationErrors,
+    AppState,
+};
+
+const DEFAULT_LIMIT: i64 = 100;
+const MAX_LIMIT: i64 = 1000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SnapshotQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateSnapshotQuery {
+    /// Collect the inventory before taking the snapshot
+    #[serde(default)]
+    pub refresh: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DiffQuery {
+    /// Snapshot id, or an RFC 3339 time meaning the latest snapshot at or before it
+    pub from: Option<String>,
+    /// Same as `from`; the latest snapshot when absent
+    pub to: Option<String>,
+    /// Comma separated kinds, e.g. `Deployment,StatefulSet` or `pods`
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    /// Comma separated JSON pointers to leave out, e.g. `/metadata/labels`
+    pub ignore: Option<String>,
+}
+
+enum SnapshotRef {
+    Id(Uuid),
+    At(DateTime<Utc>),
+}
+
+fn parse_ref(errors: &mut ValidationErrors, field: &str, value: &str) -> Option<SnapshotRef> {
+    let value = value.trim();
+    if let Ok(id) = value.parse::<Uuid>() {
+        return Some(SnapshotRef::Id(id));
+    }
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(at) => Some(SnapshotRef::At(at.with_timezone(&Utc))),
+        Err(_) => {
+            errors.add(field, "must be a snapshot id or an RFC 3339 time");
+            None
+        }
+    }
+}
+
+async fn resolve(state: &AppState, cluster_id: Uuid, snapshot: Option<SnapshotRef>) -> Result<ClusterSnapshot, ApiError> {
+    let snapshots = &state.repos.snapshots;
+    match snapshot {
+        Some(SnapshotRef::Id(id)) => snapshots.get(cluster_id, id).await.map_err(repository_error),
+        Some(SnapshotRef::At(at)) => snapshots.at(cluster_id, at).await.map_err(repository_error),
+        None => snapshots
+            .latest(cluster_id)
+            .await
+            .map_err(repository_error)?
+            .ok_or_else(|| {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(json!({
+                        "error": "Not Found",
+                        "message": format!("Cluster '{}' has no snapshots", cluster_id)
+                    })),
+                )
+            }),
+    }
+}
+
+/// Snapshots of a cluster, newest first.
+pub async fn list_snapshots(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<SnapshotQuery>,
+) -> Result<Json<SnapshotPage>, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    if !(1..=MAX_LIMIT).contains(&limit) {
+        errors.add("limit", format!("must be between 1 and {}", MAX_LIMIT));
+    }
+    let offset = query.offset.unwrap_or(0);
+    if offset < 0 {
+        errors.add("offset", "must not be negative");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid snapshot query"))?;
+
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let filter = SnapshotFilter {
+        from: query.from,
+        to: query.to,
+        limit,
+        offset,
+    };
+    let (total, items) = state
+        .repos
+        .snapshots
+        .list(id, &filter)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(SnapshotPage { total, items }))
+}
+
+pub async fn get_snapshot(
+    State(state): State<AppState>,
+    Path((id, snapshot_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<ClusterSnapshot>, ApiError> {
+    let snapshot = state
+        .repos
+        .snapshots
+        .get(id, snapshot_id)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(snapshot))
+}
+
+/// What changed between two snapshots, with field-level differences.
+pub async fn diff_snapshots(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<DiffQuery>,
+) -> Result<Json<SnapshotDiff>, ApiError> {
+    let mut errors = ValidationErrors::new();
+    let from = match query.from.as_deref().filter(|f| !f.trim().is_empty()) {
+        Some(from) => parse_ref(&mut errors, "from", from),
+        None => {
+            errors.add("from", "is required");
+            None
+        }
+    };
+    let to = query
+        .to
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+        .and_then(|to| parse_ref(&mut errors, "to", to));
+
+    let mut kinds = Vec::new();
+    for raw in query.kind.as_deref().unwrap_or_default().split(',').filter(|k| !k.trim().is_empty()) {
+        match InventoryKind::parse(raw) {
+            Some(kind) => kinds.push(kind),
+            None => errors.add(
+                "kind",
+                format!(
+                    "'{}' is not one of {}",
+                    raw.trim(),
+                    InventoryKind::ALL.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            ),
+        }
+    }
+
+    let mut ignored = Vec::new();
+    for pointer in query.ignore.as_deref().unwrap_or_default().split(',').map(str::trim) {
+        if pointer.is_empty() {
+            continue;
+        }
+        if !pointer.starts_with('/') {
+            errors.add("ignore", format!("'{}' is not a JSON pointer", pointer));
+        }
+        ignored.push(pointer.trim_end_matches('/').to_string());
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid snapshot diff query"))?;
+
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let from = resolve(&state, id, from).await?;
+    let to = resolve(&state, id, to).await?;
+    let namespace = query.namespace.filter(|n| !n.trim().is_empty());
+    let diff = snapshots::compare(&state.repos.snapshots, from, to, &kinds, namespace.as_deref(), &ignored)
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(diff))
+}
+
+/// Takes a snapshot of the stored inventory now.
+pub async fn create_snapshot(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<CreateSnapshotQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<(StatusCode, Json<ClusterSnapshot>), ApiError> {
+    info!("Admin: snapshot cluster '{}'", id);
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let status = if query.refresh {
+        Some(
+            state
+                .collector
+                .collect_cluster(&cluster)
+                .await
+                .map_err(repository_error)?,
+        )
+    } else {
+        state.repos.inventory.status(id).await.map_err(repository_error)?
+    };
+    let message = match status {
+        None => Some("Inventory of the cluster has not been collected yet"),
+        Some(status) if status.finished_at.is_none() => Some("Inventory of the cluster is being collected"),
+        Some(_) => None,
+    };
+    if let Some(message) = message {
+        return Err((
+            StatusCode::CONFLICT,
+            Json(json!({
+                "error": "Conflict",
+                "message": message
+            })),
+        ));
+    }
+
+    let snapshot = state
+        .repos
+        .snapshots
+        .create(id, SnapshotTrigger::Manual, Some(&user.preferred_username))
+        .await
+        .map_err(repository_error)?;
+    Ok((StatusCode::CREATED, Json(snapshot)))
+}
+
+pub async fn delete_snapshot(
+    State(state): State<AppState>,
+    Path((id, snapshot_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, ApiError> {
+    info!("Admin: delete snapshot '{}' of cluster '{}'", snapshot_id, id);
+    state
+        .repos
+        .snapshots
+        .delete(id, snapshot_id)
+        .await
+        .map_err(repository_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
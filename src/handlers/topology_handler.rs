@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    handlers::cluster_handler::{authorized_client, kube_api_error, repository_error, ApiError},
+    models::TopologyGraph,
+    topology::{self, LISTED_KINDS},
+    validation::{is_dns_label, ValidationErrors},
+    AppState,
+};
+
+const DEFAULT_DEPTH: usize = 2;
+const MAX_DEPTH: usize = 10;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TopologyQuery {
+    /// Whole cluster when absent
+    pub namespace: Option<String>,
+    /// Node id to start from, e.g. `Deployment/shop/web`
+    pub start: Option<String>,
+    /// Edges to follow from `start`
+    pub depth: Option<usize>,
+}
+
+/// Relationship graph of a cluster or namespace, read live from the cluster.
+/// Needs `list` on every kind the graph is built from.
+pub async fn get_topology(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<TopologyQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Json<TopologyGraph>, ApiError> {
+    let namespace = query.namespace.filter(|n| !n.trim().is_empty());
+    let start = query.start.filter(|s| !s.trim().is_empty());
+    let depth = query.depth.unwrap_or(DEFAULT_DEPTH);
+    let mut errors = ValidationErrors::new();
+    if namespace.as_deref().is_some_and(|n| !is_dns_label(n)) {
+        errors.add("namespace", "must be a DNS-1123 label");
+    }
+    if depth > MAX_DEPTH {
+        errors.add("depth", format!("must be at most {}", MAX_DEPTH));
+    }
+    if query.depth.is_some() && start.is_none() {
+        errors.add("start", "is required with depth");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid topology query"))?;
+
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let checks: Vec<KubeRequestInfo> = LISTED_KINDS
+        .iter()
+        .map(|kind| {
+            KubeRequestInfo::resource_request("list", kind.api_group, kind.resource, None, namespace.as_deref())
+        })
+        .collect();
+    let client = authorized_client(&state, &user, &cluster, &checks).await?;
+
+    let graph = topology::collect(&client, namespace.as_deref())
+        .await
+        .map_err(kube_api_error)?;
+    info!(
+        "Topology of cluster '{}' ({}) built for {}: {} nodes, {} edges",
+        cluster.name,
+        namespace.as_deref().unwrap_or("all namespaces"),
+        user.preferred_username,
+        graph.nodes.len(),
+        graph.edges.len()
+    );
+    match start {
+        None => Ok(Json(graph)),
+        Some(start) => topology::neighbourhood(graph, &start, depth).map(Json).ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Not Found",
+                    "message": format!("Node '{}' not found", start)
+                })),
+            )
+        }),
+    }
+}
@@ -1,23 +1,117 @@
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Body,
+    extract::{Multipart, Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
 };
+use serde::Deserialize;
 use serde_json::json;
+use std::collections::HashSet;
 use tracing::{info, warn};
 
-use crate::{models::{CreateUserRequest, UpdateUserRequest}, AppState};
+use crate::{
+    auth::{CreateUserStep, KeycloakError},
+    models::{CreateUserRequest, ImportUsersReport, UpdateUserRequest},
+    user_export::{self, ExportFormat},
+    user_import::{self, ImportFormat},
+    validation::{self, ValidationErrors},
+    AppState,
+};
+
+type ApiError = (StatusCode, Json<serde_json::Value>);
+
+/// Checks that every requested role exists in the realm.
+async fn validate_roles(state: &AppState, roles: &[String]) -> Result<(), ApiError> {
+    if roles.is_empty() {
+        return Ok(());
+    }
+    let known_roles: HashSet<String> = async {
+        let token = state.auth_service.get_admin_access_token().await?;
+        state.auth_service.list_realm_role_names(&token).await
+    }
+    .await
+    .map_err(|e| {
+        warn!("Cannot load realm roles for validation: {}", e);
+        keycloak_unavailable()
+    })?
+    .into_iter()
+    .collect();
+
+    let mut errors = ValidationErrors::new();
+    validation::validate_roles_exist(&mut errors, "roles", roles, &known_roles);
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))
+}
+
+fn keycloak_unavailable() -> ApiError {
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(json!({
+            "error": "Bad Gateway",
+            "message": "Keycloak request failed"
+        })),
+    )
+}
+
+/// Maps typed Keycloak failures to field errors; anything else is a gateway error.
+fn keycloak_error_response(e: &anyhow::Error, field_hint: &str) -> ApiError {
+    match e.downcast_ref::<KeycloakError>() {
+        Some(KeycloakError::Conflict(message)) => {
+            let field = validation::conflict_field(message);
+            ValidationErrors::single(field, "is already taken")
+                .into_error(StatusCode::CONFLICT, &format!("{} already exists", message))
+        }
+        Some(KeycloakError::BadRequest(message)) => ValidationErrors::single(field_hint, message.clone())
+            .into_error(StatusCode::BAD_REQUEST, "Rejected by Keycloak"),
+        Some(KeycloakError::NotFound(message)) => (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "error": "Not Found",
+                "message": format!("{} not found", message)
+            })),
+        ),
+        _ => (
+            StatusCode::BAD_GATEWAY,
+            Json(json!({
+                "error": "Bad Gateway",
+                "message": e.to_string()
+            })),
+        ),
+    }
+}
 
 pub async fn create_user(
     State(state): State<AppState>,
     Json(payload): Json<CreateUserRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     info!("Admin: create user '{}': roles={:?}", payload.username, payload.roles);
+    validation::validate_create_user(&payload, &state.config.password_policy)
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+    validate_roles(&state, &payload.roles).await?;
+
     match state.auth_service.create_keycloak_user(payload).await {
         Ok(user_id) => Ok(Json(json!({ "id": user_id }))),
         Err(e) => {
             warn!("Create user failed: {}", e);
-            Err(StatusCode::BAD_REQUEST)
+            let field_hint = match e.step {
+                CreateUserStep::ValidateRoles | CreateUserStep::AssignRoles => "roles",
+                CreateUserStep::ValidateGroups | CreateUserStep::JoinGroups => "groups",
+                _ => "password",
+            };
+            let (status, Json(mut body)) = match (e.step, e.cause.downcast_ref::<KeycloakError>()) {
+                // Roles or groups vanished between validation and creation
+                (
+                    CreateUserStep::ValidateRoles | CreateUserStep::ValidateGroups,
+                    Some(KeycloakError::NotFound(message)),
+                ) => ValidationErrors::single(field_hint, format!("{} does not exist", message))
+                    .into_error(StatusCode::BAD_REQUEST, "Request validation failed"),
+                _ => keycloak_error_response(&e.cause, field_hint),
+            };
+            body["step"] = json!(e.step);
+            body["rolled_back"] = json!(e.rolled_back);
+            Err((status, Json(body)))
         }
     }
 }
@@ -26,8 +120,15 @@ pub async fn update_user(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Json(payload): Json<UpdateUserRequest>,
-) -> Result<Json<serde_json::Value>, StatusCode> {
+) -> Result<Json<serde_json::Value>, ApiError> {
     info!("Admin: update user '{}': payload", user_id);
+    validation::validate_update_user(&payload)
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+    if let Some(roles) = &payload.roles {
+        validate_roles(&state, roles).await?;
+    }
+
     match state
         .auth_service
         .update_keycloak_user(&user_id, payload)
@@ -36,9 +137,116 @@ pub async fn update_user(
         Ok(_) => Ok(Json(json!({ "id": user_id }))),
         Err(e) => {
             warn!("Update user failed: {}", e);
-            Err(StatusCode::BAD_REQUEST)
+            Err(keycloak_error_response(&e, "roles"))
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct ImportUsersQuery {
+    #[serde(default)]
+    pub dry_run: bool,
+    pub format: Option<String>,
+}
+
+fn bad_request(message: String) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Bad Request",
+            "message": message
+        })),
+    )
+}
 
+/// Multipart upload with a single `file` part holding CSV or JSON users.
+pub async fn import_users(
+    State(state): State<AppState>,
+    Query(query): Query<ImportUsersQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<ImportUsersReport>, ApiError> {
+    let mut upload: Option<(Option<String>, Option<String>, Vec<u8>)> = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let file_name = field.file_name().map(str::to_string);
+        let content_type = field.content_type().map(str::to_string);
+        let data = field
+            .bytes()
+            .await
+            .map_err(|e| bad_request(format!("Failed to read upload: {}", e)))?;
+        upload = Some((file_name, content_type, data.to_vec()));
+    }
+
+    let (file_name, content_type, data) =
+        upload.ok_or_else(|| bad_request("Missing 'file' part".to_string()))?;
+    let format = ImportFormat::detect(
+        query.format.as_deref(),
+        file_name.as_deref(),
+        content_type.as_deref(),
+        &data,
+    )
+    .map_err(|e| bad_request(e.to_string()))?;
+    let rows = user_import::parse_records(&data, format).map_err(|e| bad_request(e.to_string()))?;
+
+    info!(
+        "Admin: import {} user rows ({:?}, dry_run={})",
+        rows.len(),
+        format,
+        query.dry_run
+    );
+    match user_import::import_users(
+        &state.auth_service,
+        rows,
+        query.dry_run,
+        state.config.user_import_concurrency,
+        &state.config.password_policy,
+    )
+    .await
+    {
+        Ok(report) => Ok(Json(report)),
+        Err(e) => {
+            warn!("User import failed: {}", e);
+            Err((
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": "Bad Gateway",
+                    "message": format!("User import failed: {}", e)
+                })),
+            ))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportUsersQuery {
+    pub format: Option<String>,
+}
+
+pub async fn export_users(
+    State(state): State<AppState>,
+    Query(query): Query<ExportUsersQuery>,
+) -> Result<Response, ApiError> {
+    let format =
+        ExportFormat::parse(query.format.as_deref()).map_err(|e| bad_request(e.to_string()))?;
+    info!("Admin: export users ({:?})", format);
+
+    let body = Body::from_stream(user_export::export_users(state.auth_service.clone(), format));
+    let disposition = format!(
+        "attachment; filename=\"users.{}\"",
+        format.file_extension()
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
@@ -1,12 +1,18 @@
 use axum::{
-    extract::Request,
+    extract::{Request, State},
     http::StatusCode,
     response::Json,
+    Extension,
 };
 use serde_json::{json, Value};
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::auth::KeycloakUser;
+use crate::{
+    auth::{KeycloakError, KeycloakUser},
+    models::{ChangePasswordRequest, UpdateProfileRequest},
+    validation::{self, ValidationErrors},
+    AppState,
+};
 
 pub async fn get_profile(request: Request) -> Result<Json<Value>, StatusCode> {
     // Extract user from request extensions (set by auth middleware)
@@ -48,7 +54,7 @@ pub async fn get_user_roles(request: Request) -> Result<Json<Value>, StatusCode>
     
     // Add client roles
     if let Some(resource_access) = &user.resource_access {
-        for (_, access) in resource_access {
+        for access in resource_access.values() {
             roles.extend(access.roles.clone());
         }
     }
@@ -63,3 +69,107 @@ pub async fn get_user_roles(request: Request) -> Result<Json<Value>, StatusCode>
 
     Ok(Json(response))
 }
+
+type ApiError = (StatusCode, Json<Value>);
+
+fn self_service_error(e: anyhow::Error, field_hint: &str) -> ApiError {
+    match e.downcast_ref::<KeycloakError>() {
+        Some(KeycloakError::Conflict(message)) => {
+            ValidationErrors::single(validation::conflict_field(message), "is already taken")
+                .into_error(StatusCode::CONFLICT, &format!("{} already exists", message))
+        }
+        Some(KeycloakError::BadRequest(message)) => ValidationErrors::single(field_hint, message.clone())
+            .into_error(StatusCode::BAD_REQUEST, "Rejected by Keycloak"),
+        Some(KeycloakError::Forbidden(message)) => {
+            ValidationErrors::single("current_password", "is incorrect")
+                .into_error(StatusCode::FORBIDDEN, message)
+        }
+        _ => {
+            warn!("Self-service request failed: {}", e);
+            (
+                StatusCode::BAD_GATEWAY,
+                Json(json!({
+                    "error": "Bad Gateway",
+                    "message": "Keycloak request failed"
+                })),
+            )
+        }
+    }
+}
+
+/// Updates the caller's own first/last name and email; `sub` comes from the token.
+pub async fn update_profile(
+    State(state): State<AppState>,
+    Extension(user): Extension<KeycloakUser>,
+    Json(payload): Json<UpdateProfileRequest>,
+) -> Result<Json<Value>, ApiError> {
+    info!("Updating profile for user: {}", user.preferred_username);
+
+    let mut errors = ValidationErrors::new();
+    if let Some(email) = &payload.email {
+        validation::validate_email(&mut errors, "email", email);
+    }
+    validation::validate_name(&mut errors, "first_name", payload.first_name.as_deref());
+    validation::validate_name(&mut errors, "last_name", payload.last_name.as_deref());
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+
+    let updated = state
+        .auth_service
+        .update_own_profile(&user.sub, payload)
+        .await
+        .map_err(|e| self_service_error(e, "email"))?;
+
+    let email_verified = updated
+        .get("emailVerified")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    Ok(Json(json!({
+        "id": user.sub,
+        "username": user.preferred_username,
+        "email": updated.get("email"),
+        "firstName": updated.get("firstName"),
+        "lastName": updated.get("lastName"),
+        "emailVerified": email_verified
+    })))
+}
+
+/// Changes the caller's own password after re-checking the current one.
+pub async fn change_password(
+    State(state): State<AppState>,
+    Extension(user): Extension<KeycloakUser>,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    info!("Changing password for user: {}", user.preferred_username);
+
+    let mut errors = ValidationErrors::new();
+    if payload.current_password.is_empty() {
+        errors.add("current_password", "is required");
+    }
+    validation::validate_password(
+        &mut errors,
+        "new_password",
+        &payload.new_password,
+        &state.config.password_policy,
+    );
+    if payload.new_password == payload.current_password {
+        errors.add("new_password", "must differ from the current password");
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Request validation failed"))?;
+
+    state
+        .auth_service
+        .change_own_password(
+            &user.sub,
+            &user.preferred_username,
+            &payload.current_password,
+            &payload.new_password,
+        )
+        .await
+        .map_err(|e| self_service_error(e, "new_password"))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,222 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::Deserialize;
+use serde_json::json;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    advisory_feed,
+    auth::KeycloakUser,
+    handlers::cluster_handler::{repository_error, ApiError},
+    models::{
+        ClusterVulnerabilitySummary, FeedFormat, FeedImport, ImageSbom, ImageSbomDetails, ImageVulnerabilities,
+        SeverityCounts, Severity, VulnerabilityFeed, WorkloadVulnerabilitySummary,
+    },
+    sbom,
+    validation::ValidationErrors,
+    AppState,
+};
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportFeedQuery {
+    /// Importing under an existing name replaces that feed
+    pub name: Option<String>,
+    /// `osv` or `trivy`; detected from the document when absent
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WorkloadVulnerabilityQuery {
+    pub namespace: Option<String>,
+}
+
+fn bad_request(message: String) -> ApiError {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "error": "Bad Request",
+            "message": message
+        })),
+    )
+}
+
+/// Digests as Kubernetes reports them: `sha256:<hex>`.
+fn validate_digest(digest: &str) -> Result<(), ApiError> {
+    let valid = digest.split_once(':').is_some_and(|(algorithm, hex)| {
+        !algorithm.is_empty()
+            && algorithm.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            && !hex.is_empty()
+            && hex.chars().all(|c| c.is_ascii_hexdigit())
+    });
+    if valid {
+        Ok(())
+    } else {
+        Err(bad_request(format!("Invalid image digest '{}'", digest)))
+    }
+}
+
+async fn read_file(mut multipart: Multipart) -> Result<Vec<u8>, ApiError> {
+    let mut data = None;
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| bad_request(format!("Invalid multipart body: {}", e)))?
+    {
+        if field.name() != Some("file") {
+            continue;
+        }
+        let bytes = field
+            .bytes()
+            .await
+            .map_err(|e| bad_request(format!("Failed to read upload: {}", e)))?;
+        data = Some(bytes.to_vec());
+    }
+    data.ok_or_else(|| bad_request("Missing 'file' part".to_string()))
+}
+
+/// Imports an advisory feed and matches every stored SBOM against all feeds.
+pub async fn import_feed(
+    State(state): State<AppState>,
+    Query(query): Query<ImportFeedQuery>,
+    Extension(user): Extension<KeycloakUser>,
+    multipart: Multipart,
+) -> Result<(StatusCode, Json<FeedImport>), ApiError> {
+    let mut errors = ValidationErrors::new();
+    let name = query.name.map(|n| n.trim().to_string()).unwrap_or_default();
+    if name.is_empty() {
+        errors.add("name", "is required");
+    }
+    let format = match query.format.as_deref().map(FeedFormat::parse).transpose() {
+        Ok(format) => format,
+        Err(e) => {
+            errors.add("format", e.to_string());
+            None
+        }
+    };
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid feed import"))?;
+
+    let data = read_file(multipart).await?;
+    let format = format.unwrap_or_else(|| advisory_feed::detect(&data));
+    let advisories = advisory_feed::parse(&data, format).map_err(|e| bad_request(format!("{:#}", e)))?;
+    info!("Admin: import {} advisories into feed '{}' ({:?})", advisories.len(), name, format);
+
+    let feed = state
+        .repos
+        .vulnerabilities
+        .import_feed(&name, format, &advisories, Some(&user.preferred_username))
+        .await
+        .map_err(repository_error)?;
+    let digests = state.repos.vulnerabilities.sbom_digests().await.map_err(repository_error)?;
+    let mut images_matched = 0;
+    for digest in &digests {
+        match state.repos.vulnerabilities.rematch(digest).await {
+            Ok(_) => images_matched += 1,
+            Err(e) => warn!("Matching image '{}' failed: {:#}", digest, e),
+        }
+    }
+    Ok((StatusCode::CREATED, Json(FeedImport { feed, images_matched })))
+}
+
+pub async fn list_feeds(State(state): State<AppState>) -> Result<Json<Vec<VulnerabilityFeed>>, ApiError> {
+    let feeds = state.repos.vulnerabilities.list_feeds().await.map_err(repository_error)?;
+    Ok(Json(feeds))
+}
+
+pub async fn delete_feed(State(state): State<AppState>, Path(id): Path<Uuid>) -> Result<StatusCode, ApiError> {
+    info!("Admin: delete vulnerability feed '{}'", id);
+    state.repos.vulnerabilities.delete_feed(id).await.map_err(repository_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Stores a CycloneDX or SPDX SBOM for an image digest and matches it.
+pub async fn put_sbom(
+    State(state): State<AppState>,
+    Path(digest): Path<String>,
+    Extension(user): Extension<KeycloakUser>,
+    multipart: Multipart,
+) -> Result<Json<ImageSbom>, ApiError> {
+    validate_digest(&digest)?;
+    let data = read_file(multipart).await?;
+    let (format, packages) = sbom::parse(&data).map_err(|e| bad_request(format!("{:#}", e)))?;
+    info!("Admin: SBOM with {} packages for image '{}'", packages.len(), digest);
+    let sbom = state
+        .repos
+        .vulnerabilities
+        .put_sbom(&digest, format, &packages, Some(&user.preferred_username))
+        .await
+        .map_err(repository_error)?;
+    state.repos.vulnerabilities.rematch(&digest).await.map_err(repository_error)?;
+    Ok(Json(sbom))
+}
+
+pub async fn get_sbom(
+    State(state): State<AppState>,
+    Path(digest): Path<String>,
+) -> Result<Json<ImageSbomDetails>, ApiError> {
+    validate_digest(&digest)?;
+    let details = state.repos.vulnerabilities.sbom_details(&digest).await.map_err(repository_error)?;
+    Ok(Json(details))
+}
+
+pub async fn delete_sbom(State(state): State<AppState>, Path(digest): Path<String>) -> Result<StatusCode, ApiError> {
+    validate_digest(&digest)?;
+    info!("Admin: delete SBOM of image '{}'", digest);
+    state.repos.vulnerabilities.delete_sbom(&digest).await.map_err(repository_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Advisories matched against an image's SBOM, most severe first.
+pub async fn image_vulnerabilities(
+    State(state): State<AppState>,
+    Path(digest): Path<String>,
+) -> Result<Json<ImageVulnerabilities>, ApiError> {
+    validate_digest(&digest)?;
+    let sbom = state.repos.vulnerabilities.get_sbom(&digest).await.map_err(repository_error)?;
+    let findings = state.repos.vulnerabilities.findings(&digest).await.map_err(repository_error)?;
+    let mut seen = std::collections::HashSet::new();
+    let mut counts = SeverityCounts::default();
+    for finding in &findings {
+        if !seen.insert((finding.advisory_id.as_str(), finding.severity)) {
+            continue;
+        }
+        match finding.severity {
+            Severity::Critical => counts.critical += 1,
+            Severity::High => counts.high += 1,
+            Severity::Medium => counts.medium += 1,
+            Severity::Low => counts.low += 1,
+            Severity::Unknown => counts.unknown += 1,
+        }
+    }
+    Ok(Json(ImageVulnerabilities { sbom, counts, findings }))
+}
+
+pub async fn cluster_summaries(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<ClusterVulnerabilitySummary>>, ApiError> {
+    let summaries = state.repos.vulnerabilities.cluster_summaries().await.map_err(repository_error)?;
+    Ok(Json(summaries))
+}
+
+/// Running workloads of a cluster with the advisories in their images.
+pub async fn workload_summaries(
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WorkloadVulnerabilityQuery>,
+) -> Result<Json<Vec<WorkloadVulnerabilitySummary>>, ApiError> {
+    state.repos.clusters.get(id).await.map_err(repository_error)?;
+    let namespace = query.namespace.filter(|n| !n.trim().is_empty());
+    let summaries = state
+        .repos
+        .vulnerabilities
+        .workload_summaries(id, namespace.as_deref())
+        .await
+        .map_err(repository_error)?;
+    Ok(Json(summaries))
+}
@@ -0,0 +1,175 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Extension,
+};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::{sync::mpsc, task::JoinSet};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    access_policy::KubeRequestInfo,
+    auth::KeycloakUser,
+    handlers::cluster_handler::{authorized_client, repository_error, ApiError},
+    kube_client::KubeClient,
+    label_selector::LabelSelector,
+    models::InventoryKind,
+    resource_watch::{self, WatchFilter},
+    validation::{is_dns_label, ValidationErrors},
+    AppState,
+};
+
+const HEARTBEAT: Duration = Duration::from_secs(30);
+/// Events buffered per connection before the watches wait for the browser
+const BUFFER: usize = 256;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct WatchQuery {
+    /// Comma separated kinds, e.g. `Deployment,Pod`
+    pub kind: Option<String>,
+    pub namespace: Option<String>,
+    #[serde(alias = "labelSelector")]
+    pub label_selector: Option<String>,
+    #[serde(alias = "fieldSelector")]
+    pub field_selector: Option<String>,
+    /// Resume after a reconnect from the last `resource_version` received
+    #[serde(alias = "resourceVersion")]
+    pub resource_version: Option<String>,
+}
+
+fn parse_query(query: &WatchQuery) -> Result<(Vec<InventoryKind>, WatchFilter), ApiError> {
+    let mut errors = ValidationErrors::new();
+    let mut kinds = Vec::new();
+    for raw in query.kind.as_deref().unwrap_or_default().split(',').filter(|k| !k.trim().is_empty()) {
+        match InventoryKind::parse(raw) {
+            Some(kind) if !kinds.contains(&kind) => kinds.push(kind),
+            Some(_) => {}
+            None => errors.add(
+                "kind",
+                format!(
+                    "'{}' is not one of {}",
+                    raw.trim(),
+                    InventoryKind::ALL.iter().map(|k| k.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            ),
+        }
+    }
+    if kinds.is_empty() && errors.is_empty() {
+        errors.add("kind", "at least one kind is required");
+    }
+    let namespace = query.namespace.clone().filter(|n| !n.trim().is_empty());
+    if namespace.as_deref().is_some_and(|n| !is_dns_label(n)) {
+        errors.add("namespace", "must be a DNS-1123 label");
+    }
+    let label_selector = query.label_selector.clone().filter(|s| !s.trim().is_empty());
+    if let Some(selector) = &label_selector {
+        if let Err(e) = LabelSelector::parse(selector) {
+            errors.add("label_selector", e.to_string());
+        }
+    }
+    errors
+        .into_result()
+        .map_err(|e| e.into_error(StatusCode::BAD_REQUEST, "Invalid watch request"))?;
+    Ok((
+        kinds,
+        WatchFilter {
+            namespace,
+            label_selector,
+            field_selector: query.field_selector.clone().filter(|s| !s.trim().is_empty()),
+        },
+    ))
+}
+
+/// Streams Kubernetes watch events for one or more kinds over a WebSocket.
+/// Authorization happens once, before the upgrade: every kind needs `list`
+/// and `watch` under the access policy, or the cluster's RBAC when it impersonates.
+pub async fn watch(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<WatchQuery>,
+    Extension(user): Extension<KeycloakUser>,
+) -> Result<Response, ApiError> {
+    let (kinds, filter) = parse_query(&query)?;
+    let cluster = state.repos.clusters.get(id).await.map_err(repository_error)?;
+
+    let checks: Vec<KubeRequestInfo> = kinds
+        .iter()
+        .flat_map(|kind| {
+            let namespace = filter.namespace.as_deref().filter(|_| kind.is_namespaced());
+            ["list", "watch"].map(|verb| {
+                KubeRequestInfo::resource_request(verb, kind.api_group(), &kind.resource(), None, namespace)
+            })
+        })
+        .collect();
+    let client = authorized_client(&state, &user, &cluster, &checks).await?;
+    info!(
+        "Watch of {:?} on cluster '{}' opened by {}",
+        kinds, cluster.name, user.preferred_username
+    );
+    let from = query.resource_version.clone();
+    Ok(ws
+        .on_upgrade(move |socket| run(socket, client, kinds, filter, from))
+        .into_response())
+}
+
+async fn run(
+    mut socket: WebSocket,
+    client: KubeClient,
+    kinds: Vec<InventoryKind>,
+    filter: WatchFilter,
+    from: Option<String>,
+) {
+    let (tx, mut rx) = mpsc::channel(BUFFER);
+    // Dropping the set aborts the watches when the socket goes away
+    let mut watches = JoinSet::new();
+    for kind in kinds {
+        watches.spawn(resource_watch::watch_kind(
+            client.clone(),
+            kind,
+            filter.clone(),
+            from.clone(),
+            tx.clone(),
+        ));
+    }
+    drop(tx);
+
+    let mut heartbeat = tokio::time::interval(HEARTBEAT);
+    loop {
+        tokio::select! {
+            message = rx.recv() => {
+                let Some(message) = message else {
+                    // Every watch stopped (each sent its error)
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                };
+                let text = match serde_json::to_string(&message) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        warn!("Failed to encode watch event: {}", e);
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text)).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+            _ = heartbeat.tick() => {
+                if socket.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    watches.abort_all();
+}
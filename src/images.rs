@@ -0,0 +1,214 @@
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::inventory_collector::{array_at, str_at, string_map};
+use crate::models::ImageReference;
+use crate::repositories::NewContainerImage;
+
+const DEFAULT_REGISTRY: &str = "docker.io";
+const DEFAULT_TAG: &str = "latest";
+const POD_TEMPLATE_HASH_LABEL: &str = "pod-template-hash";
+
+/// Splits a reference the way container runtimes read it: the first path
+/// component is a registry only if it looks like a host.
+pub fn parse_reference(image: &str) -> ImageReference {
+    let (name, digest) = match image.split_once('@') {
+        Some((name, digest)) => (name, Some(digest.to_string())),
+        None => (image, None),
+    };
+    // A colon after the last slash starts the tag; one before it is a registry port
+    let (name, tag) = match name.rfind(':') {
+        Some(i) if !name[i..].contains('/') => (&name[..i], Some(name[i + 1..].to_string())),
+        _ => (name, None),
+    };
+    let (registry, repository) = match name.split_once('/') {
+        Some((host, rest)) if host.contains('.') || host.contains(':') || host == "localhost" => {
+            (host.to_string(), rest.to_string())
+        }
+        _ => (DEFAULT_REGISTRY.to_string(), name.to_string()),
+    };
+    let repository = if registry == DEFAULT_REGISTRY && !repository.contains('/') {
+        format!("library/{}", repository)
+    } else {
+        repository
+    };
+    let tag = match (tag, &digest) {
+        (None, None) => Some(DEFAULT_TAG.to_string()),
+        (tag, _) => tag,
+    };
+    ImageReference {
+        registry,
+        repository,
+        tag,
+        digest,
+    }
+}
+
+/// The repository digest from a container status `imageID`, e.g.
+/// `docker-pullable://nginx@sha256:...`. Bare image ids are not digests.
+fn resolved_digest(image_id: &str) -> Option<String> {
+    image_id
+        .split_once('@')
+        .map(|(_, digest)| digest.to_string())
+        .filter(|digest| digest.starts_with("sha256:"))
+}
+
+/// The workload a pod belongs to. Deployments are recognised through their
+/// ReplicaSet's name, which is the Deployment's plus the pod template hash.
+fn workload(pod: &Value) -> (String, String) {
+    let owner = array_at(pod, "/metadata/ownerReferences")
+        .iter()
+        .find(|o| o.pointer("/controller").and_then(Value::as_bool) == Some(true));
+    let Some(owner) = owner else {
+        return ("Pod".to_string(), str_at(pod, "/metadata/name").unwrap_or_default());
+    };
+    let kind = str_at(owner, "/kind").unwrap_or_default();
+    let name = str_at(owner, "/name").unwrap_or_default();
+    if kind == "ReplicaSet" {
+        let labels = string_map(pod, "/metadata/labels");
+        if let Some(deployment) = labels
+            .get(POD_TEMPLATE_HASH_LABEL)
+            .and_then(|hash| name.strip_suffix(&format!("-{}", hash)))
+        {
+            return ("Deployment".to_string(), deployment.to_string());
+        }
+    }
+    (kind, name)
+}
+
+/// Images of every container (init containers included) in the pods, once
+/// per workload, container and image.
+pub fn sightings(pods: &[Value]) -> Vec<NewContainerImage> {
+    let mut found = BTreeMap::new();
+    for pod in pods {
+        let namespace = str_at(pod, "/metadata/namespace").unwrap_or_default();
+        let (workload_kind, workload_name) = workload(pod);
+        let resolved: BTreeMap<String, String> = ["/status/initContainerStatuses", "/status/containerStatuses"]
+            .iter()
+            .flat_map(|p| array_at(pod, p))
+            .filter_map(|s| Some((str_at(s, "/name")?, resolved_digest(&str_at(s, "/imageID")?)?)))
+            .collect();
+        for container in ["/spec/initContainers", "/spec/containers"].iter().flat_map(|p| array_at(pod, p)) {
+            let (Some(name), Some(image)) = (str_at(container, "/name"), str_at(container, "/image")) else {
+                continue;
+            };
+            let reference = parse_reference(&image);
+            let digest = reference.digest.clone().or_else(|| resolved.get(&name).cloned());
+            let sighting = NewContainerImage {
+                namespace: namespace.clone(),
+                workload_kind: workload_kind.clone(),
+                workload_name: workload_name.clone(),
+                container: name,
+                image,
+                registry: reference.registry,
+                repository: reference.repository,
+                tag: reference.tag,
+                digest,
+            };
+            found.insert(
+                (
+                    sighting.namespace.clone(),
+                    sighting.workload_kind.clone(),
+                    sighting.workload_name.clone(),
+                    sighting.container.clone(),
+                    sighting.image.clone(),
+                    sighting.digest.clone(),
+                ),
+                sighting,
+            );
+        }
+    }
+    found.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn reference(registry: &str, repository: &str, tag: Option<&str>, digest: Option<&str>) -> ImageReference {
+        ImageReference {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            tag: tag.map(str::to_string),
+            digest: digest.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn parse_reference_fills_docker_hub_defaults() {
+        assert_eq!(parse_reference("nginx"), reference("docker.io", "library/nginx", Some("latest"), None));
+        assert_eq!(
+            parse_reference("bitnami/redis:7.2"),
+            reference("docker.io", "bitnami/redis", Some("7.2"), None)
+        );
+        assert_eq!(
+            parse_reference("nginx@sha256:abc"),
+            reference("docker.io", "library/nginx", None, Some("sha256:abc"))
+        );
+    }
+
+    #[test]
+    fn parse_reference_recognises_registries() {
+        assert_eq!(
+            parse_reference("ghcr.io/org/app:v1@sha256:abc"),
+            reference("ghcr.io", "org/app", Some("v1"), Some("sha256:abc"))
+        );
+        assert_eq!(
+            parse_reference("registry.local:5000/team/app"),
+            reference("registry.local:5000", "team/app", Some("latest"), None)
+        );
+        assert_eq!(
+            parse_reference("localhost/app:dev"),
+            reference("localhost", "app", Some("dev"), None)
+        );
+    }
+
+    #[test]
+    fn resolved_digests() {
+        assert_eq!(
+            resolved_digest("docker-pullable://nginx@sha256:abc").as_deref(),
+            Some("sha256:abc")
+        );
+        assert_eq!(resolved_digest("sha256:abc"), None);
+    }
+
+    #[test]
+    fn workload_follows_the_controller() {
+        let pod = |owners: Value, labels: Value| {
+            json!({ "metadata": { "name": "web-6d4f-x2", "ownerReferences": owners, "labels": labels } })
+        };
+        let owner = |kind: &str, name: &str| json!([{ "kind": kind, "name": name, "controller": true }]);
+
+        let deployment = pod(owner("ReplicaSet", "web-6d4f"), json!({ "pod-template-hash": "6d4f" }));
+        assert_eq!(workload(&deployment), ("Deployment".to_string(), "web".to_string()));
+        let bare_replica_set = pod(owner("ReplicaSet", "web-6d4f"), json!({}));
+        assert_eq!(workload(&bare_replica_set), ("ReplicaSet".to_string(), "web-6d4f".to_string()));
+        let stateful = pod(owner("StatefulSet", "db"), json!({}));
+        assert_eq!(workload(&stateful), ("StatefulSet".to_string(), "db".to_string()));
+        let not_controller = pod(json!([{ "kind": "StatefulSet", "name": "db" }]), json!({}));
+        assert_eq!(workload(&not_controller), ("Pod".to_string(), "web-6d4f-x2".to_string()));
+    }
+
+    #[test]
+    fn sightings_take_digests_from_statuses() {
+        let pod = json!({
+            "metadata": { "namespace": "shop", "name": "web-0" },
+            "spec": {
+                "initContainers": [{ "name": "init", "image": "busybox" }],
+                "containers": [{ "name": "app", "image": "ghcr.io/org/app:v1" }]
+            },
+            "status": {
+                "containerStatuses": [{ "name": "app", "imageID": "ghcr.io/org/app@sha256:abc" }]
+            }
+        });
+        let found = sightings(&[pod.clone(), pod]);
+        assert_eq!(found.len(), 2);
+        let app = found.iter().find(|s| s.container == "app").unwrap();
+        assert_eq!((app.workload_kind.as_str(), app.workload_name.as_str()), ("Pod", "web-0"));
+        assert_eq!(app.digest.as_deref(), Some("sha256:abc"));
+        let init = found.iter().find(|s| s.container == "init").unwrap();
+        assert_eq!(init.repository, "library/busybox");
+        assert_eq!(init.digest, None);
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::{anyhow, Result};
+
+use crate::auth::KeycloakUser;
+use crate::kube_client::Impersonation;
+use crate::models::{ImpersonationSettings, ImpersonationUserClaim};
+
+/// Kubernetes reserves this prefix for its own users and groups.
+const RESERVED_PREFIX: &str = "system:";
+
+/// The Kubernetes identity for `user` under the cluster's settings, or `None`
+/// when the cluster does not impersonate. `roles` are the user's KubeAtlas roles.
+/// Names that would land in the reserved `system:` namespace are refused
+/// (user) or dropped (groups).
+pub fn identity(
+    settings: &ImpersonationSettings,
+    user: &KeycloakUser,
+    roles: &[String],
+) -> Result<Option<Impersonation>> {
+    if !settings.enabled {
+        return Ok(None);
+    }
+    let name = match settings.user_claim {
+        ImpersonationUserClaim::PreferredUsername => &user.preferred_username,
+        ImpersonationUserClaim::Sub => &user.sub,
+    };
+    let user_name = format!("{}{}", settings.user_prefix, name);
+    if name.is_empty() || user_name.starts_with(RESERVED_PREFIX) {
+        return Err(anyhow!("cannot impersonate user '{}'", user_name));
+    }
+
+    let mut names: Vec<&str> = Vec::new();
+    if settings.include_groups {
+        // Keycloak emits full group paths such as `/platform/sre`
+        names.extend(user.groups.iter().map(|g| g.trim_start_matches('/')));
+    }
+    if settings.include_roles {
+        names.extend(roles.iter().map(String::as_str));
+    }
+    let mut groups: Vec<String> = names
+        .into_iter()
+        .filter(|g| !g.is_empty())
+        .map(|g| format!("{}{}", settings.group_prefix, g))
+        .filter(|g| !g.starts_with(RESERVED_PREFIX))
+        .collect();
+    groups.sort();
+    groups.dedup();
+
+    Ok(Some(Impersonation {
+        user: user_name,
+        groups,
+    }))
+}
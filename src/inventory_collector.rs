@@ -0,0 +1,401 @@
+use anyhow::{Context, Result};
+use futures::stream::{self, StreamExt};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::config::InventoryConfig;
+use crate::credentials::CredentialVault;
+use crate::deprecations;
+use crate::kube_client::KubeClient;
+use crate::models::{
+    Cluster, ClusterStatus, InventoryKind, InventoryStatus, NamespaceDetails, NodeCondition,
+    NodeDetails, OwnerRef, PodDetails, WorkloadDetails,
+};
+use crate::images;
+use crate::repositories::{ClusterRepository, ImageRepository, InventoryRepository, NewInventoryObject};
+use crate::snapshots;
+
+pub(crate) fn str_at(value: &Value, pointer: &str) -> Option<String> {
+    value.pointer(pointer).and_then(Value::as_str).map(str::to_string)
+}
+
+pub(crate) fn i64_at(value: &Value, pointer: &str) -> Option<i64> {
+    value.pointer(pointer).and_then(Value::as_i64)
+}
+
+pub(crate) fn string_map(value: &Value, pointer: &str) -> BTreeMap<String, String> {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_object)
+        .map(|map| {
+            map.iter()
+                .filter_map(|(k, v)| v.as_str().map(|v| (k.clone(), v.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn array_at<'a>(value: &'a Value, pointer: &str) -> &'a [Value] {
+    value
+        .pointer(pointer)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Images of all containers (init containers included) in a pod spec.
+fn images(pod_spec: &Value) -> Vec<String> {
+    let mut images: Vec<String> = ["/initContainers", "/containers"]
+        .iter()
+        .flat_map(|p| array_at(pod_spec, p))
+        .filter_map(|c| str_at(c, "/image"))
+        .collect();
+    // The same image often appears in an init container and a container
+    images.sort();
+    images.dedup();
+    images
+}
+
+fn node_details(item: &Value) -> NodeDetails {
+    let conditions: Vec<NodeCondition> = array_at(item, "/status/conditions")
+        .iter()
+        .map(|c| NodeCondition {
+            condition_type: str_at(c, "/type").unwrap_or_default(),
+            status: str_at(c, "/status").unwrap_or_default(),
+            reason: str_at(c, "/reason"),
+            message: str_at(c, "/message"),
+        })
+        .collect();
+    NodeDetails {
+        capacity: string_map(item, "/status/capacity"),
+        allocatable: string_map(item, "/status/allocatable"),
+        kubelet_version: str_at(item, "/status/nodeInfo/kubeletVersion"),
+        os_image: str_at(item, "/status/nodeInfo/osImage"),
+        operating_system: str_at(item, "/status/nodeInfo/operatingSystem"),
+        architecture: str_at(item, "/status/nodeInfo/architecture"),
+        kernel_version: str_at(item, "/status/nodeInfo/kernelVersion"),
+        container_runtime: str_at(item, "/status/nodeInfo/containerRuntimeVersion"),
+        unschedulable: item
+            .pointer("/spec/unschedulable")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        ready: conditions
+            .iter()
+            .any(|c| c.condition_type == "Ready" && c.status == "True"),
+        conditions,
+    }
+}
+
+fn workload_details(kind: InventoryKind, item: &Value) -> WorkloadDetails {
+    let base = WorkloadDetails {
+        selector: string_map(item, "/spec/selector/matchLabels"),
+        images: images(item.pointer("/spec/template/spec").unwrap_or(&Value::Null)),
+        ..Default::default()
+    };
+    match kind {
+        InventoryKind::DaemonSet => WorkloadDetails {
+            desired: i64_at(item, "/status/desiredNumberScheduled"),
+            ready: i64_at(item, "/status/numberReady"),
+            available: i64_at(item, "/status/numberAvailable"),
+            updated: i64_at(item, "/status/updatedNumberScheduled"),
+            ..base
+        },
+        InventoryKind::Job => WorkloadDetails {
+            desired: i64_at(item, "/spec/completions"),
+            ready: i64_at(item, "/status/ready"),
+            succeeded: Some(i64_at(item, "/status/succeeded").unwrap_or(0)),
+            failed: Some(i64_at(item, "/status/failed").unwrap_or(0)),
+            active: Some(i64_at(item, "/status/active").unwrap_or(0)),
+            ..base
+        },
+        InventoryKind::CronJob => WorkloadDetails {
+            schedule: str_at(item, "/spec/schedule"),
+            suspend: Some(
+                item.pointer("/spec/suspend")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            ),
+            last_schedule_time: str_at(item, "/status/lastScheduleTime"),
+            active: Some(array_at(item, "/status/active").len() as i64),
+            selector: BTreeMap::new(),
+            images: images(
+                item.pointer("/spec/jobTemplate/spec/template/spec")
+                    .unwrap_or(&Value::Null),
+            ),
+            ..base
+        },
+        // Deployments and StatefulSets
+        _ => WorkloadDetails {
+            desired: Some(i64_at(item, "/spec/replicas").unwrap_or(1)),
+            ready: Some(i64_at(item, "/status/readyReplicas").unwrap_or(0)),
+            available: i64_at(item, "/status/availableReplicas"),
+            updated: i64_at(item, "/status/updatedReplicas"),
+            ..base
+        },
+    }
+}
+
+fn pod_details(item: &Value) -> PodDetails {
+    let statuses = array_at(item, "/status/containerStatuses");
+    PodDetails {
+        phase: str_at(item, "/status/phase"),
+        node_name: str_at(item, "/spec/nodeName"),
+        pod_ip: str_at(item, "/status/podIP"),
+        ready: array_at(item, "/status/conditions")
+            .iter()
+            .any(|c| str_at(c, "/type").as_deref() == Some("Ready") && str_at(c, "/status").as_deref() == Some("True")),
+        restarts: statuses
+            .iter()
+            .filter_map(|s| i64_at(s, "/restartCount"))
+            .sum(),
+        owner: array_at(item, "/metadata/ownerReferences")
+            .iter()
+            .find(|o| o.pointer("/controller").and_then(Value::as_bool) == Some(true))
+            .map(|o| OwnerRef {
+                kind: str_at(o, "/kind").unwrap_or_default(),
+                name: str_at(o, "/name").unwrap_or_default(),
+            }),
+        images: images(item.pointer("/spec").unwrap_or(&Value::Null)),
+    }
+}
+
+/// Maps a raw Kubernetes object to its stored form.
+pub fn normalize(kind: InventoryKind, item: &Value) -> Result<NewInventoryObject> {
+    let details = match kind {
+        InventoryKind::Node => serde_json::to_value(node_details(item))?,
+        InventoryKind::Namespace => serde_json::to_value(NamespaceDetails {
+            phase: str_at(item, "/status/phase"),
+        })?,
+        InventoryKind::Pod => serde_json::to_value(pod_details(item))?,
+        _ => serde_json::to_value(workload_details(kind, item))?,
+    };
+    Ok(NewInventoryObject {
+        namespace: str_at(item, "/metadata/namespace").filter(|_| kind.is_namespaced()),
+        name: str_at(item, "/metadata/name").unwrap_or_default(),
+        uid: str_at(item, "/metadata/uid").unwrap_or_default(),
+        labels: string_map(item, "/metadata/labels"),
+        details,
+        created_at: str_at(item, "/metadata/creationTimestamp")
+            .and_then(|t| chrono::DateTime::parse_from_rfc3339(&t).ok())
+            .map(|t| t.with_timezone(&chrono::Utc)),
+        manifest: snapshots::manifest(item),
+        api_versions: deprecations::applied_api_versions(item),
+    })
+}
+
+/// Periodically collects nodes, namespaces and workloads from active clusters.
+#[derive(Clone)]
+pub struct InventoryCollector {
+    clusters: ClusterRepository,
+    inventory: InventoryRepository,
+    images: ImageRepository,
+    credentials: CredentialVault,
+    config: InventoryConfig,
+}
+
+impl InventoryCollector {
+    pub fn new(
+        clusters: ClusterRepository,
+        inventory: InventoryRepository,
+        images: ImageRepository,
+        credentials: CredentialVault,
+        config: InventoryConfig,
+    ) -> Self {
+        Self {
+            clusters,
+            inventory,
+            images,
+            credentials,
+            config,
+        }
+    }
+
+    /// Collects every kind; a kind that fails keeps its previous objects and
+    /// the error is recorded in the collection status.
+    pub async fn collect_cluster(&self, cluster: &Cluster) -> Result<InventoryStatus> {
+        self.inventory.start_collection(cluster.id).await?;
+        let mut errors = BTreeMap::new();
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        match self.credentials.client(cluster, timeout, Some(timeout)).await {
+            Ok(client) => {
+                for kind in InventoryKind::ALL {
+                    if let Err(e) = self.collect_kind(cluster, &client, kind).await {
+                        warn!("Inventory of {} in '{}' failed: {:#}", kind.as_str(), cluster.name, e);
+                        errors.insert(kind.as_str().to_string(), format!("{:#}", e));
+                    }
+                }
+            }
+            Err(e) => {
+                errors.insert("client".to_string(), format!("{:#}", e));
+            }
+        }
+
+        self.inventory.finish_collection(cluster.id, &errors).await?;
+        let status = self.inventory.status(cluster.id).await?;
+        status.ok_or_else(|| anyhow::anyhow!("inventory status of '{}' disappeared", cluster.id))
+    }
+
+    async fn collect_kind(&self, cluster: &Cluster, client: &KubeClient, kind: InventoryKind) -> Result<()> {
+        let items = client.list_all(&kind.collection_path(None)?, self.config.page_size).await?;
+        let objects = items
+            .iter()
+            .map(|item| normalize(kind, item))
+            .collect::<Result<Vec<_>>>()?;
+        self.inventory.replace_kind(cluster.id, kind, &objects).await?;
+        if kind == InventoryKind::Pod {
+            self.images
+                .record(cluster.id, &images::sightings(&items))
+                .await
+                .context("recording container images failed")?;
+        }
+        Ok(())
+    }
+
+    pub async fn collect_all(&self) -> Result<()> {
+        // Unreachable or failing clusters are left to the health prober
+        let clusters: Vec<Cluster> = self
+            .clusters
+            .list()
+            .await?
+            .into_iter()
+            .filter(|c| c.status == ClusterStatus::Active)
+            .collect();
+        stream::iter(clusters)
+            .for_each_concurrent(self.config.concurrency, |cluster| async move {
+                match self.collect_cluster(&cluster).await {
+                    Ok(status) => info!(
+                        "Inventory of '{}' collected: {:?}",
+                        cluster.name, status.counts
+                    ),
+                    Err(e) => warn!("Inventory collection of '{}' failed: {}", cluster.name, e),
+                }
+            })
+            .await;
+        Ok(())
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.collect_all().await {
+                    warn!("Inventory collection round failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn images_are_deduplicated_across_containers() {
+        let spec = json!({
+            "initContainers": [{ "image": "busybox:1.36" }, { "image": "app:1.0" }],
+            "containers": [{ "image": "app:1.0" }, { "image": "sidecar:2" }, { "image": "busybox:1.36" }]
+        });
+        assert_eq!(images(&spec), vec!["app:1.0", "busybox:1.36", "sidecar:2"]);
+        assert!(images(&Value::Null).is_empty());
+    }
+
+    #[test]
+    fn normalizes_pods() {
+        let pod = json!({
+            "apiVersion": "v1",
+            "kind": "Pod",
+            "metadata": {
+                "name": "web-7d9f-abc",
+                "namespace": "shop",
+                "uid": "uid-1",
+                "labels": { "app": "web" },
+                "creationTimestamp": "2026-10-01T12:00:00Z",
+                "ownerReferences": [
+                    { "kind": "Node", "name": "n1" },
+                    { "kind": "ReplicaSet", "name": "web-7d9f", "controller": true }
+                ]
+            },
+            "spec": { "nodeName": "n1", "containers": [{ "image": "web:1" }] },
+            "status": {
+                "phase": "Running",
+                "podIP": "10.0.0.5",
+                "conditions": [{ "type": "Ready", "status": "True" }],
+                "containerStatuses": [{ "restartCount": 2 }, { "restartCount": 1 }]
+            }
+        });
+        let object = normalize(InventoryKind::Pod, &pod).unwrap();
+        assert_eq!(object.namespace.as_deref(), Some("shop"));
+        assert_eq!(object.name, "web-7d9f-abc");
+        assert_eq!(object.uid, "uid-1");
+        assert_eq!(object.labels.get("app").map(String::as_str), Some("web"));
+        assert_eq!(object.created_at.unwrap().to_rfc3339(), "2026-10-01T12:00:00+00:00");
+        assert_eq!(
+            object.details,
+            json!({
+                "phase": "Running",
+                "node_name": "n1",
+                "pod_ip": "10.0.0.5",
+                "ready": true,
+                "restarts": 3,
+                "owner": { "kind": "ReplicaSet", "name": "web-7d9f" },
+                "images": ["web:1"]
+            })
+        );
+    }
+
+    #[test]
+    fn cluster_scoped_kinds_have_no_namespace() {
+        let node = json!({
+            "metadata": { "name": "n1", "namespace": "ignored" },
+            "status": {
+                "nodeInfo": { "kubeletVersion": "v1.29.3" },
+                "conditions": [{ "type": "Ready", "status": "False", "reason": "KubeletNotReady" }]
+            }
+        });
+        let object = normalize(InventoryKind::Node, &node).unwrap();
+        assert_eq!(object.namespace, None);
+        assert_eq!(object.details["kubelet_version"], "v1.29.3");
+        assert_eq!(object.details["ready"], false);
+        assert_eq!(object.details["unschedulable"], false);
+    }
+
+    #[test]
+    fn normalizes_workloads_by_kind() {
+        let deployment = json!({
+            "metadata": { "name": "web", "namespace": "shop" },
+            "spec": {
+                "selector": { "matchLabels": { "app": "web" } },
+                "template": { "spec": { "containers": [{ "image": "web:1" }] } }
+            },
+            "status": { "availableReplicas": 1 }
+        });
+        let details = normalize(InventoryKind::Deployment, &deployment).unwrap().details;
+        assert_eq!(details["desired"], 1);
+        assert_eq!(details["ready"], 0);
+        assert_eq!(details["available"], 1);
+        assert_eq!(details["selector"], json!({ "app": "web" }));
+        assert_eq!(details["images"], json!(["web:1"]));
+
+        let cronjob = json!({
+            "metadata": { "name": "backup", "namespace": "ops" },
+            "spec": {
+                "schedule": "0 3 * * *",
+                "jobTemplate": { "spec": { "template": { "spec": { "containers": [{ "image": "backup:2" }] } } } }
+            },
+            "status": { "active": [{ "name": "backup-1" }] }
+        });
+        let details = normalize(InventoryKind::CronJob, &cronjob).unwrap().details;
+        assert_eq!(details["schedule"], "0 3 * * *");
+        assert_eq!(details["suspend"], false);
+        assert_eq!(details["active"], 1);
+        assert_eq!(details["images"], json!(["backup:2"]));
+    }
+}
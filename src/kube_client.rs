@@ -0,0 +1,652 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64_URL, Engine};
+use reqwest::{Certificate, Client, ClientBuilder, Identity, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use std::time::Duration;
+use tokio_tungstenite::{
+    tungstenite::{
+        handshake::{client::generate_key, derive_accept_key},
+        protocol::Role,
+    },
+    WebSocketStream,
+};
+
+use crate::models::{ClusterAuth, ClusterCredential};
+use crate::validation::is_dns_label;
+
+/// A non-2xx answer from the API server, kept typed so callers can tell
+/// rejected credentials from missing objects.
+#[derive(Debug, thiserror::Error)]
+#[error("API server returned {status}: {message}")]
+pub struct KubeApiError {
+    pub status: StatusCode,
+    pub message: String,
+}
+
+/// `/version` response of the API server.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KubeVersion {
+    pub git_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcDiscovery {
+    token_endpoint: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OidcTokenResponse {
+    id_token: Option<String>,
+    access_token: String,
+    /// Issuers that rotate refresh tokens return the one to use next time
+    refresh_token: Option<String>,
+}
+
+/// ID tokens are reused until this close to their expiry.
+const TOKEN_EXPIRY_MARGIN_SECS: i64 = 60;
+
+/// Identity sent as `Impersonate-User` / `Impersonate-Group`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impersonation {
+    pub user: String,
+    pub groups: Vec<String>,
+}
+
+/// Minimal HTTP client for one cluster's API server, built from the stored credential.
+#[derive(Clone)]
+pub struct KubeClient {
+    http: Client,
+    base_url: String,
+    bearer: Option<String>,
+    impersonation: Option<Impersonation>,
+}
+
+impl std::fmt::Debug for KubeClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KubeClient")
+            .field("base_url", &self.base_url)
+            .field("impersonation", &self.impersonation)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A JWT's `exp`. The token is not verified here; the API server does that.
+fn token_expiry(token: &str) -> Option<i64> {
+    token
+        .split('.')
+        .nth(1)
+        .and_then(|payload| BASE64_URL.decode(payload).ok())
+        .and_then(|payload| serde_json::from_slice::<serde_json::Value>(&payload).ok())
+        .and_then(|claims| claims.get("exp").and_then(|exp| exp.as_i64()))
+}
+
+/// Whether a JWT's `exp` is far enough away to keep using it.
+fn token_is_fresh(token: &str) -> bool {
+    token_expiry(token).is_some_and(|exp| exp - TOKEN_EXPIRY_MARGIN_SECS > chrono::Utc::now().timestamp())
+}
+
+/// Exchanges an OIDC refresh token at the issuer's token endpoint.
+async fn refresh_oidc_token(
+    http: &Client,
+    issuer_url: &str,
+    client_id: &str,
+    client_secret: Option<&str>,
+    refresh_token: &str,
+) -> Result<OidcTokenResponse> {
+    let discovery_url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer_url.trim_end_matches('/')
+    );
+    let discovery: OidcDiscovery = http
+        .get(&discovery_url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await
+        .context("OIDC discovery failed")?;
+
+    let mut form = vec![
+        ("grant_type", "refresh_token"),
+        ("refresh_token", refresh_token),
+        ("client_id", client_id),
+    ];
+    if let Some(secret) = client_secret {
+        form.push(("client_secret", secret));
+    }
+    let response = http.post(&discovery.token_endpoint).form(&form).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("OIDC token refresh failed with {}", response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+/// reqwest cannot set the TLS server name on its own, so requests go to
+/// `server_name` and that name resolves to the endpoint's addresses: the
+/// serving certificate is then verified against `server_name`, as with
+/// kubectl's `tls-server-name`. Returns the base URL to use.
+async fn override_server_name(
+    builder: ClientBuilder,
+    endpoint: &str,
+    server_name: &str,
+) -> Result<(ClientBuilder, String)> {
+    let mut url = Url::parse(endpoint).context("invalid endpoint")?;
+    let host = url.host_str().ok_or_else(|| anyhow!("endpoint has no host"))?.to_string();
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| anyhow!("endpoint has no port"))?;
+    let addrs: Vec<_> = tokio::net::lookup_host((host.trim_matches(['[', ']']), port))
+        .await
+        .with_context(|| format!("failed to resolve '{}'", host))?
+        .collect();
+    url.set_host(Some(server_name))
+        .map_err(|e| anyhow!("invalid TLS server name '{}': {}", server_name, e))?;
+    Ok((
+        builder.resolve_to_addrs(server_name, &addrs),
+        url.as_str().trim_end_matches('/').to_string(),
+    ))
+}
+
+/// Path of the `v1` collection of `resource` in `api_group` (empty for the
+/// core group), across all namespaces unless `namespace` is given. A
+/// namespace that is not a DNS-1123 label is rejected so it cannot add
+/// segments to the path.
+pub fn collection_path(api_group: &str, resource: &str, namespace: Option<&str>) -> Result<String> {
+    let prefix = match api_group {
+        "" => "/api/v1".to_string(),
+        group => format!("/apis/{}/v1", group),
+    };
+    match namespace {
+        Some(namespace) if !is_dns_label(namespace) => {
+            Err(anyhow!("invalid namespace '{}': must be a DNS-1123 label", namespace))
+        }
+        Some(namespace) => Ok(format!("{}/namespaces/{}/{}", prefix, namespace, resource)),
+        None => Ok(format!("{}/{}", prefix, resource)),
+    }
+}
+
+/// Turns a non-2xx response into `KubeApiError`.
+pub async fn check_status(response: Response) -> Result<Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    // Kubernetes returns a `Status` object with a human readable message
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or_else(|| body.trim().chars().take(200).collect());
+    Err(KubeApiError { status, message }.into())
+}
+
+impl KubeClient {
+    /// Builds a client; with `timeout` requests, bodies included, must finish
+    /// in time, without it (watches, log streams) only connecting is bounded.
+    ///
+    /// An OIDC credential's ID token is used while it is fresh; otherwise it
+    /// is refreshed, and the credential with the new ID token and the
+    /// possibly rotated refresh token is returned for the caller to store.
+    pub async fn connect(
+        endpoint: &str,
+        credential: Option<&ClusterCredential>,
+        connect_timeout: Duration,
+        timeout: Option<Duration>,
+    ) -> Result<(Self, Option<ClusterCredential>)> {
+        let default_credential = ClusterCredential::default();
+        let credential = credential.unwrap_or(&default_credential);
+
+        let mut builder = Client::builder().connect_timeout(connect_timeout);
+        if let Some(timeout) = timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(ca) = &credential.ca_bundle_pem {
+            for cert in Certificate::from_pem_bundle(ca.as_bytes()).context("invalid CA bundle")? {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if credential.insecure_skip_tls_verify {
+            builder = builder.danger_accept_invalid_certs(true);
+        }
+        if let ClusterAuth::ClientCertificate { certificate_pem, key_pem } = &credential.auth {
+            let pem = format!("{}\n{}", certificate_pem.trim_end(), key_pem);
+            builder = builder
+                .identity(Identity::from_pem(pem.as_bytes()).context("invalid client certificate")?);
+        }
+        let mut base_url = endpoint.trim_end_matches('/').to_string();
+        if let Some(server_name) = &credential.tls_server_name {
+            (builder, base_url) = override_server_name(builder, endpoint, server_name).await?;
+        }
+        let http = builder.build()?;
+
+        let mut refreshed = None;
+        let bearer = match &credential.auth {
+            ClusterAuth::Token { token } => Some(token.clone()),
+            ClusterAuth::Oidc { id_token: Some(id_token), .. } if token_is_fresh(id_token) => {
+                Some(id_token.clone())
+            }
+            ClusterAuth::Oidc {
+                issuer_url,
+                client_id,
+                client_secret,
+                refresh_token: Some(refresh_token),
+                ..
+            } => {
+                let tokens = refresh_oidc_token(
+                    &http,
+                    issuer_url,
+                    client_id,
+                    client_secret.as_deref(),
+                    refresh_token,
+                )
+                .await?;
+                refreshed = Some(ClusterCredential {
+                    auth: ClusterAuth::Oidc {
+                        issuer_url: issuer_url.clone(),
+                        client_id: client_id.clone(),
+                        client_secret: client_secret.clone(),
+                        refresh_token: tokens.refresh_token.or_else(|| Some(refresh_token.clone())),
+                        id_token: tokens.id_token.clone(),
+                    },
+                    ..credential.clone()
+                });
+                // Kubernetes authenticates OIDC users by the ID token
+                Some(tokens.id_token.unwrap_or(tokens.access_token))
+            }
+            ClusterAuth::Oidc { id_token, .. } => id_token.clone(),
+            ClusterAuth::None | ClusterAuth::ClientCertificate { .. } => None,
+        };
+
+        let client = Self {
+            http,
+            base_url,
+            bearer,
+            impersonation: None,
+        };
+        Ok((client, refreshed))
+    }
+
+    /// How much longer the bearer token may be sent, for tokens with an
+    /// `exp`; zero once it is within `TOKEN_EXPIRY_MARGIN_SECS` of expiring.
+    pub fn bearer_lifetime(&self) -> Option<Duration> {
+        let exp = token_expiry(self.bearer.as_deref()?)?;
+        let remaining = exp - TOKEN_EXPIRY_MARGIN_SECS - chrono::Utc::now().timestamp();
+        Some(Duration::from_secs(remaining.max(0) as u64))
+    }
+
+    /// The same client, acting as `impersonation` on every request.
+    pub fn impersonating(&self, impersonation: Impersonation) -> Self {
+        Self {
+            impersonation: Some(impersonation),
+            ..self.clone()
+        }
+    }
+
+    /// Starts a request to `path` (e.g. `/api/v1/nodes`) with authentication applied.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+        if let Some(token) = &self.bearer {
+            request = request.bearer_auth(token);
+        }
+        if let Some(impersonation) = &self.impersonation {
+            request = request.header("Impersonate-User", &impersonation.user);
+            for group in &impersonation.groups {
+                request = request.header("Impersonate-Group", group);
+            }
+        }
+        request
+    }
+
+    /// Opens a WebSocket to a streaming subresource such as `pods/exec`,
+    /// negotiating `protocol`. Fails with `KubeApiError` if the API server
+    /// answers with an error instead of switching protocols.
+    pub async fn websocket(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        protocol: &str,
+    ) -> Result<WebSocketStream<reqwest::Upgraded>> {
+        let key = generate_key();
+        let response = self
+            .request(Method::GET, path)
+            .query(query)
+            .header("Connection", "Upgrade")
+            .header("Upgrade", "websocket")
+            .header("Sec-WebSocket-Version", "13")
+            .header("Sec-WebSocket-Key", &key)
+            .header("Sec-WebSocket-Protocol", protocol)
+            .send()
+            .await?;
+        if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+            let status = response.status();
+            check_status(response).await?;
+            return Err(anyhow!("API server answered {} instead of switching protocols", status));
+        }
+        let accept = response
+            .headers()
+            .get("sec-websocket-accept")
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or_default();
+        if accept != derive_accept_key(key.as_bytes()) {
+            return Err(anyhow!("API server sent an invalid WebSocket accept key"));
+        }
+        let upgraded = response.upgrade().await?;
+        Ok(WebSocketStream::from_raw_socket(upgraded, Role::Client, None).await)
+    }
+
+    /// Sends a GET and fails with `KubeApiError` on a non-2xx status.
+    pub async fn get(&self, path: &str) -> Result<Response> {
+        check_status(self.request(Method::GET, path).send().await?).await
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T> {
+        Ok(self.get(path).await?.json().await?)
+    }
+
+    /// Lists all items of a collection (e.g. `/api/v1/pods`), following `continue` tokens.
+    pub async fn list_all(&self, path: &str, page_size: usize) -> Result<Vec<serde_json::Value>> {
+        Ok(self.list_with_version(path, &[], page_size).await?.0)
+    }
+
+    /// Like `list_all` with extra query parameters (e.g. `labelSelector`); also
+    /// returns the list's `resourceVersion`, from which a watch can continue.
+    pub async fn list_with_version(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+        page_size: usize,
+    ) -> Result<(Vec<serde_json::Value>, String)> {
+        let mut items = Vec::new();
+        let mut continue_token: Option<String> = None;
+        loop {
+            let mut request = self
+                .request(Method::GET, path)
+                .query(query)
+                .query(&[("limit", page_size.to_string())]);
+            if let Some(token) = &continue_token {
+                request = request.query(&[("continue", token)]);
+            }
+            let response = request.send().await?;
+            let page: serde_json::Value = check_status(response).await?.json().await?;
+            if let Some(page_items) = page.get("items").and_then(|i| i.as_array()) {
+                items.extend(page_items.iter().cloned());
+            }
+            continue_token = page
+                .pointer("/metadata/continue")
+                .and_then(|c| c.as_str())
+                .filter(|c| !c.is_empty())
+                .map(str::to_string);
+            if continue_token.is_none() {
+                let resource_version = page
+                    .pointer("/metadata/resourceVersion")
+                    .and_then(|v| v.as_str())
+                    .unwrap_or_default()
+                    .to_string();
+                return Ok((items, resource_version));
+            }
+        }
+    }
+
+    pub async fn version(&self) -> Result<KubeVersion> {
+        self.get_json("/version").await
+    }
+}
+
+/// Serves a router on a random local port, standing in for an API server or
+/// an OIDC issuer.
+#[cfg(test)]
+pub(crate) mod fake_server {
+    pub async fn spawn(router: axum::Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        format!("http://{}", address)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::Query,
+        http::{HeaderMap, StatusCode as AxumStatus},
+        routing::{get, post},
+        Form, Json, Router,
+    };
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn collection_paths() {
+        assert_eq!(collection_path("", "pods", None).unwrap(), "/api/v1/pods");
+        assert_eq!(
+            collection_path("apps", "deployments", Some("shop")).unwrap(),
+            "/apis/apps/v1/namespaces/shop/deployments"
+        );
+        for namespace in ["", "../secrets", "shop/pods", "shop?x=1", "Shop"] {
+            assert!(collection_path("", "pods", Some(namespace)).is_err(), "{}", namespace);
+        }
+    }
+
+    fn header(headers: &HeaderMap, name: &str) -> String {
+        headers.get_all(name).iter().map(|v| v.to_str().unwrap()).collect::<Vec<_>>().join(",")
+    }
+
+    fn jwt(exp: i64) -> String {
+        let payload = BASE64_URL.encode(json!({ "exp": exp }).to_string());
+        format!("e30.{}.sig", payload)
+    }
+
+    fn token_credential(token: &str) -> ClusterCredential {
+        ClusterCredential {
+            auth: ClusterAuth::Token { token: token.to_string() },
+            ..Default::default()
+        }
+    }
+
+    fn oidc_credential(issuer_url: &str, id_token: Option<String>) -> ClusterCredential {
+        ClusterCredential {
+            auth: ClusterAuth::Oidc {
+                issuer_url: issuer_url.to_string(),
+                client_id: "kubernetes".to_string(),
+                client_secret: None,
+                refresh_token: Some("refresh-1".to_string()),
+                id_token,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// `/whoami` echoes the headers the API server would authenticate with;
+    /// the issuer rotates `refresh-1` to `refresh-2`.
+    async fn spawn_server(token_requests: Arc<AtomicUsize>) -> String {
+        let router = Router::new()
+            .route(
+                "/whoami",
+                get(|headers: HeaderMap| async move {
+                    Json(json!({
+                        "authorization": header(&headers, "authorization"),
+                        "user": header(&headers, "impersonate-user"),
+                        "groups": header(&headers, "impersonate-group"),
+                        "host": header(&headers, "host"),
+                    }))
+                }),
+            )
+            .route(
+                "/api/v1/pods",
+                get(|Query(query): Query<HashMap<String, String>>| async move {
+                    let limit = query.get("limit").cloned().unwrap_or_default();
+                    match query.get("continue").map(String::as_str) {
+                        None => Json(json!({
+                            "metadata": { "continue": "page-2", "resourceVersion": "10" },
+                            "items": [{ "limit": limit }]
+                        })),
+                        Some(_) => Json(json!({
+                            "metadata": { "resourceVersion": "11" },
+                            "items": [{ "page": 2 }]
+                        })),
+                    }
+                }),
+            )
+            .route(
+                "/api/v1/namespaces/missing",
+                get(|| async {
+                    (
+                        AxumStatus::NOT_FOUND,
+                        Json(json!({ "kind": "Status", "message": "namespaces \"missing\" not found" })),
+                    )
+                }),
+            )
+            .route(
+                "/.well-known/openid-configuration",
+                get(|headers: HeaderMap| async move {
+                    Json(json!({ "token_endpoint": format!("http://{}/token", header(&headers, "host")) }))
+                }),
+            )
+            .route(
+                "/token",
+                post(move |Form(form): Form<HashMap<String, String>>| {
+                    let token_requests = token_requests.clone();
+                    async move {
+                        token_requests.fetch_add(1, Ordering::SeqCst);
+                        if form.get("refresh_token").map(String::as_str) != Some("refresh-1") {
+                            return (AxumStatus::BAD_REQUEST, Json(json!({ "error": "invalid_grant" })));
+                        }
+                        (
+                            AxumStatus::OK,
+                            Json(json!({
+                                "access_token": "access",
+                                "id_token": "id-2",
+                                "refresh_token": "refresh-2"
+                            })),
+                        )
+                    }
+                }),
+            );
+        fake_server::spawn(router).await
+    }
+
+    async fn whoami(client: &KubeClient) -> serde_json::Value {
+        client.get_json("/whoami").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn sends_bearer_token_and_impersonation() {
+        let url = spawn_server(Arc::default()).await;
+        let (client, refreshed) = KubeClient::connect(&url, Some(&token_credential("abc")), TIMEOUT, Some(TIMEOUT))
+            .await
+            .unwrap();
+        assert!(refreshed.is_none());
+        assert_eq!(whoami(&client).await["authorization"], "Bearer abc");
+
+        let client = client.impersonating(Impersonation {
+            user: "oidc:alice".to_string(),
+            groups: vec!["oidc:dev".to_string(), "oidc:ops".to_string()],
+        });
+        let seen = whoami(&client).await;
+        assert_eq!(seen["user"], "oidc:alice");
+        assert_eq!(seen["groups"], "oidc:dev,oidc:ops");
+    }
+
+    #[tokio::test]
+    async fn list_follows_continue_tokens() {
+        let url = spawn_server(Arc::default()).await;
+        let (client, _) = KubeClient::connect(&url, None, TIMEOUT, Some(TIMEOUT)).await.unwrap();
+        let (items, resource_version) = client.list_with_version("/api/v1/pods", &[], 50).await.unwrap();
+        assert_eq!(items, vec![json!({ "limit": "50" }), json!({ "page": 2 })]);
+        assert_eq!(resource_version, "11");
+    }
+
+    #[tokio::test]
+    async fn api_errors_carry_status_and_message() {
+        let url = spawn_server(Arc::default()).await;
+        let (client, _) = KubeClient::connect(&url, None, TIMEOUT, Some(TIMEOUT)).await.unwrap();
+        let error = client.get("/api/v1/namespaces/missing").await.unwrap_err();
+        let api = error.downcast_ref::<KubeApiError>().unwrap();
+        assert_eq!(api.status, StatusCode::NOT_FOUND);
+        assert_eq!(api.message, "namespaces \"missing\" not found");
+    }
+
+    #[tokio::test]
+    async fn oidc_refresh_returns_rotated_credential() {
+        let token_requests = Arc::new(AtomicUsize::new(0));
+        let url = spawn_server(token_requests.clone()).await;
+        let credential = oidc_credential(&url, Some(jwt(chrono::Utc::now().timestamp() - 10)));
+        let (client, refreshed) = KubeClient::connect(&url, Some(&credential), TIMEOUT, Some(TIMEOUT))
+            .await
+            .unwrap();
+        assert_eq!(token_requests.load(Ordering::SeqCst), 1);
+        assert_eq!(whoami(&client).await["authorization"], "Bearer id-2");
+        match refreshed.unwrap().auth {
+            ClusterAuth::Oidc { refresh_token, id_token, .. } => {
+                assert_eq!(refresh_token.as_deref(), Some("refresh-2"));
+                assert_eq!(id_token.as_deref(), Some("id-2"));
+            }
+            other => panic!("unexpected auth {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_oidc_id_token_is_used_without_refresh() {
+        let token_requests = Arc::new(AtomicUsize::new(0));
+        let url = spawn_server(token_requests.clone()).await;
+        let id_token = jwt(chrono::Utc::now().timestamp() + 3600);
+        let credential = oidc_credential(&url, Some(id_token.clone()));
+        let (client, refreshed) = KubeClient::connect(&url, Some(&credential), TIMEOUT, Some(TIMEOUT))
+            .await
+            .unwrap();
+        assert!(refreshed.is_none());
+        assert_eq!(token_requests.load(Ordering::SeqCst), 0);
+        assert_eq!(whoami(&client).await["authorization"], format!("Bearer {}", id_token));
+    }
+
+    #[tokio::test]
+    async fn tls_server_name_is_requested_at_the_endpoint_address() {
+        let url = spawn_server(Arc::default()).await;
+        let port = url.rsplit(':').next().unwrap().to_string();
+        let credential = ClusterCredential {
+            tls_server_name: Some("api.cluster.test".to_string()),
+            ..Default::default()
+        };
+        let (client, _) = KubeClient::connect(&url, Some(&credential), TIMEOUT, Some(TIMEOUT))
+            .await
+            .unwrap();
+        assert_eq!(whoami(&client).await["host"], format!("api.cluster.test:{}", port));
+    }
+
+    #[test]
+    fn token_freshness_reads_exp() {
+        let now = chrono::Utc::now().timestamp();
+        assert!(token_is_fresh(&jwt(now + 3600)));
+        assert!(!token_is_fresh(&jwt(now + 10)));
+        assert!(!token_is_fresh("not-a-jwt"));
+    }
+
+    #[tokio::test]
+    async fn bearer_lifetime_stops_short_of_exp() {
+        let lifetime = |credential: Option<ClusterCredential>| async move {
+            let (client, _) = KubeClient::connect("https://k8s.example.com", credential.as_ref(), TIMEOUT, None)
+                .await
+                .unwrap();
+            client.bearer_lifetime()
+        };
+        let now = chrono::Utc::now().timestamp();
+        let remaining = lifetime(Some(token_credential(&jwt(now + 600)))).await.unwrap();
+        assert!((Duration::from_secs(535)..=Duration::from_secs(540)).contains(&remaining), "{:?}", remaining);
+        // Without a refresh token a nearly expired ID token is still sent
+        let mut stale = oidc_credential("https://idp.example.com", Some(jwt(now + 30)));
+        if let ClusterAuth::Oidc { refresh_token, .. } = &mut stale.auth {
+            *refresh_token = None;
+        }
+        assert_eq!(lifetime(Some(stale)).await, Some(Duration::ZERO));
+        assert_eq!(lifetime(Some(token_credential("opaque"))).await, None);
+        assert_eq!(lifetime(None).await, None);
+    }
+}
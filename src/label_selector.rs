@@ -0,0 +1,276 @@
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// One requirement of a Kubernetes label selector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Requirement {
+    Equals(String, String),
+    NotEquals(String, String),
+    In(String, Vec<String>),
+    NotIn(String, Vec<String>),
+    Exists(String),
+    DoesNotExist(String),
+}
+
+/// A parsed selector such as `app=web,tier in (frontend,edge),!canary`.
+/// All requirements must match (logical AND), as in Kubernetes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LabelSelector {
+    pub requirements: Vec<Requirement>,
+}
+
+fn is_valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 317
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-' | '/'))
+}
+
+fn is_valid_value(value: &str) -> bool {
+    value.len() <= 63
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+}
+
+fn key(raw: &str) -> Result<String> {
+    let key = raw.trim();
+    if !is_valid_key(key) {
+        return Err(anyhow!("invalid label key '{}'", key));
+    }
+    Ok(key.to_string())
+}
+
+fn value(raw: &str) -> Result<String> {
+    let value = raw.trim();
+    if !is_valid_value(value) {
+        return Err(anyhow!("invalid label value '{}'", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Splits on commas that are not inside a `( ... )` value list.
+fn split_terms(selector: &str) -> Result<Vec<&str>> {
+    let mut terms = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| anyhow!("unbalanced ')' in label selector"))?
+            }
+            ',' if depth == 0 => {
+                terms.push(&selector[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return Err(anyhow!("unbalanced '(' in label selector"));
+    }
+    terms.push(&selector[start..]);
+    Ok(terms)
+}
+
+fn parse_set(key_part: &str, rest: &str) -> Result<(String, Vec<String>)> {
+    let rest = rest.trim();
+    let list = rest
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .ok_or_else(|| anyhow!("expected '(values)' after '{}'", key_part.trim()))?;
+    if list.trim().is_empty() {
+        return Err(anyhow!("empty value list for '{}'", key_part.trim()));
+    }
+    let values = list
+        .split(',')
+        .map(|v| match value(v)? {
+            v if v.is_empty() => Err(anyhow!("empty value in list for '{}'", key_part.trim())),
+            v => Ok(v),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((key(key_part)?, values))
+}
+
+fn parse_term(term: &str) -> Result<Requirement> {
+    let term = term.trim();
+    if let Some(k) = term.strip_prefix('!') {
+        return Ok(Requirement::DoesNotExist(key(k)?));
+    }
+    if let Some((k, v)) = term.split_once("!=") {
+        return Ok(Requirement::NotEquals(key(k)?, value(v)?));
+    }
+    if let Some((k, v)) = term.split_once("==") {
+        return Ok(Requirement::Equals(key(k)?, value(v)?));
+    }
+    if let Some((k, v)) = term.split_once('=') {
+        return Ok(Requirement::Equals(key(k)?, value(v)?));
+    }
+    if let Some((k, rest)) = term.split_once(" notin ") {
+        let (k, values) = parse_set(k, rest)?;
+        return Ok(Requirement::NotIn(k, values));
+    }
+    if let Some((k, rest)) = term.split_once(" in ") {
+        let (k, values) = parse_set(k, rest)?;
+        return Ok(Requirement::In(k, values));
+    }
+    Ok(Requirement::Exists(key(term)?))
+}
+
+impl LabelSelector {
+    pub fn parse(selector: &str) -> Result<Self> {
+        if selector.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let requirements = split_terms(selector)?
+            .into_iter()
+            .map(parse_term)
+            .collect::<Result<Vec<_>>>()
+            .map_err(|e| anyhow!("Invalid label selector: {}", e))?;
+        Ok(Self { requirements })
+    }
+
+    /// Reads a selector object as found in specs (`matchLabels` and
+    /// `matchExpressions`). Values are taken as they are, without validation.
+    pub fn from_spec(spec: &Value) -> Result<Self> {
+        let mut requirements = Vec::new();
+        if let Some(labels) = spec.get("matchLabels").and_then(Value::as_object) {
+            for (k, v) in labels {
+                let v = v.as_str().unwrap_or_default();
+                requirements.push(Requirement::Equals(k.clone(), v.to_string()));
+            }
+        }
+        for expression in spec.get("matchExpressions").and_then(Value::as_array).into_iter().flatten() {
+            let k = expression.get("key").and_then(Value::as_str).unwrap_or_default().to_string();
+            let values: Vec<String> = expression
+                .get("values")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|v| v.as_str().map(str::to_string))
+                .collect();
+            requirements.push(match expression.get("operator").and_then(Value::as_str) {
+                Some("In") => Requirement::In(k, values),
+                Some("NotIn") => Requirement::NotIn(k, values),
+                Some("Exists") => Requirement::Exists(k),
+                Some("DoesNotExist") => Requirement::DoesNotExist(k),
+                other => return Err(anyhow!("unknown selector operator {:?}", other)),
+            });
+        }
+        Ok(Self { requirements })
+    }
+
+    /// An empty selector matches everything.
+    pub fn matches(&self, labels: &BTreeMap<String, String>) -> bool {
+        self.requirements.iter().all(|requirement| match requirement {
+            Requirement::Equals(k, v) => labels.get(k) == Some(v),
+            Requirement::NotEquals(k, v) => labels.get(k) != Some(v),
+            Requirement::In(k, values) => labels.get(k).is_some_and(|l| values.contains(l)),
+            Requirement::NotIn(k, values) => labels.get(k).is_none_or(|l| !values.contains(l)),
+            Requirement::Exists(k) => labels.contains_key(k),
+            Requirement::DoesNotExist(k) => !labels.contains_key(k),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn labels(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    fn s(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_every_operator() {
+        let selector =
+            LabelSelector::parse("app=web, tier==front, env!=dev, zone in (a, b), team notin (x), canary, !legacy")
+                .unwrap();
+        assert_eq!(
+            selector.requirements,
+            vec![
+                Requirement::Equals("app".into(), "web".into()),
+                Requirement::Equals("tier".into(), "front".into()),
+                Requirement::NotEquals("env".into(), "dev".into()),
+                Requirement::In("zone".into(), s(&["a", "b"])),
+                Requirement::NotIn("team".into(), s(&["x"])),
+                Requirement::Exists("canary".into()),
+                Requirement::DoesNotExist("legacy".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_selector_matches_everything() {
+        let selector = LabelSelector::parse("  ").unwrap();
+        assert!(selector.requirements.is_empty());
+        assert!(selector.matches(&labels(&[])));
+    }
+
+    #[test]
+    fn rejects_malformed_selectors() {
+        for selector in [
+            "zone in ()",
+            "zone in (a,)",
+            "zone in (a,,b)",
+            "zone in a",
+            "zone in (a",
+            "zone in a)",
+            "app=web!",
+            "=web",
+            "bad key=web",
+        ] {
+            assert!(LabelSelector::parse(selector).is_err(), "{}", selector);
+        }
+    }
+
+    #[test]
+    fn matches_requires_every_requirement() {
+        let selector = LabelSelector::parse("app=web,zone in (a,b),!legacy,env!=dev").unwrap();
+        assert!(selector.matches(&labels(&[("app", "web"), ("zone", "a")])));
+        assert!(selector.matches(&labels(&[("app", "web"), ("zone", "b"), ("env", "prod")])));
+        assert!(!selector.matches(&labels(&[("app", "web"), ("zone", "c")])));
+        assert!(!selector.matches(&labels(&[("app", "web"), ("zone", "a"), ("legacy", "")])));
+        assert!(!selector.matches(&labels(&[("app", "web"), ("zone", "a"), ("env", "dev")])));
+        assert!(!selector.matches(&labels(&[("zone", "a")])));
+    }
+
+    #[test]
+    fn not_in_matches_missing_labels() {
+        let selector = LabelSelector::parse("team notin (x,y)").unwrap();
+        assert!(selector.matches(&labels(&[])));
+        assert!(selector.matches(&labels(&[("team", "z")])));
+        assert!(!selector.matches(&labels(&[("team", "y")])));
+    }
+
+    #[test]
+    fn reads_spec_selectors() {
+        let spec = json!({
+            "matchLabels": { "app": "web" },
+            "matchExpressions": [
+                { "key": "zone", "operator": "In", "values": ["a"] },
+                { "key": "legacy", "operator": "DoesNotExist" }
+            ]
+        });
+        let selector = LabelSelector::from_spec(&spec).unwrap();
+        assert_eq!(
+            selector.requirements,
+            vec![
+                Requirement::Equals("app".into(), "web".into()),
+                Requirement::In("zone".into(), s(&["a"])),
+                Requirement::DoesNotExist("legacy".into()),
+            ]
+        );
+        assert!(LabelSelector::from_spec(&json!({ "matchExpressions": [{ "key": "a", "operator": "Gt" }] })).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use axum::{
+    extract::{DefaultBodyLimit, Request},
     middleware::from_fn_with_state,
-    routing::{get, post, put},
+    routing::{any, delete, get, post, put},
     Router,
 };
 use tower::ServiceBuilder;
@@ -9,23 +10,77 @@ use tower_http::{
     trace::TraceLayer,
 };
 use tracing::{info, Level};
-use tracing_subscriber;
 
+mod access_policy;
+mod advisory_feed;
 mod auth;
+mod cluster_clients;
+mod cluster_import;
+mod cluster_prober;
+mod comparison;
 mod config;
+mod credentials;
+mod crypto;
+mod db;
+mod deprecations;
 mod handlers;
+mod images;
+mod impersonation;
+mod inventory_collector;
+mod kube_client;
+mod kubeconfig;
+mod label_selector;
 mod middleware;
 mod models;
+mod pod_exec;
+mod pod_logs;
+mod preferences;
+mod recording;
+mod recording_store;
+mod repositories;
+mod resource_watch;
+mod sbom;
+mod snapshots;
+mod topology;
+mod user_export;
+mod user_import;
+mod validation;
+mod vulnerability_matching;
 
+use access_policy::AccessPolicy;
 use auth::AuthService;
+use cluster_clients::ClusterClients;
+use cluster_prober::ClusterProber;
 use config::Config;
-use handlers::{auth_handler, health_handler, user_handler, user_admin_handler};
+use credentials::CredentialVault;
+use inventory_collector::InventoryCollector;
+use recording::Recordings;
+use recording_store::RecordingStore;
+use repositories::Repositories;
+use snapshots::Snapshotter;
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use handlers::{
+    audit_handler, auth_handler, cluster_handler, comparison_handler, deprecation_handler, exec_handler,
+    health_handler, image_handler, inventory_handler, log_handler, preferences_handler,
+    proxy_handler, recording_handler, role_admin_handler, snapshot_handler, topology_handler,
+    user_handler, user_admin_handler, vulnerability_handler, watch_handler,
+};
 use crate::middleware::{auth_middleware, require_admin_middleware};
 
 #[derive(Clone)]
 pub struct AppState {
     pub config: Config,
     pub auth_service: AuthService,
+    pub db: PgPool,
+    pub repos: Repositories,
+    pub credentials: CredentialVault,
+    pub prober: ClusterProber,
+    pub collector: InventoryCollector,
+    pub policy: Arc<AccessPolicy>,
+    pub clients: ClusterClients,
+    pub recordings: Recordings,
 }
 
 #[tokio::main]
@@ -52,6 +107,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // `kubeatlas-backend migrate` applies pending migrations and exits
+    let migrate_only = std::env::args().nth(1).as_deref() == Some("migrate");
+
     // Initialize auth service
     let auth_service = match AuthService::new(&config) {
         Ok(auth_service) => {
@@ -64,6 +122,70 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     };
 
+    // Connect to the database and apply migrations
+    let db = match db::connect(&config).await {
+        Ok(pool) => {
+            info!("✅ Database connected");
+            pool
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to connect to database: {}", e);
+            return Err(e.into());
+        }
+    };
+    if migrate_only || config.database.auto_migrate {
+        if let Err(e) = db::run_migrations(&db).await {
+            eprintln!("❌ Failed to apply database migrations: {}", e);
+            return Err(e.into());
+        }
+    }
+    if migrate_only {
+        info!("✅ Migrations complete");
+        return Ok(());
+    }
+
+    // Load credential master keys
+    let keyring = match crypto::Keyring::from_config(&config.credentials) {
+        Ok(Some(keyring)) => {
+            info!("✅ Credential master key loaded ({})", keyring.current_key_id());
+            Some(keyring)
+        }
+        Ok(None) => {
+            eprintln!("⚠️ No credential master key configured; cluster credentials cannot be stored");
+            None
+        }
+        Err(e) => {
+            eprintln!("❌ Invalid credential master key: {:#}", e);
+            return Err(e.into());
+        }
+    };
+
+    // Load the API proxy access policy
+    let policy = match AccessPolicy::from_config(&config.proxy) {
+        Ok(policy) => {
+            info!("✅ Access policy loaded ({} roles)", policy.roles.len());
+            policy
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to load access policy: {:#}", e);
+            return Err(e.into());
+        }
+    };
+
+    // Open the exec session recording store
+    let recording_store = match RecordingStore::from_config(&config.recording) {
+        Ok(store) => {
+            if config.recording.enabled {
+                info!("✅ Exec session recording enabled ({} storage)", config.recording.storage);
+            }
+            store
+        }
+        Err(e) => {
+            eprintln!("❌ Invalid recording storage: {:#}", e);
+            return Err(e.into());
+        }
+    };
+
     // Wait for Keycloak readiness
     if let Err(e) = auth_service.wait_for_keycloak_ready(120).await {
         eprintln!("⚠️ Keycloak not ready: {}", e);
@@ -79,14 +201,133 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Create app state
+    let repos = Repositories::new(db.clone());
+    let credentials = CredentialVault::new(keyring, repos.credentials.clone());
+    let prober = ClusterProber::new(
+        repos.clusters.clone(),
+        credentials.clone(),
+        config.cluster_probe.clone(),
+    );
+    if config.cluster_probe.enabled {
+        prober.clone().spawn();
+        info!(
+            "✅ Cluster health prober started (every {}s)",
+            config.cluster_probe.interval_secs
+        );
+    }
+    let collector = InventoryCollector::new(
+        repos.clusters.clone(),
+        repos.inventory.clone(),
+        repos.images.clone(),
+        credentials.clone(),
+        config.inventory.clone(),
+    );
+    if config.inventory.enabled {
+        collector.clone().spawn();
+        info!(
+            "✅ Inventory collector started (every {}s)",
+            config.inventory.interval_secs
+        );
+    }
+    if config.snapshot.enabled {
+        Snapshotter::new(repos.snapshots.clone(), config.snapshot.clone()).spawn();
+        info!(
+            "✅ Inventory snapshots started (every {}s, retention {} days)",
+            config.snapshot.interval_secs, config.snapshot.retention_days
+        );
+    }
+    let clients = ClusterClients::new(credentials.clone(), &config.proxy);
+    let recordings = Recordings::new(
+        repos.recordings.clone(),
+        recording_store,
+        config.recording.clone(),
+    );
+    if let Err(e) = recordings
+        .recover(Duration::from_secs(config.exec.max_session_secs))
+        .await
+    {
+        eprintln!("⚠️ Failed to recover interrupted recordings: {:#}", e);
+    }
+    if config.recording.retention_days > 0 {
+        recordings.clone().spawn_purge();
+        info!(
+            "✅ Recording purge started (retention {} days)",
+            config.recording.retention_days
+        );
+    }
     let app_state = AppState {
         config: config.clone(),
         auth_service,
+        credentials,
+        policy: Arc::new(policy),
+        clients,
+        recordings,
+        prober,
+        collector,
+        repos,
+        db,
     };
 
+    // Feeds and SBOMs are much larger than other request bodies
+    let upload_limit = DefaultBodyLimit::max(app_state.config.vulnerability.max_upload_mb * 1024 * 1024);
+
     // Protected user routes
     let protected = Router::new()
-        .route("/api/v1/user/profile", get(user_handler::get_profile))
+        .route(
+            "/api/v1/user/profile",
+            get(user_handler::get_profile).put(user_handler::update_profile),
+        )
+        .route("/api/v1/user/password", post(user_handler::change_password))
+        .route(
+            "/api/v1/user/preferences",
+            get(preferences_handler::get_preferences).patch(preferences_handler::patch_preferences),
+        )
+        .route("/api/v1/clusters", get(cluster_handler::list_clusters))
+        .route("/api/v1/clusters/:id", get(cluster_handler::get_cluster))
+        .route("/api/v1/compare", get(comparison_handler::compare_clusters))
+        .route("/api/v1/images", get(image_handler::list_images))
+        .route("/api/v1/images/export", get(image_handler::export_images))
+        .route("/api/v1/images/:digest/sbom", get(vulnerability_handler::get_sbom))
+        .route(
+            "/api/v1/images/:digest/vulnerabilities",
+            get(vulnerability_handler::image_vulnerabilities),
+        )
+        .route("/api/v1/vulnerabilities/clusters", get(vulnerability_handler::cluster_summaries))
+        .route("/api/v1/clusters/:id/inventory", get(inventory_handler::list_inventory))
+        .route(
+            "/api/v1/clusters/:id/inventory/status",
+            get(inventory_handler::get_inventory_status),
+        )
+        .route("/api/v1/clusters/:id/nodes", get(inventory_handler::list_nodes))
+        .route("/api/v1/clusters/:id/namespaces", get(inventory_handler::list_namespaces))
+        .route("/api/v1/clusters/:id/workloads", get(inventory_handler::list_workloads))
+        .route("/api/v1/clusters/:id/snapshots", get(snapshot_handler::list_snapshots))
+        .route("/api/v1/clusters/:id/snapshots/diff", get(snapshot_handler::diff_snapshots))
+        .route(
+            "/api/v1/clusters/:id/snapshots/:snapshot_id",
+            get(snapshot_handler::get_snapshot),
+        )
+        .route("/api/v1/clusters/:id/topology", get(topology_handler::get_topology))
+        .route(
+            "/api/v1/clusters/:id/deprecations",
+            get(deprecation_handler::scan_cluster).post(deprecation_handler::scan_cluster_with_manifests),
+        )
+        .route("/api/v1/deprecations", get(deprecation_handler::list_deprecated_apis))
+        .route(
+            "/api/v1/clusters/:id/vulnerabilities",
+            get(vulnerability_handler::workload_summaries),
+        )
+        .route("/api/v1/clusters/:id/proxy/*path", any(proxy_handler::proxy))
+        .route("/api/v1/clusters/:id/watch", get(watch_handler::watch))
+        .route(
+            "/api/v1/clusters/:id/namespaces/:ns/pods/:pod/logs",
+            get(log_handler::pod_logs),
+        )
+        .route("/api/v1/clusters/:id/namespaces/:ns/logs", get(log_handler::namespace_logs))
+        .route(
+            "/api/v1/clusters/:id/namespaces/:ns/pods/:pod/exec",
+            get(exec_handler::exec),
+        )
         .route("/api/v1/user/roles", get(user_handler::get_user_roles))
         .layer(from_fn_with_state(app_state.clone(), auth_middleware));
 
@@ -94,6 +335,75 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let admin = Router::new()
         .route("/api/v1/admin/users", post(user_admin_handler::create_user))
         .route("/api/v1/admin/users/:id", put(user_admin_handler::update_user))
+        .route("/api/v1/admin/users/import", post(user_admin_handler::import_users))
+        .route("/api/v1/admin/users/export", get(user_admin_handler::export_users))
+        .route(
+            "/api/v1/admin/roles",
+            get(role_admin_handler::list_roles).post(role_admin_handler::create_role),
+        )
+        .route(
+            "/api/v1/admin/roles/:name",
+            get(role_admin_handler::get_role)
+                .put(role_admin_handler::update_role)
+                .delete(role_admin_handler::delete_role),
+        )
+        .route("/api/v1/admin/roles/:name/users", get(role_admin_handler::list_role_members))
+        .route(
+            "/api/v1/admin/roles/:name/composites",
+            get(role_admin_handler::list_role_composites)
+                .post(role_admin_handler::add_role_composites)
+                .delete(role_admin_handler::remove_role_composites),
+        )
+        .route("/api/v1/clusters", post(cluster_handler::create_cluster))
+        .route("/api/v1/clusters/import", post(cluster_handler::import_kubeconfig))
+        .route(
+            "/api/v1/clusters/:id",
+            put(cluster_handler::update_cluster).delete(cluster_handler::delete_cluster),
+        )
+        .route(
+            "/api/v1/clusters/:id/credential",
+            get(cluster_handler::get_credential)
+                .put(cluster_handler::put_credential)
+                .delete(cluster_handler::delete_credential),
+        )
+        .route("/api/v1/clusters/:id/probe", post(cluster_handler::probe_cluster))
+        .route(
+            "/api/v1/clusters/:id/inventory/refresh",
+            post(inventory_handler::refresh_inventory),
+        )
+        .route("/api/v1/clusters/:id/snapshots", post(snapshot_handler::create_snapshot))
+        .route(
+            "/api/v1/clusters/:id/snapshots/:snapshot_id",
+            delete(snapshot_handler::delete_snapshot),
+        )
+        .route("/api/v1/admin/credentials/rotate", post(cluster_handler::rotate_credentials))
+        .route("/api/v1/admin/audit", get(audit_handler::list_audit_log))
+        .route("/api/v1/admin/recordings", get(recording_handler::list_recordings))
+        .route("/api/v1/admin/recordings/:id", get(recording_handler::get_recording))
+        .route(
+            "/api/v1/admin/recordings/:id/download",
+            get(recording_handler::download_recording),
+        )
+        .route(
+            "/api/v1/admin/recordings/:id/replay",
+            get(recording_handler::replay_recording),
+        )
+        .route(
+            "/api/v1/admin/vulnerability-feeds",
+            get(vulnerability_handler::list_feeds)
+                .post(vulnerability_handler::import_feed)
+                .layer(upload_limit),
+        )
+        .route(
+            "/api/v1/admin/vulnerability-feeds/:id",
+            delete(vulnerability_handler::delete_feed),
+        )
+        .route(
+            "/api/v1/images/:digest/sbom",
+            put(vulnerability_handler::put_sbom)
+                .delete(vulnerability_handler::delete_sbom)
+                .layer(upload_limit),
+        )
         // Порядок важен: внешний слой выполняется первым, поэтому сначала auth, потом require_admin
         .route_layer(from_fn_with_state(app_state.clone(), require_admin_middleware))
         .route_layer(from_fn_with_state(app_state.clone(), auth_middleware));
@@ -115,7 +425,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         // Add CORS and tracing middleware
         .layer(
             ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
+                .layer(TraceLayer::new_for_http().make_span_with(|request: &Request| {
+                    // WebSocket handshakes may carry `access_token` in the query
+                    tracing::debug_span!(
+                        "request",
+                        method = %request.method(),
+                        uri = %AuthService::strip_token_from_uri(request.uri()),
+                        version = ?request.version(),
+                    )
+                }))
                 .layer(
                     CorsLayer::new()
                         .allow_origin(Any)
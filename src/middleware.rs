@@ -1,13 +1,13 @@
 use axum::{
     extract::{Request, State},
-    http::StatusCode,
+    http::{header, StatusCode},
     middleware::Next,
     response::{Json, Response},
 };
 use serde_json::json;
 use tracing::{info, warn};
 
-use crate::AppState;
+use crate::{auth::AuthService, AppState};
 
 pub async fn auth_middleware(
     State(state): State<AppState>,
@@ -16,7 +16,21 @@ pub async fn auth_middleware(
 ) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
     // Extract token from Authorization header
     let headers = request.headers().clone();
-    let token = match state.auth_service.extract_token_from_headers(&headers) {
+    let is_websocket = headers
+        .get(header::UPGRADE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.eq_ignore_ascii_case("websocket"));
+    let token = state
+        .auth_service
+        .extract_token_from_headers(&headers)
+        .or_else(|e| match is_websocket {
+            true => state
+                .auth_service
+                .extract_token_from_query(request.uri().query())
+                .ok_or(e),
+            false => Err(e),
+        });
+    let token = match token {
         Ok(token) => token,
         Err(e) => {
             warn!("Failed to extract token: {}", e);
@@ -51,6 +65,7 @@ pub async fn auth_middleware(
                 
                 // Add user info to request extensions for use in handlers
                 request.extensions_mut().insert(user);
+                *request.uri_mut() = AuthService::strip_token_from_uri(request.uri());
                 
                 Ok(next.run(request).await)
             } else {
@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// One audited Kubernetes API call made through the proxy.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+    /// Keycloak subject of the caller
+    pub user_id: String,
+    pub username: String,
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub verb: String,
+    pub api_group: String,
+    /// Resource with subresource, e.g. `pods/eviction`
+    pub resource: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub path: String,
+    /// `false` if the access policy rejected the call
+    pub allowed: bool,
+    /// Upstream response status; `None` if the call was denied or never answered
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditPage {
+    pub total: i64,
+    pub items: Vec<AuditEntry>,
+}
@@ -1,18 +1,82 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct Cluster {
     pub id: Uuid,
     pub name: String,
     pub description: Option<String>,
     pub endpoint: String,
     pub status: ClusterStatus,
+    #[sqlx(flatten)]
+    pub health: ClusterHealth,
+    #[sqlx(flatten)]
+    pub impersonation: ImpersonationSettings,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Latest results of the health prober; empty until the first probe.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterHealth {
+    pub kubernetes_version: Option<String>,
+    pub probe_latency_ms: Option<i32>,
+    pub last_error: Option<String>,
+    pub last_probe_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_success_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub consecutive_failures: i32,
+    pub consecutive_successes: i32,
+}
+
+/// Token claim that becomes the impersonated Kubernetes user name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ImpersonationUserClaim {
+    #[default]
+    PreferredUsername,
+    Sub,
+}
+
+/// Whether requests made on behalf of a user impersonate them, so the
+/// cluster's RoleBindings decide what they may do. Prefixes are prepended to
+/// the user name and to every group (Keycloak groups and roles).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[serde(default)]
+pub struct ImpersonationSettings {
+    #[sqlx(rename = "impersonation_enabled")]
+    pub enabled: bool,
+    #[sqlx(rename = "impersonation_user_claim")]
+    pub user_claim: ImpersonationUserClaim,
+    #[sqlx(rename = "impersonation_user_prefix")]
+    pub user_prefix: String,
+    #[sqlx(rename = "impersonation_group_prefix")]
+    pub group_prefix: String,
+    #[sqlx(rename = "impersonation_include_groups")]
+    pub include_groups: bool,
+    #[sqlx(rename = "impersonation_include_roles")]
+    pub include_roles: bool,
+}
+
+impl Default for ImpersonationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            user_claim: ImpersonationUserClaim::default(),
+            user_prefix: String::new(),
+            group_prefix: String::new(),
+            include_groups: true,
+            include_roles: true,
+        }
+    }
+}
+
+/// Stored and serialized as lowercase strings (`active`, `inactive`, ...).
+/// The prober sets `active` (healthy), `error` (responds but unhealthy or
+/// rejects the credential) and `inactive` (unreachable).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
 pub enum ClusterStatus {
     Active,
     Inactive,
@@ -25,6 +89,11 @@ pub struct CreateClusterRequest {
     pub name: String,
     pub description: Option<String>,
     pub endpoint: String,
+    /// Stored encrypted; never returned by read APIs
+    #[serde(default)]
+    pub credential: Option<ClusterCredential>,
+    #[serde(default)]
+    pub impersonation: Option<ImpersonationSettings>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -33,4 +102,168 @@ pub struct UpdateClusterRequest {
     pub description: Option<String>,
     pub endpoint: Option<String>,
     pub status: Option<ClusterStatus>,
+    /// Replaces all impersonation settings when present
+    pub impersonation: Option<ImpersonationSettings>,
+}
+
+/// How KubeAtlas (or a kubeconfig context) authenticates to a cluster's API server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "text", rename_all = "snake_case")]
+pub enum ClusterAuthMethod {
+    Token,
+    ClientCertificate,
+    Oidc,
+    Basic,
+    Exec,
+    File,
+    None,
+}
+
+/// Secret part of a credential. Only ever serialized into the encrypted payload.
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClusterAuth {
+    #[default]
+    None,
+    Token {
+        token: String,
+    },
+    ClientCertificate {
+        certificate_pem: String,
+        key_pem: String,
+    },
+    /// OIDC with static client settings; exec plugins are not supported
+    Oidc {
+        issuer_url: String,
+        client_id: String,
+        client_secret: Option<String>,
+        refresh_token: Option<String>,
+        id_token: Option<String>,
+    },
+}
+
+impl ClusterAuth {
+    pub fn method(&self) -> ClusterAuthMethod {
+        match self {
+            Self::None => ClusterAuthMethod::None,
+            Self::Token { .. } => ClusterAuthMethod::Token,
+            Self::ClientCertificate { .. } => ClusterAuthMethod::ClientCertificate,
+            Self::Oidc { .. } => ClusterAuthMethod::Oidc,
+        }
+    }
+}
+
+impl std::fmt::Debug for ClusterAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::None => f.write_str("None"),
+            Self::Token { .. } => f.write_str("Token([REDACTED])"),
+            Self::ClientCertificate { .. } => f.write_str("ClientCertificate([REDACTED])"),
+            Self::Oidc { issuer_url, client_id, .. } => f
+                .debug_struct("Oidc")
+                .field("issuer_url", issuer_url)
+                .field("client_id", client_id)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// Everything needed to reach a cluster's API server besides its endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterCredential {
+    #[serde(default)]
+    pub auth: ClusterAuth,
+    /// PEM bundle used to verify the API server certificate
+    #[serde(default)]
+    pub ca_bundle_pem: Option<String>,
+    #[serde(default)]
+    pub insecure_skip_tls_verify: bool,
+    #[serde(default)]
+    pub tls_server_name: Option<String>,
+}
+
+/// What the API reveals about a stored credential.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterCredentialInfo {
+    pub cluster_id: Uuid,
+    pub auth_method: ClusterAuthMethod,
+    pub has_ca_bundle: bool,
+    pub insecure_skip_tls_verify: bool,
+    /// Master key the credential is currently wrapped with
+    pub key_id: String,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RotateCredentialsResponse {
+    pub key_id: String,
+    pub rotated: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterRef {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CaSource {
+    Inline,
+    File,
+    None,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubeconfigContextPreview {
+    pub context: String,
+    pub cluster_name: String,
+    pub user: Option<String>,
+    pub namespace: Option<String>,
+    pub server: String,
+    pub current: bool,
+    pub suggested_name: String,
+    pub ca_source: CaSource,
+    pub insecure_skip_tls_verify: bool,
+    pub tls_server_name: Option<String>,
+    pub auth_method: ClusterAuthMethod,
+    /// Whether the context can be registered with its credentials
+    pub importable: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+    /// Existing cluster registered with the same endpoint
+    pub duplicate_of: Option<ClusterRef>,
+}
+
+/// One context chosen for registration; `name` overrides the suggested name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterImportSelection {
+    pub context: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClusterImportStatus {
+    Created,
+    SkippedDuplicate,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterImportResult {
+    pub context: String,
+    pub status: ClusterImportStatus,
+    pub cluster: Option<Cluster>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClusterImportResponse {
+    pub contexts: Vec<KubeconfigContextPreview>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub results: Option<Vec<ClusterImportResult>>,
 }
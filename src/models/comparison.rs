@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DifferenceCategory {
+    Replicas,
+    Image,
+    /// Container requests and limits
+    Resources,
+    /// ConfigMap and Secret keys, Secret type
+    Data,
+}
+
+/// One field whose value is not the same everywhere the resource exists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldDifference {
+    pub category: DifferenceCategory,
+    /// e.g. `containers[web].image` or `data.LOG_LEVEL`
+    pub field: String,
+    /// Value per cluster name; `null` where the field is not set
+    pub values: BTreeMap<String, Option<Value>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ComparisonStatus {
+    Identical,
+    Different,
+    /// Absent from at least one cluster
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceComparison {
+    pub name: String,
+    pub status: ComparisonStatus,
+    /// Cluster names without the resource
+    pub missing_in: Vec<String>,
+    /// Differences between the clusters that have the resource
+    pub differences: Vec<FieldDifference>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparedCluster {
+    pub id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ComparisonSummary {
+    pub resources: usize,
+    pub identical: usize,
+    pub different: usize,
+    pub missing: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparisonReport {
+    pub kind: String,
+    pub namespace: String,
+    pub clusters: Vec<ComparedCluster>,
+    pub summary: ComparisonSummary,
+    pub resources: Vec<ResourceComparison>,
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::InventoryKind;
+
+/// An `apiVersion` a client wrote an object with, as the object records it.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AppliedApiVersion {
+    pub api_version: String,
+    /// `last-applied-configuration` or the field manager, e.g. `helm`
+    pub source: String,
+}
+
+/// A collected object with the API versions it was written with.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct InventoryApiVersions {
+    pub kind: InventoryKind,
+    pub namespace: String,
+    pub name: String,
+    pub api_versions: sqlx::types::Json<Vec<AppliedApiVersion>>,
+}
+
+/// An entry of the built-in table of deprecated API versions.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecatedApi {
+    pub api_version: &'static str,
+    pub kind: &'static str,
+    /// Kubernetes minor release, e.g. `1.21`
+    pub deprecated_in: &'static str,
+    /// None while the API is only deprecated
+    pub removed_in: Option<&'static str>,
+    /// `apiVersion` to migrate to; None when the API has no successor
+    pub replacement: Option<&'static str>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeprecationStatus {
+    /// Not served by the target release
+    Removed,
+    /// Still served by the target release
+    Deprecated,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FindingOrigin {
+    /// Collected from the cluster
+    Cluster,
+    /// From an uploaded manifest
+    Manifest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecationFinding {
+    pub origin: FindingOrigin,
+    /// Uploaded file name, for manifests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// 1-based YAML document within the file, for manifests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub document: Option<usize>,
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub api_version: String,
+    /// Where a collected object records the version, see `AppliedApiVersion`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub recorded_by: Vec<String>,
+    pub status: DeprecationStatus,
+    pub deprecated_in: &'static str,
+    pub removed_in: Option<&'static str>,
+    pub replacement: Option<&'static str>,
+    /// Removed after the current release and by the target release
+    pub blocks_upgrade: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct DeprecationSummary {
+    pub objects_scanned: usize,
+    /// Collected objects without recorded API versions, e.g. collected
+    /// before they were stored; checked after the next collection
+    pub objects_not_scanned: usize,
+    pub manifests_scanned: usize,
+    pub removed: usize,
+    pub deprecated: usize,
+    pub blocking: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecationReport {
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub current_version: String,
+    pub target_version: String,
+    pub summary: DeprecationSummary,
+    /// APIs removed after the current release and by the target release,
+    /// whether or not anything uses them
+    pub removals: Vec<DeprecatedApi>,
+    pub findings: Vec<DeprecationFinding>,
+}
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A container image reference split into its parts. Docker Hub images get
+/// their implicit registry and `library/` prefix, untagged ones `latest`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageReference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+/// One place an image runs or ran.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageLocation {
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub namespace: String,
+    /// Deployment for ReplicaSet-owned pods, otherwise the pod's controller
+    /// kind, or `Pod` for bare pods
+    pub workload_kind: String,
+    pub workload_name: String,
+    pub container: String,
+    /// The reference as written in the pod spec
+    pub image: String,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    /// Seen in the latest pod collection of the cluster
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ContainerImage {
+    /// `registry/repository[:tag][@digest]`
+    pub image: String,
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+    /// Tagged `latest`, explicitly or by omitting the tag
+    pub latest_tag: bool,
+    /// Referenced by tag only somewhere, so what runs can change under it
+    pub mutable_tag: bool,
+    pub first_seen: chrono::DateTime<chrono::Utc>,
+    pub last_seen: chrono::DateTime<chrono::Utc>,
+    pub running: bool,
+    #[sqlx(json)]
+    pub locations: Vec<ImageLocation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ContainerImagePage {
+    pub total: i64,
+    pub items: Vec<ContainerImage>,
+}
@@ -0,0 +1,196 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Object kinds collected into the inventory, named as in Kubernetes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text")]
+pub enum InventoryKind {
+    Node,
+    Namespace,
+    Deployment,
+    StatefulSet,
+    DaemonSet,
+    Job,
+    CronJob,
+    Pod,
+}
+
+impl InventoryKind {
+    pub const ALL: [InventoryKind; 8] = [
+        Self::Node,
+        Self::Namespace,
+        Self::Deployment,
+        Self::StatefulSet,
+        Self::DaemonSet,
+        Self::Job,
+        Self::CronJob,
+        Self::Pod,
+    ];
+
+    pub const WORKLOADS: [InventoryKind; 6] = [
+        Self::Deployment,
+        Self::StatefulSet,
+        Self::DaemonSet,
+        Self::Job,
+        Self::CronJob,
+        Self::Pod,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Node => "Node",
+            Self::Namespace => "Namespace",
+            Self::Deployment => "Deployment",
+            Self::StatefulSet => "StatefulSet",
+            Self::DaemonSet => "DaemonSet",
+            Self::Job => "Job",
+            Self::CronJob => "CronJob",
+            Self::Pod => "Pod",
+        }
+    }
+
+    /// Case-insensitive, accepts the kind (`Deployment`) or its plural resource (`deployments`).
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim().to_ascii_lowercase();
+        Self::ALL.into_iter().find(|kind| {
+            let name = kind.as_str().to_ascii_lowercase();
+            value == name || value == format!("{}s", name)
+        })
+    }
+
+    pub fn is_namespaced(&self) -> bool {
+        !matches!(self, Self::Node | Self::Namespace)
+    }
+
+    /// API group, empty for the core group.
+    pub fn api_group(&self) -> &'static str {
+        match self {
+            Self::Node | Self::Namespace | Self::Pod => "",
+            Self::Deployment | Self::StatefulSet | Self::DaemonSet => "apps",
+            Self::Job | Self::CronJob => "batch",
+        }
+    }
+
+    /// Plural resource name, e.g. `statefulsets`.
+    pub fn resource(&self) -> String {
+        format!("{}s", self.as_str().to_ascii_lowercase())
+    }
+
+    /// Collection path, across all namespaces unless `namespace` is given
+    /// (ignored for cluster-scoped kinds).
+    pub fn collection_path(&self, namespace: Option<&str>) -> anyhow::Result<String> {
+        crate::kube_client::collection_path(
+            self.api_group(),
+            &self.resource(),
+            namespace.filter(|_| self.is_namespaced()),
+        )
+    }
+}
+
+/// One collected object. `details` holds the normalized, kind-specific fields.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InventoryObject {
+    pub kind: InventoryKind,
+    pub namespace: Option<String>,
+    pub name: String,
+    pub uid: String,
+    #[sqlx(json)]
+    pub labels: BTreeMap<String, String>,
+    pub details: serde_json::Value,
+    /// `metadata.creationTimestamp` in the cluster
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub collected_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryPage {
+    pub total: i64,
+    pub items: Vec<InventoryObject>,
+}
+
+/// Outcome of the latest collection run for a cluster.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct InventoryStatus {
+    pub cluster_id: Uuid,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Objects stored per kind
+    #[sqlx(json)]
+    pub counts: BTreeMap<String, i64>,
+    /// Per-kind errors; kinds that failed keep the previously collected objects
+    #[sqlx(json)]
+    pub errors: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NodeCondition {
+    #[serde(rename = "type")]
+    pub condition_type: String,
+    pub status: String,
+    pub reason: Option<String>,
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NodeDetails {
+    pub capacity: BTreeMap<String, String>,
+    pub allocatable: BTreeMap<String, String>,
+    pub kubelet_version: Option<String>,
+    pub os_image: Option<String>,
+    pub operating_system: Option<String>,
+    pub architecture: Option<String>,
+    pub kernel_version: Option<String>,
+    pub container_runtime: Option<String>,
+    pub unschedulable: bool,
+    pub ready: bool,
+    pub conditions: Vec<NodeCondition>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NamespaceDetails {
+    pub phase: Option<String>,
+}
+
+/// Shared shape for Deployments, StatefulSets, DaemonSets, Jobs and CronJobs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkloadDetails {
+    /// Desired replicas (`desiredNumberScheduled` for DaemonSets, `completions` for Jobs)
+    pub desired: Option<i64>,
+    pub ready: Option<i64>,
+    pub available: Option<i64>,
+    pub updated: Option<i64>,
+    /// Jobs: pods that succeeded / failed / are running
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub succeeded: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<i64>,
+    /// CronJobs only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspend: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_schedule_time: Option<String>,
+    pub selector: BTreeMap<String, String>,
+    pub images: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OwnerRef {
+    pub kind: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PodDetails {
+    pub phase: Option<String>,
+    pub node_name: Option<String>,
+    pub pod_ip: Option<String>,
+    pub ready: bool,
+    pub restarts: i64,
+    pub owner: Option<OwnerRef>,
+    pub images: Vec<String>,
+}
@@ -2,9 +2,29 @@
 // This will contain data structures for the application
 
 pub mod user;
+pub mod role;
+pub mod preferences;
 pub mod cluster;
-pub mod response;
+pub mod comparison;
+pub mod deprecation;
+pub mod inventory;
+pub mod image;
+pub mod audit;
+pub mod recording;
+pub mod snapshot;
+pub mod topology;
+pub mod vulnerability;
 
 pub use user::*;
+pub use role::*;
+pub use preferences::*;
 pub use cluster::*;
-pub use response::*;
+pub use comparison::*;
+pub use deprecation::*;
+pub use inventory::*;
+pub use image::*;
+pub use audit::*;
+pub use recording::*;
+pub use snapshot::*;
+pub use topology::*;
+pub use vulnerability::*;
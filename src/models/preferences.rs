@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+/// Column layout of one UI table, keyed by table id in `UserPreferences::table_layouts`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TableLayout {
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub hidden: Vec<String>,
+    #[serde(default)]
+    pub widths: BTreeMap<String, u32>,
+    pub sort_by: Option<String>,
+    #[serde(default)]
+    pub sort_desc: bool,
+    pub page_size: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserPreferences {
+    #[serde(default)]
+    pub default_cluster: Option<Uuid>,
+    #[serde(default)]
+    pub selected_namespaces: Vec<String>,
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default = "default_language")]
+    pub language: String,
+    #[serde(default)]
+    pub table_layouts: BTreeMap<String, TableLayout>,
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            default_cluster: None,
+            selected_namespaces: Vec::new(),
+            theme: Theme::default(),
+            language: default_language(),
+            table_layouts: BTreeMap::new(),
+        }
+    }
+}
@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum RecordingStatus {
+    /// The session is still running
+    Recording,
+    Complete,
+    /// The server stopped during the session; the cast may be cut short
+    Interrupted,
+    /// The cast could not be stored; `end_reason` says why
+    Failed,
+}
+
+/// An asciicast v2 recording of one exec session.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ExecRecording {
+    pub id: Uuid,
+    /// Audit entry of the session; `None` if writing it failed
+    pub audit_id: Option<Uuid>,
+    /// Keycloak subject of the user
+    pub user_id: String,
+    pub username: String,
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub namespace: String,
+    pub pod: String,
+    pub container: Option<String>,
+    pub command: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    /// Server instance that wrote the session; `None` for older recordings
+    pub instance_id: Option<String>,
+    pub status: RecordingStatus,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub ended_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub size_bytes: Option<i64>,
+    pub exit_code: Option<i32>,
+    /// Why the session ended, e.g. `command finished` or `idle timeout`
+    pub end_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingPage {
+    pub total: i64,
+    pub items: Vec<ExecRecording>,
+}
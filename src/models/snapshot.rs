@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SnapshotTrigger {
+    Scheduled,
+    Manual,
+}
+
+/// A point-in-time copy of a cluster's collected inventory.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterSnapshot {
+    pub id: Uuid,
+    pub cluster_id: Uuid,
+    pub taken_at: chrono::DateTime<chrono::Utc>,
+    pub trigger: SnapshotTrigger,
+    /// Username for manual snapshots
+    pub created_by: Option<String>,
+    /// When the inventory the snapshot was taken from finished collecting
+    pub collected_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub object_count: i32,
+    /// Objects per kind
+    #[sqlx(json)]
+    pub counts: BTreeMap<String, i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotPage {
+    pub total: i64,
+    pub items: Vec<ClusterSnapshot>,
+}
+
+/// An object in a snapshot; `hash` identifies its manifest.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct SnapshotEntry {
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ObjectRef {
+    pub kind: String,
+    pub namespace: Option<String>,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeOp {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One changed field, addressed by a JSON pointer into the manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    pub path: String,
+    pub op: ChangeOp,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangedObject {
+    #[serde(flatten)]
+    pub object: ObjectRef,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub from: ClusterSnapshot,
+    pub to: ClusterSnapshot,
+    pub summary: DiffSummary,
+    pub added: Vec<ObjectRef>,
+    pub removed: Vec<ObjectRef>,
+    pub changed: Vec<ChangedObject>,
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// One object in the topology graph. The id is `Kind/namespace/name`, so it
+/// stays the same across requests and object re-creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyNode {
+    pub id: String,
+    pub kind: String,
+    pub namespace: String,
+    pub name: String,
+    /// `None` for objects only known from a reference (volumes, env,
+    /// service accounts, owners of kinds that are not read)
+    pub uid: Option<String>,
+    pub labels: BTreeMap<String, String>,
+    /// Pod phase, or ready/desired replicas of workloads
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeType {
+    /// Ingress → Service backend
+    Routes,
+    /// Service → its EndpointSlices
+    Endpoints,
+    /// EndpointSlice → Pod behind an endpoint
+    Targets,
+    /// Object → owner from `ownerReferences`
+    OwnedBy,
+    /// Pod → ConfigMap, Secret or PersistentVolumeClaim volume
+    Mounts,
+    /// Pod → ConfigMap or Secret used for env or image pulls
+    References,
+    /// Pod → ServiceAccount
+    RunsAs,
+    /// NetworkPolicy → Pod it applies to
+    Selects,
+}
+
+impl EdgeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Routes => "routes",
+            Self::Endpoints => "endpoints",
+            Self::Targets => "targets",
+            Self::OwnedBy => "owned_by",
+            Self::Mounts => "mounts",
+            Self::References => "references",
+            Self::RunsAs => "runs_as",
+            Self::Selects => "selects",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyEdge {
+    /// `source|type|target`
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub edge_type: EdgeType,
+}
+
+/// A kind that could not be read, e.g. denied by cluster RBAC or not served.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TopologyWarning {
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TopologyGraph {
+    pub nodes: Vec<TopologyNode>,
+    pub edges: Vec<TopologyEdge>,
+    pub warnings: Vec<TopologyWarning>,
+}
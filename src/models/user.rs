@@ -1,17 +1,4 @@
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct User {
-    pub id: Uuid,
-    pub username: String,
-    pub email: String,
-    pub first_name: Option<String>,
-    pub last_name: Option<String>,
-    pub roles: Vec<String>,
-    pub created_at: chrono::DateTime<chrono::Utc>,
-    pub updated_at: chrono::DateTime<chrono::Utc>,
-}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateUserRequest {
@@ -21,6 +8,8 @@ pub struct CreateUserRequest {
     pub last_name: Option<String>,
     pub password: String,
     pub roles: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -31,15 +20,73 @@ pub struct UpdateUserRequest {
     pub roles: Option<Vec<String>>,
 }
 
+/// Self-service profile change; roles and other fields are rejected.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct UserProfile {
-    pub id: String,
+#[serde(deny_unknown_fields)]
+pub struct UpdateProfileRequest {
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportUserRecord {
     pub username: String,
     pub email: String,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
+    pub password: String,
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    WouldCreate,
+    SkippedExisting,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImportRowResult {
+    pub row: usize,
+    pub username: Option<String>,
+    pub status: ImportRowStatus,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportUsersReport {
+    pub dry_run: bool,
+    pub total: usize,
+    pub created: usize,
+    pub skipped_existing: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportRowResult>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserExportRecord {
+    pub id: String,
+    pub username: String,
+    pub email: Option<String>,
+    pub first_name: Option<String>,
+    pub last_name: Option<String>,
+    pub enabled: bool,
     pub roles: Vec<String>,
-    pub is_admin: bool,
-    pub is_user: bool,
-    pub is_guest: bool,
+    pub groups: Vec<String>,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_login: Option<chrono::DateTime<chrono::Utc>>,
 }
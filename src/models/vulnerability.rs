@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Severity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Unknown,
+}
+
+impl Severity {
+    /// Severity names used by feeds: GitHub's `MODERATE` is medium, Debian's
+    /// `NEGLIGIBLE` is low.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "critical" => Some(Self::Critical),
+            "high" | "important" => Some(Self::High),
+            "medium" | "moderate" => Some(Self::Medium),
+            "low" | "negligible" => Some(Self::Low),
+            "unknown" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+
+    /// CVSS base score bands.
+    pub fn from_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Self::Critical,
+            s if s >= 7.0 => Self::High,
+            s if s >= 4.0 => Self::Medium,
+            s if s > 0.0 => Self::Low,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum FeedFormat {
+    /// OSV advisories: one object, an array, or `{"vulns": [...]}`
+    Osv,
+    /// Trivy DB buckets exported as JSON
+    Trivy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct VulnerabilityFeed {
+    pub id: Uuid,
+    pub name: String,
+    pub format: FeedFormat,
+    pub advisory_count: i32,
+    pub imported_at: chrono::DateTime<chrono::Utc>,
+    pub imported_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeedImport {
+    #[serde(flatten)]
+    pub feed: VulnerabilityFeed,
+    /// Images whose SBOMs were matched against the feeds again without error
+    pub images_matched: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum SbomFormat {
+    CycloneDx,
+    Spdx,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ImageSbom {
+    pub digest: String,
+    pub format: SbomFormat,
+    pub package_count: i32,
+    pub uploaded_at: chrono::DateTime<chrono::Utc>,
+    pub uploaded_by: Option<String>,
+}
+
+/// A package from an SBOM, identified by its package URL.
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SbomPackage {
+    /// Lower case, without release: `debian`, `npm`, `pypi`
+    pub ecosystem: String,
+    /// Distribution release for OS packages, e.g. `12`
+    pub release: Option<String>,
+    pub name: String,
+    pub version: String,
+    pub purl: String,
+}
+
+/// Distinct advisories per severity.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, sqlx::FromRow)]
+pub struct SeverityCounts {
+    pub critical: i64,
+    pub high: i64,
+    pub medium: i64,
+    pub low: i64,
+    pub unknown: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct VulnerabilityFinding {
+    pub advisory_id: String,
+    pub aliases: sqlx::types::Json<Vec<String>>,
+    pub summary: Option<String>,
+    pub severity: Severity,
+    /// Name of the feed the advisory came from
+    pub feed: String,
+    pub ecosystem: String,
+    pub package: String,
+    pub version: String,
+    pub fixed_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageVulnerabilities {
+    pub sbom: ImageSbom,
+    pub counts: SeverityCounts,
+    pub findings: Vec<VulnerabilityFinding>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageSbomDetails {
+    #[serde(flatten)]
+    pub sbom: ImageSbom,
+    pub packages: Vec<SbomPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ClusterVulnerabilitySummary {
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    /// Running image digests
+    pub images: i64,
+    /// Of those, images with an SBOM
+    pub scanned: i64,
+    /// Running containers whose image digest is unknown, so nothing can be matched
+    pub unresolved: i64,
+    #[sqlx(flatten)]
+    pub counts: SeverityCounts,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct WorkloadVulnerabilitySummary {
+    pub namespace: String,
+    pub workload_kind: String,
+    pub workload_name: String,
+    /// Running image digests
+    pub images: Vec<String>,
+    pub scanned: i64,
+    #[sqlx(flatten)]
+    pub counts: SeverityCounts,
+}
@@ -0,0 +1,255 @@
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio_tungstenite::{tungstenite::Message, WebSocketStream};
+
+use crate::{
+    kube_client::KubeClient,
+    validation::{is_dns_label, is_dns_subdomain},
+};
+
+/// Kubernetes remote command subprotocol: every binary frame starts with
+/// the channel it belongs to.
+pub const PROTOCOL: &str = "v4.channel.k8s.io";
+
+const STDIN: u8 = 0;
+const STDOUT: u8 = 1;
+const STDERR: u8 = 2;
+const ERROR: u8 = 3;
+const RESIZE: u8 = 4;
+
+pub type ExecStream = WebSocketStream<reqwest::Upgraded>;
+
+/// What to run in the pod.
+#[derive(Debug, Clone)]
+pub struct ExecRequest {
+    pub container: Option<String>,
+    pub command: Vec<String>,
+    /// With a TTY stderr is merged into stdout, as with `kubectl exec -t`
+    pub tty: bool,
+}
+
+impl ExecRequest {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query: Vec<(&'static str, String)> = self
+            .command
+            .iter()
+            .map(|arg| ("command", arg.clone()))
+            .collect();
+        if let Some(container) = &self.container {
+            query.push(("container", container.clone()));
+        }
+        query.push(("stdin", "true".to_string()));
+        query.push(("stdout", "true".to_string()));
+        query.push(("stderr", (!self.tty).to_string()));
+        query.push(("tty", self.tty.to_string()));
+        query
+    }
+
+    /// The query as sent to the API server, form-encoded so arguments cannot
+    /// add parameters; used for the audit record.
+    pub fn encoded_query(&self) -> String {
+        let mut url = reqwest::Url::parse("http://localhost/").expect("static URL is valid");
+        url.query_pairs_mut().extend_pairs(self.query());
+        url.query().unwrap_or_default().to_string()
+    }
+}
+
+/// Opens an exec session; fails before anything runs if the API server
+/// rejects it (unknown pod or container, RBAC).
+pub async fn connect(
+    client: &KubeClient,
+    namespace: &str,
+    pod: &str,
+    request: &ExecRequest,
+) -> Result<ExecStream> {
+    ensure!(
+        is_dns_label(namespace) && is_dns_subdomain(pod),
+        "invalid namespace or pod name"
+    );
+    let path = format!("/api/v1/namespaces/{}/pods/{}/exec", namespace, pod);
+    client.websocket(&path, &request.query(), PROTOCOL).await
+}
+
+pub fn stdin_frame(data: &[u8]) -> Message {
+    let mut frame = Vec::with_capacity(data.len() + 1);
+    frame.push(STDIN);
+    frame.extend_from_slice(data);
+    Message::Binary(frame)
+}
+
+pub fn resize_frame(cols: u16, rows: u16) -> Message {
+    let mut frame = vec![RESIZE];
+    frame.extend_from_slice(json!({ "Width": cols, "Height": rows }).to_string().as_bytes());
+    Message::Binary(frame)
+}
+
+/// How the command ended, from the error channel's `Status` object.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExitStatus {
+    /// `None` when the session failed without an exit code
+    pub code: Option<i32>,
+    pub message: Option<String>,
+}
+
+impl ExitStatus {
+    fn from_status(status: &Value) -> Self {
+        if status.get("status").and_then(Value::as_str) == Some("Success") {
+            return Self { code: Some(0), message: None };
+        }
+        let code = status
+            .pointer("/details/causes")
+            .and_then(Value::as_array)
+            .and_then(|causes| {
+                causes
+                    .iter()
+                    .find(|c| c.get("reason").and_then(Value::as_str) == Some("ExitCode"))
+            })
+            .and_then(|c| c.get("message").and_then(Value::as_str))
+            .and_then(|m| m.parse().ok());
+        Self {
+            code,
+            message: status.get("message").and_then(Value::as_str).map(str::to_string),
+        }
+    }
+}
+
+/// A decoded frame from the API server.
+#[derive(Debug)]
+pub enum ExecOutput {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+    Exit(ExitStatus),
+}
+
+/// Decodes one upstream message; control frames and empty or unknown
+/// channels yield `None`.
+pub fn decode(message: Message) -> Option<ExecOutput> {
+    let data = match message {
+        Message::Binary(data) => data,
+        Message::Text(text) => text.into_bytes(),
+        _ => return None,
+    };
+    let (channel, payload) = data.split_first()?;
+    match *channel {
+        STDOUT if !payload.is_empty() => Some(ExecOutput::Stdout(payload.to_vec())),
+        STDERR if !payload.is_empty() => Some(ExecOutput::Stderr(payload.to_vec())),
+        ERROR if !payload.is_empty() => {
+            let status = serde_json::from_slice::<Value>(payload)
+                .unwrap_or_else(|_| json!({ "message": String::from_utf8_lossy(payload) }));
+            Some(ExecOutput::Exit(ExitStatus::from_status(&status)))
+        }
+        _ => None,
+    }
+}
+
+/// Messages the browser sends as JSON text; binary messages are raw stdin.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Stdin { data: String },
+    Resize { cols: u16, rows: u16 },
+}
+
+/// Messages sent to the browser as JSON text.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Stdout { data: String },
+    Stderr { data: String },
+    Exit(ExitStatus),
+    /// The session was ended by KubeAtlas (timeout) or failed
+    Error { message: String },
+}
+
+/// Turns a byte stream into text without breaking multi-byte characters
+/// that are split across frames.
+#[derive(Debug, Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    /// Invalid bytes become U+FFFD; an incomplete sequence at the end waits
+    /// for the next frame.
+    pub fn decode(&mut self, bytes: &[u8]) -> String {
+        self.pending.extend_from_slice(bytes);
+        let mut text = String::new();
+        loop {
+            let error = match std::str::from_utf8(&self.pending) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    self.pending.clear();
+                    return text;
+                }
+                Err(e) => e,
+            };
+            let valid = error.valid_up_to();
+            text.push_str(&String::from_utf8_lossy(&self.pending[..valid]));
+            match error.error_len() {
+                Some(invalid) => {
+                    text.push(char::REPLACEMENT_CHARACTER);
+                    self.pending.drain(..valid + invalid);
+                }
+                None => {
+                    self.pending.drain(..valid);
+                    return text;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encoded_query_escapes_arguments() {
+        let request = ExecRequest {
+            container: Some("app".to_string()),
+            command: vec!["sh".to_string(), "-c".to_string(), "echo a&b=c #x".to_string()],
+            tty: true,
+        };
+        assert_eq!(
+            request.encoded_query(),
+            "command=sh&command=-c&command=echo+a%26b%3Dc+%23x&container=app\
+             &stdin=true&stdout=true&stderr=false&tty=true"
+        );
+    }
+
+    #[test]
+    fn utf8_decoder_joins_characters_split_across_frames() {
+        let mut decoder = Utf8Decoder::default();
+        let bytes = "ü€".as_bytes();
+        assert_eq!(decoder.decode(&bytes[..1]), "");
+        assert_eq!(decoder.decode(&bytes[1..3]), "ü");
+        assert_eq!(decoder.decode(&bytes[3..]), "€");
+        assert_eq!(decoder.decode(b"ok"), "ok");
+    }
+
+    #[test]
+    fn utf8_decoder_replaces_invalid_bytes() {
+        let mut decoder = Utf8Decoder::default();
+        // An invalid byte followed by the start of a split character
+        assert_eq!(decoder.decode(&[b'a', 0xff, b'b', 0xe2, 0x82]), "a\u{FFFD}b");
+        assert_eq!(decoder.decode(&[0xac]), "€");
+        assert_eq!(decoder.decode(&[0xe2, b'x']), "\u{FFFD}x");
+    }
+
+    #[test]
+    fn decode_frames() {
+        assert!(matches!(
+            decode(Message::Binary(vec![STDOUT, b'h', b'i'])),
+            Some(ExecOutput::Stdout(data)) if data == b"hi"
+        ));
+        assert!(decode(Message::Binary(vec![STDERR])).is_none());
+        let status = br#"{"status":"Failure","message":"command terminated with non-zero exit code","details":{"causes":[{"reason":"ExitCode","message":"2"}]}}"#;
+        let mut frame = vec![ERROR];
+        frame.extend_from_slice(status);
+        match decode(Message::Binary(frame)) {
+            Some(ExecOutput::Exit(exit)) => assert_eq!(exit.code, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::{ensure, Result};
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use reqwest::Method;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::{
+    kube_client::{check_status, KubeClient},
+    validation::{is_dns_label, is_dns_subdomain},
+};
+
+/// Pods × containers a single aggregated request may follow at once.
+pub const MAX_AGGREGATED_STREAMS: usize = 50;
+
+/// Options of the Kubernetes `pods/log` subresource.
+#[derive(Debug, Clone, Default)]
+pub struct LogOptions {
+    pub follow: bool,
+    pub tail_lines: Option<i64>,
+    pub since_seconds: Option<i64>,
+    pub timestamps: bool,
+    pub previous: bool,
+}
+
+impl LogOptions {
+    fn query(&self, container: Option<&str>) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(container) = container {
+            query.push(("container", container.to_string()));
+        }
+        if self.follow {
+            query.push(("follow", "true".to_string()));
+        }
+        if let Some(lines) = self.tail_lines {
+            query.push(("tailLines", lines.to_string()));
+        }
+        if let Some(seconds) = self.since_seconds {
+            query.push(("sinceSeconds", seconds.to_string()));
+        }
+        if self.timestamps {
+            query.push(("timestamps", "true".to_string()));
+        }
+        if self.previous {
+            query.push(("previous", "true".to_string()));
+        }
+        query
+    }
+}
+
+/// One container whose log is part of an aggregated stream.
+#[derive(Debug, Clone)]
+pub struct LogSource {
+    pub pod: String,
+    pub container: String,
+}
+
+/// Opens the log of one pod; fails before any data is read if the API server
+/// rejects the request (unknown pod, container required, ...).
+pub async fn open(
+    client: &KubeClient,
+    namespace: &str,
+    pod: &str,
+    container: Option<&str>,
+    options: &LogOptions,
+) -> Result<impl Stream<Item = reqwest::Result<Bytes>>> {
+    ensure!(
+        is_dns_label(namespace) && is_dns_subdomain(pod),
+        "invalid namespace or pod name"
+    );
+    let path = format!("/api/v1/namespaces/{}/pods/{}/log", namespace, pod);
+    let response = client
+        .request(Method::GET, &path)
+        .query(&options.query(container))
+        .send()
+        .await?;
+    Ok(check_status(response).await?.bytes_stream())
+}
+
+/// Splits a byte stream into lines (newline included), sending each with
+/// `prefix` prepended. Returns when the stream ends or the receiver is gone,
+/// even while a followed log is quiet, which closes the upstream connection.
+pub async fn forward_lines(
+    mut stream: impl Stream<Item = reqwest::Result<Bytes>> + Unpin,
+    prefix: &str,
+    tx: &mpsc::Sender<String>,
+) -> Result<()> {
+    let mut buffer: Vec<u8> = Vec::new();
+    loop {
+        let chunk = tokio::select! {
+            chunk = stream.next() => chunk,
+            _ = tx.closed() => return Ok(()),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        buffer.extend_from_slice(&chunk?);
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if tx
+                .send(format!("{}{}", prefix, String::from_utf8_lossy(&line)))
+                .await
+                .is_err()
+            {
+                return Ok(());
+            }
+        }
+    }
+    if !buffer.is_empty() {
+        let _ = tx
+            .send(format!("{}{}\n", prefix, String::from_utf8_lossy(&buffer)))
+            .await;
+    }
+    Ok(())
+}
+
+/// Containers of the pods matching `label_selector`; with `container` set only
+/// that container of each pod (pods without it are skipped).
+pub async fn sources(
+    client: &KubeClient,
+    namespace: &str,
+    label_selector: &str,
+    container: Option<&str>,
+) -> Result<Vec<LogSource>> {
+    ensure!(is_dns_label(namespace), "invalid namespace name");
+    let path = format!("/api/v1/namespaces/{}/pods", namespace);
+    let (pods, _) = client
+        .list_with_version(&path, &[("labelSelector", label_selector.to_string())], 500)
+        .await?;
+    let mut sources = Vec::new();
+    for pod in &pods {
+        let name = pod
+            .pointer("/metadata/name")
+            .and_then(Value::as_str)
+            .unwrap_or_default();
+        let containers = pod
+            .pointer("/spec/containers")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for c in containers {
+            let Some(c) = c.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            if container.is_none_or(|wanted| wanted == c) {
+                sources.push(LogSource {
+                    pod: name.to_string(),
+                    container: c.to_string(),
+                });
+            }
+        }
+    }
+    Ok(sources)
+}
+
+/// Follows every source concurrently and merges their lines, each prefixed
+/// with `[pod/container]`. The channel closes when all logs have ended.
+pub fn aggregate(
+    client: KubeClient,
+    namespace: String,
+    sources: Vec<LogSource>,
+    options: LogOptions,
+) -> mpsc::Receiver<String> {
+    let (tx, rx) = mpsc::channel(1024);
+    for source in sources {
+        let (client, namespace, options, tx) = (client.clone(), namespace.clone(), options.clone(), tx.clone());
+        tokio::spawn(async move {
+            let prefix = format!("[{}/{}] ", source.pod, source.container);
+            let result = match open(&client, &namespace, &source.pod, Some(&source.container), &options).await {
+                Ok(stream) => forward_lines(Box::pin(stream), &prefix, &tx).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                let _ = tx.send(format!("{}error: {:#}\n", prefix, e)).await;
+            }
+        });
+    }
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::stream;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn forward_lines_splits_and_prefixes() {
+        let chunks = ["first\nsec", "ond\n", "tail"].map(|c| Ok(Bytes::from(c)));
+        let (tx, mut rx) = mpsc::channel(8);
+        forward_lines(stream::iter(chunks), "[web-0/app] ", &tx).await.unwrap();
+        drop(tx);
+        let mut lines = Vec::new();
+        while let Some(line) = rx.recv().await {
+            lines.push(line);
+        }
+        assert_eq!(lines, ["[web-0/app] first\n", "[web-0/app] second\n", "[web-0/app] tail\n"]);
+    }
+
+    #[tokio::test]
+    async fn forward_lines_stops_when_the_receiver_leaves_a_quiet_log() {
+        let quiet = stream::iter([Ok(Bytes::from("hello\n"))]).chain(stream::pending());
+        let (tx, mut rx) = mpsc::channel(8);
+        let forward = tokio::spawn(async move { forward_lines(Box::pin(quiet), "", &tx).await });
+        assert_eq!(rx.recv().await.as_deref(), Some("hello\n"));
+        drop(rx);
+        let result = tokio::time::timeout(Duration::from_secs(5), forward).await;
+        assert!(result.expect("forwarder kept running").unwrap().is_ok());
+    }
+}
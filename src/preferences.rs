@@ -0,0 +1,243 @@
+use anyhow::Result;
+use serde_json::Value;
+
+use crate::models::UserPreferences;
+use crate::repositories::PreferencesRepository;
+use crate::validation::{is_dns_label, ValidationErrors};
+
+const MAX_SELECTED_NAMESPACES: usize = 100;
+const MAX_TABLE_LAYOUTS: usize = 50;
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Applies an RFC 7396 JSON merge patch: objects merge recursively,
+/// `null` removes a key, anything else replaces the target.
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch_obj) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    if let Value::Object(target_obj) = target {
+        for (key, value) in patch_obj {
+            if value.is_null() {
+                target_obj.remove(key);
+            } else {
+                merge_patch(target_obj.entry(key.clone()).or_insert(Value::Null), value);
+            }
+        }
+    }
+}
+
+/// Accepts BCP 47 style tags such as `en`, `ru` or `pt-BR`.
+fn is_language_tag(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let primary_ok = parts
+        .next()
+        .is_some_and(|p| (2..=3).contains(&p.len()) && p.chars().all(|c| c.is_ascii_lowercase()));
+    primary_ok && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Checks the merged document against the preferences schema.
+pub fn validate(document: Value) -> Result<UserPreferences, ValidationErrors> {
+    let prefs: UserPreferences = serde_json::from_value(document)
+        .map_err(|e| ValidationErrors::single("preferences", e.to_string()))?;
+
+    let mut errors = ValidationErrors::new();
+    if prefs.selected_namespaces.len() > MAX_SELECTED_NAMESPACES {
+        errors.add(
+            "selected_namespaces",
+            format!("must contain at most {} namespaces", MAX_SELECTED_NAMESPACES),
+        );
+    }
+    for ns in &prefs.selected_namespaces {
+        if !is_dns_label(ns) {
+            errors.add("selected_namespaces", format!("'{}' is not a valid namespace name", ns));
+        }
+    }
+    if !is_language_tag(&prefs.language) {
+        errors.add("language", "must be a language tag such as 'en' or 'pt-BR'");
+    }
+    if prefs.table_layouts.len() > MAX_TABLE_LAYOUTS {
+        errors.add(
+            "table_layouts",
+            format!("must contain at most {} tables", MAX_TABLE_LAYOUTS),
+        );
+    }
+    for (table, layout) in &prefs.table_layouts {
+        let field = format!("table_layouts.{}", table);
+        if table.trim().is_empty() {
+            errors.add("table_layouts", "table id must not be empty");
+        }
+        if let Some(page_size) = layout.page_size {
+            if page_size == 0 || page_size > MAX_PAGE_SIZE {
+                errors.add(
+                    &format!("{}.page_size", field),
+                    format!("must be between 1 and {}", MAX_PAGE_SIZE),
+                );
+            }
+        }
+        if let Some(sort_by) = &layout.sort_by {
+            if !layout.columns.is_empty() && !layout.columns.contains(sort_by) {
+                errors.add(
+                    &format!("{}.sort_by", field),
+                    format!("column '{}' is not in columns", sort_by),
+                );
+            }
+        }
+    }
+
+    errors.into_result().map(|_| prefs)
+}
+
+pub async fn load(repo: &PreferencesRepository, user_sub: &str) -> Result<UserPreferences> {
+    let stored = repo.find(user_sub).await?;
+    // Stored documents were validated on write; fall back to defaults if the schema moved on
+    Ok(stored
+        .and_then(|doc| serde_json::from_value(doc).ok())
+        .unwrap_or_default())
+}
+
+/// Merges `patch` into the stored preferences and saves the validated result.
+/// The outer error is a storage failure, the inner one a schema violation.
+pub async fn apply_patch(
+    repo: &PreferencesRepository,
+    user_sub: &str,
+    patch: &Value,
+) -> Result<Result<UserPreferences, ValidationErrors>> {
+    let saved = repo
+        .update(user_sub, |stored| {
+            let current: UserPreferences = serde_json::from_value(stored).unwrap_or_default();
+            let mut document = serde_json::to_value(&current)
+                .map_err(|e| ValidationErrors::single("preferences", e.to_string()))?;
+            merge_patch(&mut document, patch);
+            let prefs = validate(document)?;
+            serde_json::to_value(&prefs)
+                .map_err(|e| ValidationErrors::single("preferences", e.to_string()))
+        })
+        .await?;
+    Ok(saved.map(|doc| serde_json::from_value(doc).unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn patched(mut target: Value, patch: Value) -> Value {
+        merge_patch(&mut target, &patch);
+        target
+    }
+
+    fn errors(document: Value) -> Vec<String> {
+        validate(document).unwrap_err().messages()
+    }
+
+    #[test]
+    fn merge_patch_follows_rfc_7396() {
+        // Examples from RFC 7396, appendix A
+        assert_eq!(patched(json!({"a": "b"}), json!({"a": "c"})), json!({"a": "c"}));
+        assert_eq!(patched(json!({"a": "b"}), json!({"b": "c"})), json!({"a": "b", "b": "c"}));
+        assert_eq!(patched(json!({"a": "b"}), json!({"a": null})), json!({}));
+        assert_eq!(patched(json!({"a": "b", "b": "c"}), json!({"a": null})), json!({"b": "c"}));
+        assert_eq!(patched(json!({"a": ["b"]}), json!({"a": "c"})), json!({"a": "c"}));
+        assert_eq!(patched(json!({"a": "c"}), json!({"a": ["b"]})), json!({"a": ["b"]}));
+        assert_eq!(
+            patched(json!({"a": {"b": "c"}}), json!({"a": {"b": "d", "c": null}})),
+            json!({"a": {"b": "d"}})
+        );
+        assert_eq!(patched(json!({"a": [{"b": "c"}]}), json!({"a": [1]})), json!({"a": [1]}));
+        assert_eq!(patched(json!(["a", "b"]), json!(["c", "d"])), json!(["c", "d"]));
+        assert_eq!(patched(json!({"a": "b"}), json!(["c"])), json!(["c"]));
+        assert_eq!(patched(json!({"a": "foo"}), json!(null)), json!(null));
+        assert_eq!(patched(json!({"a": "foo"}), json!("bar")), json!("bar"));
+        assert_eq!(patched(json!({"e": null}), json!({"a": 1})), json!({"e": null, "a": 1}));
+        assert_eq!(patched(json!([1, 2]), json!({"a": "b", "c": null})), json!({"a": "b"}));
+        assert_eq!(patched(json!({}), json!({"a": {"bb": {"ccc": null}}})), json!({"a": {"bb": {}}}));
+    }
+
+    #[test]
+    fn merge_patch_merges_nested_table_layouts() {
+        let stored = json!({
+            "theme": "dark",
+            "table_layouts": {"pods": {"columns": ["name", "status"], "page_size": 50}}
+        });
+        let patch = json!({
+            "theme": null,
+            "table_layouts": {"pods": {"columns": ["name"], "sort_by": "name"}, "nodes": {"page_size": 20}}
+        });
+        assert_eq!(
+            patched(stored, patch),
+            json!({
+                "table_layouts": {
+                    "pods": {"columns": ["name"], "page_size": 50, "sort_by": "name"},
+                    "nodes": {"page_size": 20}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn validate_accepts_defaults_and_valid_documents() {
+        let prefs = validate(json!({})).unwrap();
+        assert_eq!(prefs.language, "en");
+        assert_eq!(prefs.theme, crate::models::Theme::System);
+
+        let prefs = validate(json!({
+            "selected_namespaces": ["shop", "kube-system"],
+            "theme": "dark",
+            "language": "pt-BR",
+            "table_layouts": {"pods": {"columns": ["name", "age"], "sort_by": "age", "page_size": 500}}
+        }))
+        .unwrap();
+        assert_eq!(prefs.table_layouts["pods"].page_size, Some(500));
+    }
+
+    #[test]
+    fn validate_rejects_unknown_keys_and_types() {
+        let messages = errors(json!({"colour": "red"}));
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].starts_with("preferences: unknown field `colour`"), "{:?}", messages);
+
+        let messages = errors(json!({"table_layouts": {"pods": {"hidden": [], "zoom": 2}}}));
+        assert!(messages[0].contains("unknown field `zoom`"), "{:?}", messages);
+
+        let messages = errors(json!({"theme": "neon"}));
+        assert!(messages[0].starts_with("preferences: unknown variant `neon`"), "{:?}", messages);
+    }
+
+    #[test]
+    fn validate_reports_each_field() {
+        let namespaces: Vec<String> = (0..=MAX_SELECTED_NAMESPACES).map(|i| format!("ns-{}", i)).collect();
+        assert_eq!(
+            errors(json!({"selected_namespaces": namespaces})),
+            ["selected_namespaces: must contain at most 100 namespaces"]
+        );
+        assert_eq!(
+            errors(json!({
+                "selected_namespaces": ["shop", "Shop_Prod"],
+                "language": "english",
+                "table_layouts": {
+                    "pods": {"columns": ["name"], "sort_by": "age", "page_size": 0},
+                    "nodes": {"page_size": 501},
+                    " ": {}
+                }
+            })),
+            [
+                "language: must be a language tag such as 'en' or 'pt-BR'",
+                "selected_namespaces: 'Shop_Prod' is not a valid namespace name",
+                "table_layouts: table id must not be empty",
+                "table_layouts.nodes.page_size: must be between 1 and 500",
+                "table_layouts.pods.page_size: must be between 1 and 500",
+                "table_layouts.pods.sort_by: column 'age' is not in columns"
+            ]
+        );
+        let layouts: serde_json::Map<String, Value> =
+            (0..=MAX_TABLE_LAYOUTS).map(|i| (format!("t{}", i), json!({}))).collect();
+        assert_eq!(
+            errors(json!({"table_layouts": layouts})),
+            ["table_layouts: must contain at most 50 tables"]
+        );
+    }
+}
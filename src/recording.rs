@@ -0,0 +1,426 @@
+use anyhow::{Context, Result};
+use chrono::{Duration as ChronoDuration, Utc};
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs::File;
+use tokio::io::{AsyncWriteExt, BufWriter};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::RecordingConfig;
+use crate::models::{ExecRecording, RecordingStatus};
+use crate::recording_store::{CastStream, RecordingStore};
+use crate::repositories::{NewRecording, RecordingRepository};
+
+/// Recordings removed per purge query.
+const PURGE_BATCH: i64 = 100;
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// Who started which session; the header of the recording.
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    pub user_id: String,
+    pub username: String,
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub namespace: String,
+    pub pod: String,
+    pub container: Option<String>,
+    pub command: Vec<String>,
+    /// Terminal size in columns and rows, if the browser sent one
+    pub size: Option<(u16, u16)>,
+}
+
+/// Records exec sessions and keeps the stored casts within retention.
+#[derive(Clone)]
+pub struct Recordings {
+    repo: RecordingRepository,
+    store: Arc<RecordingStore>,
+    config: RecordingConfig,
+}
+
+impl Recordings {
+    pub fn new(repo: RecordingRepository, store: RecordingStore, config: RecordingConfig) -> Self {
+        Self {
+            repo,
+            store: Arc::new(store),
+            config,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn spool_path(&self, id: Uuid) -> PathBuf {
+        PathBuf::from(&self.config.directory)
+            .join(".spool")
+            .join(format!("{}.cast", id))
+    }
+
+    /// Creates the recording row and the spool file and writes the asciicast
+    /// header. The session must not start if this fails.
+    pub async fn start(&self, session: &SessionInfo) -> Result<Recorder> {
+        let id = Uuid::new_v4();
+        let started_at = Utc::now();
+        let storage_key = format!("{}/{}.cast", started_at.format("%Y/%m/%d"), id);
+        let spool = self.spool_path(id);
+        if let Some(parent) = spool.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("cannot create {}", parent.display()))?;
+        }
+        let mut file = BufWriter::new(
+            File::create(&spool)
+                .await
+                .with_context(|| format!("cannot create {}", spool.display()))?,
+        );
+
+        let (width, height) = session.size.unwrap_or(DEFAULT_SIZE);
+        let mut title = format!(
+            "{}@{}/{}/{}",
+            session.username, session.cluster_name, session.namespace, session.pod
+        );
+        if let Some(container) = &session.container {
+            title.push_str(&format!("/{}", container));
+        }
+        let header = json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": started_at.timestamp(),
+            "command": session.command.join(" "),
+            "title": title,
+            "env": { "TERM": "xterm-256color" }
+        });
+        file.write_all(format!("{}\n", header).as_bytes()).await?;
+        file.flush().await?;
+
+        self.repo
+            .create(&NewRecording {
+                id,
+                user_id: session.user_id.clone(),
+                username: session.username.clone(),
+                cluster_id: session.cluster_id,
+                cluster_name: session.cluster_name.clone(),
+                namespace: session.namespace.clone(),
+                pod: session.pod.clone(),
+                container: session.container.clone(),
+                command: session.command.join(" "),
+                storage_key: storage_key.clone(),
+                instance_id: self.config.instance_id.clone(),
+            })
+            .await?;
+        Ok(Recorder {
+            id,
+            storage_key,
+            spool,
+            file,
+            started: Instant::now(),
+            recordings: self.clone(),
+            finished: false,
+        })
+    }
+
+    /// Stores the spool file and marks the recording finished; returns the
+    /// status it ended with, `failed` if the cast could not be stored.
+    async fn store(
+        &self,
+        id: Uuid,
+        storage_key: &str,
+        spool: &PathBuf,
+        status: RecordingStatus,
+        exit_code: Option<i32>,
+        reason: &str,
+    ) -> Result<RecordingStatus> {
+        let size = tokio::fs::metadata(spool).await.ok().map(|m| m.len() as i64);
+        if let Err(e) = self.store.put(storage_key, spool).await {
+            // The spool file is kept so the cast can still be rescued by hand
+            warn!("Failed to store recording {}: {:#}", id, e);
+            let reason = format!("{}; storing the cast failed: {:#}", reason, e);
+            self.repo
+                .finish(id, RecordingStatus::Failed, size, exit_code, &reason)
+                .await?;
+            return Ok(RecordingStatus::Failed);
+        }
+        self.repo.finish(id, status, size, exit_code, reason).await?;
+        Ok(status)
+    }
+
+    /// Handles recordings this instance left running before a restart: their
+    /// spool files are stored as they are and marked `interrupted`. Rows
+    /// without an instance are only taken once older than `max_session`.
+    pub async fn recover(&self, max_session: Duration) -> Result<()> {
+        let stale_before = Utc::now() - ChronoDuration::from_std(max_session)?;
+        for recording in self.repo.in_progress(&self.config.instance_id, stale_before).await? {
+            let spool = self.spool_path(recording.id);
+            let result = if tokio::fs::try_exists(&spool).await.unwrap_or(false) {
+                self.store(
+                    recording.id,
+                    &recording.storage_key,
+                    &spool,
+                    RecordingStatus::Interrupted,
+                    None,
+                    "server restarted",
+                )
+                .await
+            } else {
+                self.repo
+                    .finish(recording.id, RecordingStatus::Interrupted, None, None, "spool file lost")
+                    .await
+                    .map(|_| RecordingStatus::Interrupted)
+            };
+            match result {
+                Ok(RecordingStatus::Failed) => {}
+                Ok(_) => info!("Recording {} marked as interrupted", recording.id),
+                Err(e) => warn!("Failed to recover recording {}: {:#}", recording.id, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// The stored cast of a finished recording.
+    pub async fn read(&self, recording: &ExecRecording) -> Result<CastStream> {
+        self.store.get(&recording.storage_key).await
+    }
+
+    /// Deletes recordings past retention, cast first so a failed delete is retried.
+    pub async fn purge(&self) -> Result<usize> {
+        if self.config.retention_days == 0 {
+            return Ok(0);
+        }
+        let before = Utc::now() - ChronoDuration::days(self.config.retention_days as i64);
+        let mut purged = 0;
+        loop {
+            let expired = self.repo.expired(before, PURGE_BATCH).await?;
+            if expired.is_empty() {
+                break;
+            }
+            let batch = expired.len();
+            for recording in expired {
+                if let Err(e) = self.store.delete(&recording.storage_key).await {
+                    warn!("Failed to delete recording {}: {:#}", recording.id, e);
+                    return Ok(purged);
+                }
+                if recording.status == RecordingStatus::Failed {
+                    let spool = self.spool_path(recording.id);
+                    if let Err(e) = tokio::fs::remove_file(&spool).await {
+                        if e.kind() != std::io::ErrorKind::NotFound {
+                            warn!("Failed to delete spool file {}: {}", spool.display(), e);
+                        }
+                    }
+                }
+                self.repo.delete(recording.id).await?;
+                purged += 1;
+            }
+            if (batch as i64) < PURGE_BATCH {
+                break;
+            }
+        }
+        Ok(purged)
+    }
+
+    pub fn spawn_purge(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.purge_interval_secs));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                match self.purge().await {
+                    Ok(0) => {}
+                    Ok(purged) => info!("Purged {} recordings past retention", purged),
+                    Err(e) => warn!("Recording purge failed: {:#}", e),
+                }
+            }
+        })
+    }
+}
+
+/// Something that happened in a session, in asciicast terms.
+#[derive(Debug, Clone)]
+pub enum SessionEvent {
+    /// Terminal output (stdout and stderr alike)
+    Output(String),
+    /// Keystrokes sent to the pod
+    Input(String),
+    /// New terminal size in columns and rows
+    Resize(u16, u16),
+}
+
+/// Writes the events of one running session to its spool file. A recorder
+/// dropped without `finish`, e.g. when the browser never completes the
+/// WebSocket upgrade, still stores its cast and marks it `interrupted`.
+pub struct Recorder {
+    id: Uuid,
+    storage_key: String,
+    spool: PathBuf,
+    file: BufWriter<File>,
+    started: Instant,
+    recordings: Recordings,
+    finished: bool,
+}
+
+impl Recorder {
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Links the recording to the audit entry of its session.
+    pub async fn link_audit(&self, audit_id: Uuid) -> Result<()> {
+        self.recordings.repo.set_audit_id(self.id, audit_id).await
+    }
+
+    async fn event(&mut self, code: &str, data: Value) -> Result<()> {
+        let time = self.started.elapsed().as_secs_f64();
+        let line = json!([(time * 1_000_000.0).round() / 1_000_000.0, code, data]);
+        self.file.write_all(format!("{}\n", line).as_bytes()).await?;
+        Ok(())
+    }
+
+    pub async fn record(&mut self, event: SessionEvent) -> Result<()> {
+        match event {
+            SessionEvent::Output(data) => self.event("o", json!(data)).await,
+            SessionEvent::Input(data) => self.event("i", json!(data)).await,
+            SessionEvent::Resize(cols, rows) => self.event("r", json!(format!("{}x{}", cols, rows))).await,
+        }
+    }
+
+    /// Flushes the cast, hands it to the store and completes the row; a cast
+    /// that cannot be written or stored leaves the row `failed`.
+    pub async fn finish(mut self, exit_code: Option<i32>, reason: &str) {
+        self.finished = true;
+        let result = match self.file.flush().await {
+            Ok(()) => {
+                self.recordings
+                    .store(
+                        self.id,
+                        &self.storage_key,
+                        &self.spool,
+                        RecordingStatus::Complete,
+                        exit_code,
+                        reason,
+                    )
+                    .await
+                    .map(|_| ())
+            }
+            Err(e) => {
+                let reason = format!("{}; writing the cast failed: {}", reason, e);
+                self.recordings
+                    .repo
+                    .finish(self.id, RecordingStatus::Failed, None, exit_code, &reason)
+                    .await
+            }
+        };
+        if let Err(e) = result {
+            warn!("Failed to complete recording {}: {:#}", self.id, e);
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if self.finished {
+            return;
+        }
+        // Outside a runtime the row is left for `recover` after a restart
+        let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+            return;
+        };
+        let (recordings, id, storage_key, spool) =
+            (self.recordings.clone(), self.id, self.storage_key.clone(), self.spool.clone());
+        runtime.spawn(async move {
+            let reason = "session ended before the recording was finished";
+            match recordings
+                .store(id, &storage_key, &spool, RecordingStatus::Interrupted, None, reason)
+                .await
+            {
+                Ok(RecordingStatus::Failed) => {}
+                Ok(_) => info!("Recording {} marked as interrupted", id),
+                Err(e) => warn!("Failed to complete abandoned recording {}: {:#}", id, e),
+            }
+        });
+    }
+}
+
+/// Splits a stored cast into its lines (header first, then one event per
+/// line) as it is read.
+pub struct CastLines {
+    stream: CastStream,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+impl CastLines {
+    pub fn new(stream: CastStream) -> Self {
+        Self {
+            stream,
+            buffer: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// The next line without its newline; `None` at the end of the cast.
+    pub async fn next_line(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = self.buffer.drain(..=end).collect();
+                return Ok(Some(String::from_utf8_lossy(&line[..end]).trim_end_matches('\r').to_string()));
+            }
+            if self.done {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                let line = std::mem::take(&mut self.buffer);
+                return Ok(Some(String::from_utf8_lossy(&line).to_string()));
+            }
+            match self.stream.next().await {
+                Some(chunk) => self.buffer.extend_from_slice(&chunk?),
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+
+    fn cast(chunks: &[&'static str]) -> CastStream {
+        let chunks: Vec<std::io::Result<Bytes>> = chunks.iter().map(|c| Ok(Bytes::from_static(c.as_bytes()))).collect();
+        Box::pin(futures::stream::iter(chunks))
+    }
+
+    async fn lines(mut lines: CastLines) -> Vec<String> {
+        let mut all = Vec::new();
+        while let Some(line) = lines.next_line().await.unwrap() {
+            all.push(line);
+        }
+        all
+    }
+
+    #[tokio::test]
+    async fn cast_lines_span_chunks() {
+        let stream = cast(&["{\"version\": 2}\n[0.5, \"o\", \"h", "i\"]\r\n", "[1.0, \"i\", \"x\"]"]);
+        assert_eq!(
+            lines(CastLines::new(stream)).await,
+            ["{\"version\": 2}", "[0.5, \"o\", \"hi\"]", "[1.0, \"i\", \"x\"]"]
+        );
+        assert!(lines(CastLines::new(cast(&[]))).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn cast_lines_report_read_errors() {
+        let chunks = vec![
+            Ok(Bytes::from_static(b"{}\n")),
+            Err(std::io::Error::other("connection reset")),
+        ];
+        let mut lines = CastLines::new(Box::pin(futures::stream::iter(chunks)));
+        assert_eq!(lines.next_line().await.unwrap().as_deref(), Some("{}"));
+        assert!(lines.next_line().await.is_err());
+    }
+}
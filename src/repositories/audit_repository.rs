@@ -0,0 +1,112 @@
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::AuditEntry;
+
+const AUDIT_COLUMNS: &str = "id, occurred_at, user_id, username, cluster_id, cluster_name, verb, \
+    api_group, resource, namespace, name, path, allowed, status_code, error";
+
+/// An audit record before it is stored.
+#[derive(Debug, Clone)]
+pub struct NewAuditEntry {
+    pub user_id: String,
+    pub username: String,
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub verb: String,
+    pub api_group: String,
+    pub resource: String,
+    pub namespace: Option<String>,
+    pub name: Option<String>,
+    pub path: String,
+    pub allowed: bool,
+    pub status_code: Option<i32>,
+    pub error: Option<String>,
+}
+
+/// Filters for audit reads; all of them are combined with AND.
+#[derive(Debug, Default)]
+pub struct AuditFilter {
+    pub cluster_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub verb: Option<String>,
+    pub allowed: Option<bool>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Clone)]
+pub struct AuditRepository {
+    pool: PgPool,
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &AuditFilter) {
+    query.push(" WHERE true");
+    if let Some(cluster_id) = filter.cluster_id {
+        query.push(" AND cluster_id = ").push_bind(cluster_id);
+    }
+    if let Some(username) = &filter.username {
+        query.push(" AND username = ").push_bind(username.clone());
+    }
+    if let Some(verb) = &filter.verb {
+        query.push(" AND verb = ").push_bind(verb.clone());
+    }
+    if let Some(allowed) = filter.allowed {
+        query.push(" AND allowed = ").push_bind(allowed);
+    }
+}
+
+impl AuditRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Stores `entry` and returns its id.
+    pub async fn record(&self, entry: &NewAuditEntry) -> Result<Uuid> {
+        let id = Uuid::new_v4();
+        sqlx::query(
+            "INSERT INTO audit_log \
+                (id, user_id, username, cluster_id, cluster_name, verb, api_group, resource, \
+                 namespace, name, path, allowed, status_code, error) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+        )
+        .bind(id)
+        .bind(&entry.user_id)
+        .bind(&entry.username)
+        .bind(entry.cluster_id)
+        .bind(&entry.cluster_name)
+        .bind(&entry.verb)
+        .bind(&entry.api_group)
+        .bind(&entry.resource)
+        .bind(&entry.namespace)
+        .bind(&entry.name)
+        .bind(&entry.path)
+        .bind(entry.allowed)
+        .bind(entry.status_code)
+        .bind(&entry.error)
+        .execute(&self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    /// Newest entries first.
+    pub async fn list(&self, filter: &AuditFilter) -> Result<(i64, Vec<AuditEntry>)> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM audit_log");
+        push_filter(&mut count, filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::new(format!("SELECT {} FROM audit_log", AUDIT_COLUMNS));
+        push_filter(&mut query, filter);
+        query
+            .push(" ORDER BY occurred_at DESC LIMIT ")
+            .push_bind(filter.limit)
+            .push(" OFFSET ")
+            .push_bind(filter.offset);
+        let items = query
+            .build_query_as::<AuditEntry>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((total, items))
+    }
+}
@@ -0,0 +1,159 @@
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use super::{map_unique_violation, RepositoryError};
+use crate::models::{Cluster, ClusterHealth, ClusterStatus, ImpersonationSettings};
+
+const CLUSTER_COLUMNS: &str = "id, name, description, endpoint, status, \
+    kubernetes_version, probe_latency_ms, last_error, last_probe_at, last_success_at, \
+    consecutive_failures, consecutive_successes, \
+    impersonation_enabled, impersonation_user_claim, impersonation_user_prefix, \
+    impersonation_group_prefix, impersonation_include_groups, impersonation_include_roles, \
+    created_at, updated_at";
+
+/// Values for a cluster update; `None` keeps the stored value.
+#[derive(Debug, Default)]
+pub struct ClusterChanges {
+    pub name: Option<String>,
+    /// `Some(None)` clears the description
+    pub description: Option<Option<String>>,
+    pub endpoint: Option<String>,
+    pub status: Option<ClusterStatus>,
+    pub impersonation: Option<ImpersonationSettings>,
+}
+
+#[derive(Clone)]
+pub struct ClusterRepository {
+    pool: PgPool,
+}
+
+impl ClusterRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self) -> Result<Vec<Cluster>> {
+        let clusters = sqlx::query_as::<_, Cluster>(&format!(
+            "SELECT {} FROM clusters ORDER BY name",
+            CLUSTER_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(clusters)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<Cluster> {
+        sqlx::query_as::<_, Cluster>(&format!(
+            "SELECT {} FROM clusters WHERE id = $1",
+            CLUSTER_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Cluster '{}'", id)).into())
+    }
+
+    pub async fn create(
+        &self,
+        name: &str,
+        description: Option<&str>,
+        endpoint: &str,
+        impersonation: &ImpersonationSettings,
+    ) -> Result<Cluster> {
+        let cluster = sqlx::query_as::<_, Cluster>(&format!(
+            "INSERT INTO clusters (id, name, description, endpoint, status, \
+                impersonation_enabled, impersonation_user_claim, impersonation_user_prefix, \
+                impersonation_group_prefix, impersonation_include_groups, impersonation_include_roles) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11) RETURNING {}",
+            CLUSTER_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(name)
+        .bind(description)
+        .bind(endpoint)
+        .bind(ClusterStatus::Pending)
+        .bind(impersonation.enabled)
+        .bind(impersonation.user_claim)
+        .bind(&impersonation.user_prefix)
+        .bind(&impersonation.group_prefix)
+        .bind(impersonation.include_groups)
+        .bind(impersonation.include_roles)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| map_unique_violation(e, "name", name))?;
+        Ok(cluster)
+    }
+
+    pub async fn update(&self, id: Uuid, changes: ClusterChanges) -> Result<Cluster> {
+        let name_for_error = changes.name.clone().unwrap_or_default();
+        let clear_description = matches!(changes.description, Some(None));
+        sqlx::query_as::<_, Cluster>(&format!(
+            "UPDATE clusters SET \
+                name = COALESCE($2, name), \
+                description = CASE WHEN $4 THEN NULL ELSE COALESCE($3, description) END, \
+                endpoint = COALESCE($5, endpoint), \
+                status = COALESCE($6, CASE WHEN $5 <> endpoint THEN 'pending' ELSE status END), \
+                consecutive_failures = CASE WHEN $5 <> endpoint THEN 0 ELSE consecutive_failures END, \
+                consecutive_successes = CASE WHEN $5 <> endpoint THEN 0 ELSE consecutive_successes END, \
+                impersonation_enabled = COALESCE($7, impersonation_enabled), \
+                impersonation_user_claim = COALESCE($8, impersonation_user_claim), \
+                impersonation_user_prefix = COALESCE($9, impersonation_user_prefix), \
+                impersonation_group_prefix = COALESCE($10, impersonation_group_prefix), \
+                impersonation_include_groups = COALESCE($11, impersonation_include_groups), \
+                impersonation_include_roles = COALESCE($12, impersonation_include_roles), \
+                updated_at = now() \
+             WHERE id = $1 RETURNING {}",
+            CLUSTER_COLUMNS
+        ))
+        .bind(id)
+        .bind(changes.name)
+        .bind(changes.description.flatten())
+        .bind(clear_description)
+        .bind(changes.endpoint)
+        .bind(changes.status)
+        .bind(changes.impersonation.as_ref().map(|i| i.enabled))
+        .bind(changes.impersonation.as_ref().map(|i| i.user_claim))
+        .bind(changes.impersonation.as_ref().map(|i| i.user_prefix.clone()))
+        .bind(changes.impersonation.as_ref().map(|i| i.group_prefix.clone()))
+        .bind(changes.impersonation.as_ref().map(|i| i.include_groups))
+        .bind(changes.impersonation.as_ref().map(|i| i.include_roles))
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(|e| map_unique_violation(e, "name", &name_for_error))?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Cluster '{}'", id)).into())
+    }
+
+    /// Saves a probe result; `updated_at` is left alone as it tracks admin edits.
+    pub async fn record_probe(&self, id: Uuid, status: ClusterStatus, health: &ClusterHealth) -> Result<()> {
+        sqlx::query(
+            "UPDATE clusters SET status = $2, kubernetes_version = $3, probe_latency_ms = $4, \
+                last_error = $5, last_probe_at = $6, last_success_at = $7, \
+                consecutive_failures = $8, consecutive_successes = $9 \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status)
+        .bind(&health.kubernetes_version)
+        .bind(health.probe_latency_ms)
+        .bind(&health.last_error)
+        .bind(health.last_probe_at)
+        .bind(health.last_success_at)
+        .bind(health.consecutive_failures)
+        .bind(health.consecutive_successes)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM clusters WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Cluster '{}'", id)).into());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use sqlx::{PgPool, Row};
+use uuid::Uuid;
+
+use super::RepositoryError;
+use crate::crypto::SealedSecret;
+use crate::models::{ClusterAuthMethod, ClusterCredentialInfo};
+
+const INFO_COLUMNS: &str =
+    "cluster_id, auth_method, has_ca_bundle, insecure_skip_tls_verify, key_id, updated_at";
+
+/// Cleartext metadata stored next to the sealed credential.
+#[derive(Debug, Clone, Copy)]
+pub struct CredentialMetadata {
+    pub auth_method: ClusterAuthMethod,
+    pub has_ca_bundle: bool,
+    pub insecure_skip_tls_verify: bool,
+}
+
+#[derive(Clone)]
+pub struct CredentialRepository {
+    pool: PgPool,
+}
+
+fn sealed_from_row(row: &sqlx::postgres::PgRow) -> Result<SealedSecret> {
+    Ok(SealedSecret {
+        key_id: row.try_get("key_id")?,
+        wrapped_key: row.try_get("wrapped_key")?,
+        ciphertext: row.try_get("ciphertext")?,
+    })
+}
+
+impl CredentialRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find_info(&self, cluster_id: Uuid) -> Result<Option<ClusterCredentialInfo>> {
+        let info = sqlx::query_as::<_, ClusterCredentialInfo>(&format!(
+            "SELECT {} FROM cluster_credentials WHERE cluster_id = $1",
+            INFO_COLUMNS
+        ))
+        .bind(cluster_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(info)
+    }
+
+    pub async fn find_sealed(&self, cluster_id: Uuid) -> Result<Option<SealedSecret>> {
+        let row = sqlx::query(
+            "SELECT key_id, wrapped_key, ciphertext FROM cluster_credentials WHERE cluster_id = $1",
+        )
+        .bind(cluster_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        row.as_ref().map(sealed_from_row).transpose()
+    }
+
+    /// Inserts or replaces the credential of an existing cluster.
+    pub async fn upsert(
+        &self,
+        cluster_id: Uuid,
+        metadata: CredentialMetadata,
+        sealed: &SealedSecret,
+    ) -> Result<ClusterCredentialInfo> {
+        sqlx::query_as::<_, ClusterCredentialInfo>(&format!(
+            "INSERT INTO cluster_credentials \
+                (cluster_id, auth_method, has_ca_bundle, insecure_skip_tls_verify, key_id, wrapped_key, ciphertext) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7) \
+             ON CONFLICT (cluster_id) DO UPDATE SET \
+                auth_method = EXCLUDED.auth_method, \
+                has_ca_bundle = EXCLUDED.has_ca_bundle, \
+                insecure_skip_tls_verify = EXCLUDED.insecure_skip_tls_verify, \
+                key_id = EXCLUDED.key_id, \
+                wrapped_key = EXCLUDED.wrapped_key, \
+                ciphertext = EXCLUDED.ciphertext, \
+                updated_at = now() \
+             RETURNING {}",
+            INFO_COLUMNS
+        ))
+        .bind(cluster_id)
+        .bind(metadata.auth_method)
+        .bind(metadata.has_ca_bundle)
+        .bind(metadata.insecure_skip_tls_verify)
+        .bind(&sealed.key_id)
+        .bind(&sealed.wrapped_key)
+        .bind(&sealed.ciphertext)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match &e {
+            // Foreign key violation: the cluster does not exist
+            sqlx::Error::Database(db) if db.code().as_deref() == Some("23503") => {
+                RepositoryError::NotFound(format!("Cluster '{}'", cluster_id)).into()
+            }
+            _ => e.into(),
+        })
+    }
+
+    pub async fn delete(&self, cluster_id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM cluster_credentials WHERE cluster_id = $1")
+            .bind(cluster_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(
+                RepositoryError::NotFound(format!("Credential of cluster '{}'", cluster_id)).into(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Re-seals every stored credential in one transaction; any failure rolls
+    /// back so credentials are never left under a mix of old and new keys.
+    pub async fn reseal_all<F>(&self, mut reseal: F) -> Result<usize>
+    where
+        F: FnMut(Uuid, &SealedSecret) -> Result<SealedSecret>,
+    {
+        let mut tx = self.pool.begin().await?;
+        let rows = sqlx::query(
+            "SELECT cluster_id, key_id, wrapped_key, ciphertext FROM cluster_credentials FOR UPDATE",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for row in &rows {
+            let cluster_id: Uuid = row.try_get("cluster_id")?;
+            let sealed = reseal(cluster_id, &sealed_from_row(row)?)?;
+            sqlx::query(
+                "UPDATE cluster_credentials SET key_id = $2, wrapped_key = $3, ciphertext = $4, \
+                 updated_at = now() WHERE cluster_id = $1",
+            )
+            .bind(cluster_id)
+            .bind(&sealed.key_id)
+            .bind(&sealed.wrapped_key)
+            .bind(&sealed.ciphertext)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(rows.len())
+    }
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::ContainerImage;
+
+// Postgres allows 65535 bind parameters per statement
+const INSERT_BATCH: usize = 1000;
+
+/// Images grouped by registry, repository, tag and digest, with the places
+/// each one runs as a JSON array.
+const GROUPED_IMAGES: &str = "SELECT \
+        i.registry || '/' || i.repository \
+            || CASE WHEN i.tag = '' THEN '' ELSE ':' || i.tag END \
+            || CASE WHEN i.digest = '' THEN '' ELSE '@' || i.digest END AS image, \
+        i.registry, i.repository, NULLIF(i.tag, '') AS tag, NULLIF(i.digest, '') AS digest, \
+        i.tag = 'latest' AS latest_tag, \
+        bool_or(position('@' in i.image) = 0) AS mutable_tag, \
+        MIN(i.first_seen) AS first_seen, MAX(i.last_seen) AS last_seen, bool_or(i.running) AS running, \
+        jsonb_agg(jsonb_build_object( \
+            'cluster_id', i.cluster_id, 'cluster_name', c.name, 'namespace', i.namespace, \
+            'workload_kind', i.workload_kind, 'workload_name', i.workload_name, \
+            'container', i.container, 'image', i.image, \
+            'first_seen', i.first_seen, 'last_seen', i.last_seen, 'running', i.running) \
+            ORDER BY c.name, i.namespace, i.workload_kind, i.workload_name, i.container) AS locations \
+    FROM container_images i JOIN clusters c ON c.id = i.cluster_id";
+
+/// Filters for image reads; all of them are combined with AND.
+#[derive(Debug, Default)]
+pub struct ImageFilter {
+    /// Substring of the image reference, case-insensitive
+    pub search: Option<String>,
+    pub cluster_id: Option<Uuid>,
+    pub namespace: Option<String>,
+    /// Only images tagged `latest`
+    pub latest: bool,
+    /// Only images referenced by tag without a digest somewhere
+    pub mutable: bool,
+    /// Only images that are running now
+    pub running: bool,
+    /// Everything when absent
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
+/// Where an image was seen in one pod collection.
+#[derive(Debug, Clone)]
+pub struct NewContainerImage {
+    pub namespace: String,
+    pub workload_kind: String,
+    pub workload_name: String,
+    pub container: String,
+    pub image: String,
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct ImageRepository {
+    pool: PgPool,
+}
+
+fn like_pattern(value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Grouped images matching the filter, unordered and unpaged.
+fn push_grouped(query: &mut QueryBuilder<'_, Postgres>, filter: &ImageFilter) {
+    query.push(GROUPED_IMAGES).push(" WHERE TRUE");
+    if let Some(search) = &filter.search {
+        let pattern = like_pattern(search);
+        query
+            .push(" AND (i.image ILIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR i.registry || '/' || i.repository || ':' || i.tag ILIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+    if let Some(cluster_id) = filter.cluster_id {
+        query.push(" AND i.cluster_id = ").push_bind(cluster_id);
+    }
+    if let Some(namespace) = &filter.namespace {
+        query.push(" AND i.namespace = ").push_bind(namespace.clone());
+    }
+    if filter.latest {
+        query.push(" AND i.tag = 'latest'");
+    }
+    if filter.running {
+        query.push(" AND i.running");
+    }
+    query.push(" GROUP BY i.registry, i.repository, i.tag, i.digest");
+    if filter.mutable {
+        query.push(" HAVING bool_or(position('@' in i.image) = 0)");
+    }
+}
+
+impl ImageRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, filter: &ImageFilter) -> Result<(i64, Vec<ContainerImage>)> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM (");
+        push_grouped(&mut count, filter);
+        count.push(") g");
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::new("");
+        push_grouped(&mut query, filter);
+        query.push(" ORDER BY i.registry, i.repository, i.tag, i.digest");
+        if let Some(limit) = filter.limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+        query.push(" OFFSET ").push_bind(filter.offset);
+        let items = query
+            .build_query_as::<ContainerImage>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((total, items))
+    }
+
+    /// Records the images found in a cluster's latest pod collection: they
+    /// are marked running and their last-seen time moves on; every other
+    /// image of the cluster is marked as no longer running.
+    pub async fn record(&self, cluster_id: Uuid, images: &[NewContainerImage]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE container_images SET running = FALSE WHERE cluster_id = $1 AND running")
+            .bind(cluster_id)
+            .execute(&mut *tx)
+            .await?;
+        for batch in images.chunks(INSERT_BATCH) {
+            let mut insert = QueryBuilder::new(
+                "INSERT INTO container_images \
+                 (cluster_id, namespace, workload_kind, workload_name, container, image, \
+                  registry, repository, tag, digest) ",
+            );
+            insert.push_values(batch, |mut row, image| {
+                row.push_bind(cluster_id)
+                    .push_bind(image.namespace.clone())
+                    .push_bind(image.workload_kind.clone())
+                    .push_bind(image.workload_name.clone())
+                    .push_bind(image.container.clone())
+                    .push_bind(image.image.clone())
+                    .push_bind(image.registry.clone())
+                    .push_bind(image.repository.clone())
+                    .push_bind(image.tag.clone().unwrap_or_default())
+                    .push_bind(image.digest.clone().unwrap_or_default());
+            });
+            insert.push(
+                " ON CONFLICT (cluster_id, namespace, workload_kind, workload_name, container, image, digest) \
+                 DO UPDATE SET last_seen = now(), running = TRUE",
+            );
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,213 @@
+use anyhow::Result;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use crate::label_selector::{LabelSelector, Requirement};
+use crate::models::{AppliedApiVersion, InventoryApiVersions, InventoryKind, InventoryObject, InventoryStatus};
+
+const OBJECT_COLUMNS: &str =
+    "kind, NULLIF(namespace, '') AS namespace, name, uid, labels, details, created_at, collected_at";
+
+// Postgres allows 65535 bind parameters per statement
+const INSERT_BATCH: usize = 1000;
+
+/// Filters for inventory reads; all of them are combined with AND.
+#[derive(Debug, Default)]
+pub struct InventoryFilter {
+    pub kinds: Vec<InventoryKind>,
+    pub namespace: Option<String>,
+    pub selector: LabelSelector,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// A normalized object ready to be stored.
+#[derive(Debug, Clone)]
+pub struct NewInventoryObject {
+    pub namespace: Option<String>,
+    pub name: String,
+    pub uid: String,
+    pub labels: BTreeMap<String, String>,
+    pub details: serde_json::Value,
+    pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// The object without volatile fields, kept for snapshots
+    pub manifest: serde_json::Value,
+    /// API versions the object was written with
+    pub api_versions: Vec<AppliedApiVersion>,
+}
+
+#[derive(Clone)]
+pub struct InventoryRepository {
+    pool: PgPool,
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, cluster_id: Uuid, filter: &InventoryFilter) {
+    query.push(" WHERE cluster_id = ").push_bind(cluster_id);
+    if !filter.kinds.is_empty() {
+        let kinds: Vec<&str> = filter.kinds.iter().map(|k| k.as_str()).collect();
+        query.push(" AND kind = ANY(").push_bind(kinds).push(")");
+    }
+    if let Some(namespace) = &filter.namespace {
+        query.push(" AND namespace = ").push_bind(namespace.clone());
+    }
+    for requirement in &filter.selector.requirements {
+        match requirement {
+            Requirement::Equals(k, v) => {
+                query.push(" AND labels->>").push_bind(k.clone()).push(" = ").push_bind(v.clone());
+            }
+            // Like Kubernetes, `!=` and `notin` also match objects without the label
+            Requirement::NotEquals(k, v) => {
+                query
+                    .push(" AND (labels->>")
+                    .push_bind(k.clone())
+                    .push(") IS DISTINCT FROM ")
+                    .push_bind(v.clone());
+            }
+            Requirement::In(k, values) => {
+                query
+                    .push(" AND labels->>")
+                    .push_bind(k.clone())
+                    .push(" = ANY(")
+                    .push_bind(values.clone())
+                    .push(")");
+            }
+            Requirement::NotIn(k, values) => {
+                query
+                    .push(" AND NOT COALESCE(labels->>")
+                    .push_bind(k.clone())
+                    .push(" = ANY(")
+                    .push_bind(values.clone())
+                    .push("), false)");
+            }
+            Requirement::Exists(k) => {
+                query.push(" AND labels ? ").push_bind(k.clone());
+            }
+            Requirement::DoesNotExist(k) => {
+                query.push(" AND NOT labels ? ").push_bind(k.clone());
+            }
+        }
+    }
+}
+
+impl InventoryRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn list(&self, cluster_id: Uuid, filter: &InventoryFilter) -> Result<(i64, Vec<InventoryObject>)> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM inventory_objects");
+        push_filter(&mut count, cluster_id, filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::new(format!("SELECT {} FROM inventory_objects", OBJECT_COLUMNS));
+        push_filter(&mut query, cluster_id, filter);
+        query
+            .push(" ORDER BY kind, namespace, name LIMIT ")
+            .push_bind(filter.limit)
+            .push(" OFFSET ")
+            .push_bind(filter.offset);
+        let items = query
+            .build_query_as::<InventoryObject>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((total, items))
+    }
+
+    /// Replaces every stored object of `kind` for the cluster in one transaction.
+    pub async fn replace_kind(
+        &self,
+        cluster_id: Uuid,
+        kind: InventoryKind,
+        objects: &[NewInventoryObject],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM inventory_objects WHERE cluster_id = $1 AND kind = $2")
+            .bind(cluster_id)
+            .bind(kind)
+            .execute(&mut *tx)
+            .await?;
+        for batch in objects.chunks(INSERT_BATCH) {
+            let mut insert = QueryBuilder::new(
+                "INSERT INTO inventory_objects \
+                 (cluster_id, kind, namespace, name, uid, labels, details, created_at, manifest, api_versions) ",
+            );
+            insert.push_values(batch, |mut row, object| {
+                row.push_bind(cluster_id)
+                    .push_bind(kind)
+                    .push_bind(object.namespace.clone().unwrap_or_default())
+                    .push_bind(object.name.clone())
+                    .push_bind(object.uid.clone())
+                    .push_bind(sqlx::types::Json(object.labels.clone()))
+                    .push_bind(object.details.clone())
+                    .push_bind(object.created_at)
+                    .push_bind(object.manifest.clone())
+                    .push_bind(sqlx::types::Json(object.api_versions.clone()));
+            });
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Every stored object of the cluster with the API versions it was
+    /// written with, and how many objects record none: those collected
+    /// before the versions were stored, or without managed fields.
+    pub async fn api_versions(&self, cluster_id: Uuid) -> Result<(i64, Vec<InventoryApiVersions>)> {
+        let unrecorded: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM inventory_objects WHERE cluster_id = $1 AND api_versions = '[]'::jsonb",
+        )
+            .bind(cluster_id)
+            .fetch_one(&self.pool)
+            .await?;
+        let objects = sqlx::query_as::<_, InventoryApiVersions>(
+            "SELECT kind, namespace, name, api_versions FROM inventory_objects \
+             WHERE cluster_id = $1 AND api_versions <> '[]'::jsonb ORDER BY kind, namespace, name",
+        )
+        .bind(cluster_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok((unrecorded, objects))
+    }
+
+    pub async fn start_collection(&self, cluster_id: Uuid) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO inventory_collections (cluster_id, started_at) VALUES ($1, now()) \
+             ON CONFLICT (cluster_id) DO UPDATE SET started_at = now(), finished_at = NULL",
+        )
+        .bind(cluster_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the run's errors and recounts what is stored per kind.
+    pub async fn finish_collection(
+        &self,
+        cluster_id: Uuid,
+        errors: &BTreeMap<String, String>,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE inventory_collections SET finished_at = now(), errors = $2, \
+                counts = (SELECT COALESCE(jsonb_object_agg(kind, n), '{}'::jsonb) FROM \
+                    (SELECT kind, COUNT(*) AS n FROM inventory_objects WHERE cluster_id = $1 GROUP BY kind) c) \
+             WHERE cluster_id = $1",
+        )
+        .bind(cluster_id)
+        .bind(sqlx::types::Json(errors))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn status(&self, cluster_id: Uuid) -> Result<Option<InventoryStatus>> {
+        let status = sqlx::query_as::<_, InventoryStatus>(
+            "SELECT cluster_id, started_at, finished_at, counts, errors \
+             FROM inventory_collections WHERE cluster_id = $1",
+        )
+        .bind(cluster_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(status)
+    }
+}
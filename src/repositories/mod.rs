@@ -0,0 +1,80 @@
+// Repository layer: all SQL lives here, one repository per aggregate.
+// Repositories are cheap to clone (they only hold the pool).
+
+pub mod audit_repository;
+pub mod cluster_repository;
+pub mod credential_repository;
+pub mod image_repository;
+pub mod inventory_repository;
+pub mod preferences_repository;
+pub mod recording_repository;
+pub mod snapshot_repository;
+pub mod vulnerability_repository;
+
+pub use audit_repository::{AuditFilter, AuditRepository, NewAuditEntry};
+pub use cluster_repository::{ClusterChanges, ClusterRepository};
+pub use credential_repository::{CredentialMetadata, CredentialRepository};
+pub use image_repository::{ImageFilter, ImageRepository, NewContainerImage};
+pub use inventory_repository::{InventoryFilter, InventoryRepository, NewInventoryObject};
+pub use preferences_repository::PreferencesRepository;
+pub use recording_repository::{NewRecording, RecordingFilter, RecordingRepository};
+pub use snapshot_repository::{SnapshotFilter, SnapshotRepository};
+pub use vulnerability_repository::{NewAdvisory, VulnerabilityRepository};
+
+use sqlx::PgPool;
+
+/// Failures a repository reports for rows or constraints rather than for the
+/// database itself. Handlers turn them into 404 and 409 responses through
+/// `repository_error`; any other error from a repository is a 500.
+#[derive(Debug, thiserror::Error)]
+pub enum RepositoryError {
+    /// No row with the given key; holds what was looked up, e.g. `Cluster '<id>'`
+    #[error("{0} not found")]
+    NotFound(String),
+    /// A unique constraint rejected the write; holds the offending field
+    #[error("{field} '{value}' is already in use")]
+    Conflict { field: String, value: String },
+}
+
+/// Turns a Postgres unique violation (23505) into `RepositoryError::Conflict`.
+pub(crate) fn map_unique_violation(e: sqlx::Error, field: &str, value: &str) -> anyhow::Error {
+    match &e {
+        sqlx::Error::Database(db) if db.code().as_deref() == Some("23505") => {
+            RepositoryError::Conflict {
+                field: field.to_string(),
+                value: value.to_string(),
+            }
+            .into()
+        }
+        _ => e.into(),
+    }
+}
+
+#[derive(Clone)]
+pub struct Repositories {
+    pub preferences: PreferencesRepository,
+    pub clusters: ClusterRepository,
+    pub credentials: CredentialRepository,
+    pub inventory: InventoryRepository,
+    pub images: ImageRepository,
+    pub audit: AuditRepository,
+    pub recordings: RecordingRepository,
+    pub snapshots: SnapshotRepository,
+    pub vulnerabilities: VulnerabilityRepository,
+}
+
+impl Repositories {
+    pub fn new(pool: PgPool) -> Self {
+        Self {
+            preferences: PreferencesRepository::new(pool.clone()),
+            clusters: ClusterRepository::new(pool.clone()),
+            credentials: CredentialRepository::new(pool.clone()),
+            inventory: InventoryRepository::new(pool.clone()),
+            images: ImageRepository::new(pool.clone()),
+            audit: AuditRepository::new(pool.clone()),
+            recordings: RecordingRepository::new(pool.clone()),
+            snapshots: SnapshotRepository::new(pool.clone()),
+            vulnerabilities: VulnerabilityRepository::new(pool),
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use anyhow::Result;
+use serde_json::Value;
+use sqlx::PgPool;
+
+#[derive(Clone)]
+pub struct PreferencesRepository {
+    pool: PgPool,
+}
+
+impl PreferencesRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn find(&self, user_sub: &str) -> Result<Option<Value>> {
+        let stored = sqlx::query_scalar("SELECT preferences FROM user_preferences WHERE user_sub = $1")
+            .bind(user_sub)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(stored)
+    }
+
+    /// Read-modify-write under a row lock so concurrent patches do not lose updates.
+    ///
+    /// `update` receives the stored document (`{}` for a new user) and returns
+    /// either the document to save or a domain error, which rolls back and is
+    /// passed through unchanged.
+    pub async fn update<E, F>(&self, user_sub: &str, update: F) -> Result<std::result::Result<Value, E>>
+    where
+        F: FnOnce(Value) -> std::result::Result<Value, E>,
+    {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "INSERT INTO user_preferences (user_sub, preferences) VALUES ($1, '{}'::jsonb) \
+             ON CONFLICT (user_sub) DO NOTHING",
+        )
+        .bind(user_sub)
+        .execute(&mut *tx)
+        .await?;
+        let stored: Value = sqlx::query_scalar(
+            "SELECT preferences FROM user_preferences WHERE user_sub = $1 FOR UPDATE",
+        )
+        .bind(user_sub)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let document = match update(stored) {
+            Ok(document) => document,
+            Err(e) => {
+                tx.rollback().await?;
+                return Ok(Err(e));
+            }
+        };
+
+        sqlx::query(
+            "UPDATE user_preferences SET preferences = $2, updated_at = now() WHERE user_sub = $1",
+        )
+        .bind(user_sub)
+        .bind(&document)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Ok(document))
+    }
+}
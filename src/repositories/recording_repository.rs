@@ -0,0 +1,186 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use uuid::Uuid;
+
+use super::RepositoryError;
+use crate::models::{ExecRecording, RecordingStatus};
+
+const RECORDING_COLUMNS: &str = "id, audit_id, user_id, username, cluster_id, cluster_name, \
+    namespace, pod, container, command, storage_key, instance_id, status, started_at, ended_at, \
+    size_bytes, exit_code, end_reason";
+
+/// A recording row created when the session starts.
+#[derive(Debug, Clone)]
+pub struct NewRecording {
+    pub id: Uuid,
+    pub user_id: String,
+    pub username: String,
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub namespace: String,
+    pub pod: String,
+    pub container: Option<String>,
+    pub command: String,
+    pub storage_key: String,
+    pub instance_id: String,
+}
+
+/// Filters for recording reads; all of them are combined with AND.
+#[derive(Debug, Default)]
+pub struct RecordingFilter {
+    pub cluster_id: Option<Uuid>,
+    pub username: Option<String>,
+    pub audit_id: Option<Uuid>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Clone)]
+pub struct RecordingRepository {
+    pool: PgPool,
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, filter: &RecordingFilter) {
+    query.push(" WHERE true");
+    if let Some(cluster_id) = filter.cluster_id {
+        query.push(" AND cluster_id = ").push_bind(cluster_id);
+    }
+    if let Some(username) = &filter.username {
+        query.push(" AND username = ").push_bind(username.clone());
+    }
+    if let Some(audit_id) = filter.audit_id {
+        query.push(" AND audit_id = ").push_bind(audit_id);
+    }
+}
+
+impl RecordingRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub async fn create(&self, recording: &NewRecording) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO exec_recordings \
+                (id, user_id, username, cluster_id, cluster_name, namespace, pod, container, \
+                 command, storage_key, instance_id) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        )
+        .bind(recording.id)
+        .bind(&recording.user_id)
+        .bind(&recording.username)
+        .bind(recording.cluster_id)
+        .bind(&recording.cluster_name)
+        .bind(&recording.namespace)
+        .bind(&recording.pod)
+        .bind(&recording.container)
+        .bind(&recording.command)
+        .bind(&recording.storage_key)
+        .bind(&recording.instance_id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_audit_id(&self, id: Uuid, audit_id: Uuid) -> Result<()> {
+        sqlx::query("UPDATE exec_recordings SET audit_id = $2 WHERE id = $1")
+            .bind(id)
+            .bind(audit_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn finish(
+        &self,
+        id: Uuid,
+        status: RecordingStatus,
+        size_bytes: Option<i64>,
+        exit_code: Option<i32>,
+        end_reason: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            "UPDATE exec_recordings \
+             SET status = $2, ended_at = now(), size_bytes = $3, exit_code = $4, end_reason = $5 \
+             WHERE id = $1",
+        )
+        .bind(id)
+        .bind(status)
+        .bind(size_bytes)
+        .bind(exit_code)
+        .bind(end_reason)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<ExecRecording> {
+        sqlx::query_as::<_, ExecRecording>(&format!(
+            "SELECT {} FROM exec_recordings WHERE id = $1",
+            RECORDING_COLUMNS
+        ))
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Recording '{}'", id)).into())
+    }
+
+    /// Newest recordings first.
+    pub async fn list(&self, filter: &RecordingFilter) -> Result<(i64, Vec<ExecRecording>)> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM exec_recordings");
+        push_filter(&mut count, filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::new(format!("SELECT {} FROM exec_recordings", RECORDING_COLUMNS));
+        push_filter(&mut query, filter);
+        query
+            .push(" ORDER BY started_at DESC LIMIT ")
+            .push_bind(filter.limit)
+            .push(" OFFSET ")
+            .push_bind(filter.offset);
+        let items = query
+            .build_query_as::<ExecRecording>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((total, items))
+    }
+
+    /// Recordings of `instance_id` still marked as running, e.g. after a
+    /// restart, plus those without an instance started before `stale_before`.
+    pub async fn in_progress(&self, instance_id: &str, stale_before: DateTime<Utc>) -> Result<Vec<ExecRecording>> {
+        let recordings = sqlx::query_as::<_, ExecRecording>(&format!(
+            "SELECT {} FROM exec_recordings \
+             WHERE status = 'recording' \
+               AND (instance_id = $1 OR (instance_id IS NULL AND started_at < $2))",
+            RECORDING_COLUMNS
+        ))
+        .bind(instance_id)
+        .bind(stale_before)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(recordings)
+    }
+
+    /// Finished recordings started before `before`, oldest first.
+    pub async fn expired(&self, before: DateTime<Utc>, limit: i64) -> Result<Vec<ExecRecording>> {
+        let recordings = sqlx::query_as::<_, ExecRecording>(&format!(
+            "SELECT {} FROM exec_recordings \
+             WHERE started_at < $1 AND status <> 'recording' \
+             ORDER BY started_at LIMIT $2",
+            RECORDING_COLUMNS
+        ))
+        .bind(before)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(recordings)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM exec_recordings WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
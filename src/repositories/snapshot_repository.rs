@@ -0,0 +1,250 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::RepositoryError;
+use crate::models::{ClusterSnapshot, InventoryKind, SnapshotEntry, SnapshotTrigger};
+
+const SNAPSHOT_COLUMNS: &str =
+    "id, cluster_id, taken_at, trigger, created_by, collected_at, object_count, counts";
+
+/// Serializes snapshot creation with the removal of unreferenced manifests,
+/// so a manifest is never deleted between being found and being referenced.
+const MANIFEST_LOCK: i64 = 0x6b61_736e_6170;
+
+/// Filters for snapshot lists; all of them are combined with AND.
+#[derive(Debug, Default)]
+pub struct SnapshotFilter {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+#[derive(Clone)]
+pub struct SnapshotRepository {
+    pool: PgPool,
+}
+
+fn push_filter(query: &mut QueryBuilder<'_, Postgres>, cluster_id: Uuid, filter: &SnapshotFilter) {
+    query.push(" WHERE cluster_id = ").push_bind(cluster_id);
+    if let Some(from) = filter.from {
+        query.push(" AND taken_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.to {
+        query.push(" AND taken_at <= ").push_bind(to);
+    }
+}
+
+impl SnapshotRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Copies the cluster's stored inventory into a new snapshot. Objects
+    /// collected before manifests were kept are left out.
+    pub async fn create(
+        &self,
+        cluster_id: Uuid,
+        trigger: SnapshotTrigger,
+        created_by: Option<&str>,
+    ) -> Result<ClusterSnapshot> {
+        let id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MANIFEST_LOCK)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            "INSERT INTO snapshot_manifests (hash, manifest) \
+             SELECT DISTINCT ON (hash) hash, manifest FROM \
+                (SELECT encode(sha256(convert_to(manifest::text, 'UTF8')), 'hex') AS hash, manifest \
+                 FROM inventory_objects WHERE cluster_id = $1 AND manifest IS NOT NULL) m \
+             ON CONFLICT (hash) DO NOTHING",
+        )
+        .bind(cluster_id)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO cluster_snapshots (id, cluster_id, trigger, created_by, collected_at) \
+             VALUES ($1, $2, $3, $4, \
+                (SELECT finished_at FROM inventory_collections WHERE cluster_id = $2))",
+        )
+        .bind(id)
+        .bind(cluster_id)
+        .bind(trigger)
+        .bind(created_by)
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query(
+            "INSERT INTO snapshot_objects (snapshot_id, kind, namespace, name, hash) \
+             SELECT $1, kind, namespace, name, encode(sha256(convert_to(manifest::text, 'UTF8')), 'hex') \
+             FROM inventory_objects WHERE cluster_id = $2 AND manifest IS NOT NULL",
+        )
+        .bind(id)
+        .bind(cluster_id)
+        .execute(&mut *tx)
+        .await?;
+        let snapshot = sqlx::query_as::<_, ClusterSnapshot>(&format!(
+            "UPDATE cluster_snapshots SET \
+                object_count = (SELECT COUNT(*) FROM snapshot_objects WHERE snapshot_id = $1), \
+                counts = (SELECT COALESCE(jsonb_object_agg(kind, n), '{{}}'::jsonb) FROM \
+                    (SELECT kind, COUNT(*) AS n FROM snapshot_objects WHERE snapshot_id = $1 GROUP BY kind) c) \
+             WHERE id = $1 RETURNING {}",
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(snapshot)
+    }
+
+    /// Newest snapshots first.
+    pub async fn list(&self, cluster_id: Uuid, filter: &SnapshotFilter) -> Result<(i64, Vec<ClusterSnapshot>)> {
+        let mut count = QueryBuilder::new("SELECT COUNT(*) FROM cluster_snapshots");
+        push_filter(&mut count, cluster_id, filter);
+        let total: i64 = count.build_query_scalar().fetch_one(&self.pool).await?;
+
+        let mut query = QueryBuilder::new(format!("SELECT {} FROM cluster_snapshots", SNAPSHOT_COLUMNS));
+        push_filter(&mut query, cluster_id, filter);
+        query
+            .push(" ORDER BY taken_at DESC LIMIT ")
+            .push_bind(filter.limit)
+            .push(" OFFSET ")
+            .push_bind(filter.offset);
+        let items = query
+            .build_query_as::<ClusterSnapshot>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok((total, items))
+    }
+
+    pub async fn get(&self, cluster_id: Uuid, id: Uuid) -> Result<ClusterSnapshot> {
+        sqlx::query_as::<_, ClusterSnapshot>(&format!(
+            "SELECT {} FROM cluster_snapshots WHERE cluster_id = $1 AND id = $2",
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(cluster_id)
+        .bind(id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Snapshot '{}'", id)).into())
+    }
+
+    /// The latest snapshot taken at or before `at`.
+    pub async fn at(&self, cluster_id: Uuid, at: DateTime<Utc>) -> Result<ClusterSnapshot> {
+        sqlx::query_as::<_, ClusterSnapshot>(&format!(
+            "SELECT {} FROM cluster_snapshots WHERE cluster_id = $1 AND taken_at <= $2 \
+             ORDER BY taken_at DESC LIMIT 1",
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(cluster_id)
+        .bind(at)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| RepositoryError::NotFound(format!("Snapshot at or before {}", at.to_rfc3339())).into())
+    }
+
+    pub async fn latest(&self, cluster_id: Uuid) -> Result<Option<ClusterSnapshot>> {
+        let snapshot = sqlx::query_as::<_, ClusterSnapshot>(&format!(
+            "SELECT {} FROM cluster_snapshots WHERE cluster_id = $1 ORDER BY taken_at DESC LIMIT 1",
+            SNAPSHOT_COLUMNS
+        ))
+        .bind(cluster_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(snapshot)
+    }
+
+    /// Clusters whose inventory finished collecting after their latest
+    /// snapshot was taken from it, or that have no snapshot yet.
+    pub async fn due(&self) -> Result<Vec<Uuid>> {
+        let ids = sqlx::query_scalar(
+            "SELECT c.cluster_id FROM inventory_collections c \
+             WHERE c.finished_at IS NOT NULL \
+             AND EXISTS (SELECT 1 FROM inventory_objects o \
+                 WHERE o.cluster_id = c.cluster_id AND o.manifest IS NOT NULL) \
+             AND NOT EXISTS (SELECT 1 FROM cluster_snapshots s \
+                 WHERE s.cluster_id = c.cluster_id AND s.collected_at >= c.finished_at)",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ids)
+    }
+
+    /// Objects of a snapshot, optionally limited to some kinds and a namespace.
+    pub async fn entries(
+        &self,
+        snapshot_id: Uuid,
+        kinds: &[InventoryKind],
+        namespace: Option<&str>,
+    ) -> Result<Vec<SnapshotEntry>> {
+        let mut query = QueryBuilder::new(
+            "SELECT kind, namespace, name, hash FROM snapshot_objects WHERE snapshot_id = ",
+        );
+        query.push_bind(snapshot_id);
+        if !kinds.is_empty() {
+            let kinds: Vec<&str> = kinds.iter().map(|k| k.as_str()).collect();
+            query.push(" AND kind = ANY(").push_bind(kinds).push(")");
+        }
+        if let Some(namespace) = namespace {
+            query.push(" AND namespace = ").push_bind(namespace.to_string());
+        }
+        let entries = query
+            .build_query_as::<SnapshotEntry>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(entries)
+    }
+
+    pub async fn manifests(&self, hashes: &[String]) -> Result<HashMap<String, Value>> {
+        let rows: Vec<(String, Value)> =
+            sqlx::query_as("SELECT hash, manifest FROM snapshot_manifests WHERE hash = ANY($1)")
+                .bind(hashes)
+                .fetch_all(&self.pool)
+                .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn delete(&self, cluster_id: Uuid, id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM cluster_snapshots WHERE cluster_id = $1 AND id = $2")
+            .bind(cluster_id)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Snapshot '{}'", id)).into());
+        }
+        Ok(())
+    }
+
+    /// Deletes snapshots taken before `before` and every manifest no snapshot
+    /// refers to any more. Returns the number of snapshots deleted.
+    pub async fn purge(&self, before: Option<DateTime<Utc>>) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(MANIFEST_LOCK)
+            .execute(&mut *tx)
+            .await?;
+        let mut deleted = 0;
+        if let Some(before) = before {
+            deleted = sqlx::query("DELETE FROM cluster_snapshots WHERE taken_at < $1")
+                .bind(before)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+        }
+        sqlx::query(
+            "DELETE FROM snapshot_manifests m \
+             WHERE NOT EXISTS (SELECT 1 FROM snapshot_objects o WHERE o.hash = m.hash)",
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+}
@@ -0,0 +1,387 @@
+use anyhow::Result;
+use sqlx::{types::Json, PgPool, Postgres, QueryBuilder};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use super::RepositoryError;
+use crate::models::{
+    ClusterVulnerabilitySummary, FeedFormat, ImageSbom, ImageSbomDetails, SbomFormat, SbomPackage, Severity,
+    VulnerabilityFeed, VulnerabilityFinding, WorkloadVulnerabilitySummary,
+};
+use crate::vulnerability_matching::{release_matches, Affected};
+
+// Postgres allows 65535 bind parameters per statement
+const INSERT_BATCH: usize = 1000;
+
+const FEED_COLUMNS: &str = "id, name, format, advisory_count, imported_at, imported_by";
+const SBOM_COLUMNS: &str = "digest, format, package_count, uploaded_at, uploaded_by";
+
+/// Distinct advisories per severity of the joined `image_vulnerabilities v`.
+const SEVERITY_COUNTS: &str = "\
+    COUNT(DISTINCT v.advisory_id) FILTER (WHERE v.severity = 'critical') AS critical, \
+    COUNT(DISTINCT v.advisory_id) FILTER (WHERE v.severity = 'high') AS high, \
+    COUNT(DISTINCT v.advisory_id) FILTER (WHERE v.severity = 'medium') AS medium, \
+    COUNT(DISTINCT v.advisory_id) FILTER (WHERE v.severity = 'low') AS low, \
+    COUNT(DISTINCT v.advisory_id) FILTER (WHERE v.severity = 'unknown') AS unknown";
+
+/// One advisory for one package, as parsed from a feed.
+#[derive(Debug, Clone)]
+pub struct NewAdvisory {
+    pub advisory_id: String,
+    pub ecosystem: String,
+    pub release: Option<String>,
+    pub package: String,
+    pub severity: Severity,
+    pub summary: Option<String>,
+    pub aliases: Vec<String>,
+    pub affected: Affected,
+}
+
+#[derive(sqlx::FromRow)]
+struct AdvisoryRow {
+    feed_id: Uuid,
+    advisory_id: String,
+    ecosystem: String,
+    release: String,
+    package: String,
+    severity: Severity,
+    affected: Json<Affected>,
+}
+
+struct Match {
+    feed_id: Uuid,
+    advisory_id: String,
+    ecosystem: String,
+    release: String,
+    package: String,
+    version: String,
+    severity: Severity,
+    fixed_version: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct VulnerabilityRepository {
+    pool: PgPool,
+}
+
+fn non_empty(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+impl VulnerabilityRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Stores a feed's advisories, replacing those of an earlier import under
+    /// the same name. Images must be matched again afterwards.
+    pub async fn import_feed(
+        &self,
+        name: &str,
+        format: FeedFormat,
+        advisories: &[NewAdvisory],
+        imported_by: Option<&str>,
+    ) -> Result<VulnerabilityFeed> {
+        let mut tx = self.pool.begin().await?;
+        let feed = sqlx::query_as::<_, VulnerabilityFeed>(&format!(
+            "INSERT INTO vulnerability_feeds (id, name, format, advisory_count, imported_by) \
+             VALUES ($1, $2, $3, $4, $5) \
+             ON CONFLICT (name) DO UPDATE SET format = EXCLUDED.format, \
+                advisory_count = EXCLUDED.advisory_count, imported_at = now(), \
+                imported_by = EXCLUDED.imported_by \
+             RETURNING {}",
+            FEED_COLUMNS
+        ))
+        .bind(Uuid::new_v4())
+        .bind(name)
+        .bind(format)
+        .bind(advisories.len() as i32)
+        .bind(imported_by)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM image_vulnerabilities WHERE feed_id = $1")
+            .bind(feed.id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM advisories WHERE feed_id = $1")
+            .bind(feed.id)
+            .execute(&mut *tx)
+            .await?;
+        for batch in advisories.chunks(INSERT_BATCH) {
+            let mut insert = QueryBuilder::new(
+                "INSERT INTO advisories \
+                 (feed_id, advisory_id, ecosystem, release, package, severity, summary, aliases, affected) ",
+            );
+            insert.push_values(batch, |mut row, advisory| {
+                row.push_bind(feed.id)
+                    .push_bind(advisory.advisory_id.clone())
+                    .push_bind(advisory.ecosystem.clone())
+                    .push_bind(non_empty(&advisory.release))
+                    .push_bind(advisory.package.clone())
+                    .push_bind(advisory.severity)
+                    .push_bind(advisory.summary.clone())
+                    .push_bind(Json(advisory.aliases.clone()))
+                    .push_bind(Json(advisory.affected.clone()));
+            });
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(feed)
+    }
+
+    pub async fn list_feeds(&self) -> Result<Vec<VulnerabilityFeed>> {
+        let feeds = sqlx::query_as::<_, VulnerabilityFeed>(&format!(
+            "SELECT {} FROM vulnerability_feeds ORDER BY name",
+            FEED_COLUMNS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(feeds)
+    }
+
+    /// Removes a feed with its advisories and the findings they produced.
+    pub async fn delete_feed(&self, id: Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM vulnerability_feeds WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("Vulnerability feed '{}'", id)).into());
+        }
+        Ok(())
+    }
+
+    /// Stores an image's SBOM, replacing an earlier one.
+    pub async fn put_sbom(
+        &self,
+        digest: &str,
+        format: SbomFormat,
+        packages: &[SbomPackage],
+        uploaded_by: Option<&str>,
+    ) -> Result<ImageSbom> {
+        let mut tx = self.pool.begin().await?;
+        let sbom = sqlx::query_as::<_, ImageSbom>(&format!(
+            "INSERT INTO image_sboms (digest, format, package_count, uploaded_by) VALUES ($1, $2, $3, $4) \
+             ON CONFLICT (digest) DO UPDATE SET format = EXCLUDED.format, \
+                package_count = EXCLUDED.package_count, uploaded_at = now(), \
+                uploaded_by = EXCLUDED.uploaded_by \
+             RETURNING {}",
+            SBOM_COLUMNS
+        ))
+        .bind(digest)
+        .bind(format)
+        .bind(packages.len() as i32)
+        .bind(uploaded_by)
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query("DELETE FROM sbom_packages WHERE digest = $1")
+            .bind(digest)
+            .execute(&mut *tx)
+            .await?;
+        for batch in packages.chunks(INSERT_BATCH) {
+            let mut insert =
+                QueryBuilder::new("INSERT INTO sbom_packages (digest, ecosystem, release, name, version, purl) ");
+            insert.push_values(batch, |mut row, package| {
+                row.push_bind(digest.to_string())
+                    .push_bind(package.ecosystem.clone())
+                    .push_bind(non_empty(&package.release))
+                    .push_bind(package.name.clone())
+                    .push_bind(package.version.clone())
+                    .push_bind(package.purl.clone());
+            });
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(sbom)
+    }
+
+    pub async fn get_sbom(&self, digest: &str) -> Result<ImageSbom> {
+        sqlx::query_as::<_, ImageSbom>(&format!("SELECT {} FROM image_sboms WHERE digest = $1", SBOM_COLUMNS))
+            .bind(digest)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| RepositoryError::NotFound(format!("SBOM of '{}'", digest)).into())
+    }
+
+    async fn packages(&self, digest: &str) -> Result<Vec<SbomPackage>> {
+        let packages = sqlx::query_as::<_, SbomPackage>(
+            "SELECT ecosystem, NULLIF(release, '') AS release, name, version, purl \
+             FROM sbom_packages WHERE digest = $1 ORDER BY ecosystem, name, version",
+        )
+        .bind(digest)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(packages)
+    }
+
+    pub async fn sbom_details(&self, digest: &str) -> Result<ImageSbomDetails> {
+        let sbom = self.get_sbom(digest).await?;
+        let packages = self.packages(digest).await?;
+        Ok(ImageSbomDetails { sbom, packages })
+    }
+
+    pub async fn delete_sbom(&self, digest: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM image_sboms WHERE digest = $1")
+            .bind(digest)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(RepositoryError::NotFound(format!("SBOM of '{}'", digest)).into());
+        }
+        Ok(())
+    }
+
+    pub async fn sbom_digests(&self) -> Result<Vec<String>> {
+        let digests = sqlx::query_scalar("SELECT digest FROM image_sboms ORDER BY digest")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(digests)
+    }
+
+    /// Matches an image's SBOM packages against every imported advisory and
+    /// replaces its findings. Returns the number of findings.
+    pub async fn rematch(&self, digest: &str) -> Result<usize> {
+        let packages = self.packages(digest).await?;
+        let advisories = sqlx::query_as::<_, AdvisoryRow>(
+            "SELECT a.feed_id, a.advisory_id, a.ecosystem, a.release, a.package, a.severity, a.affected \
+             FROM advisories a \
+             JOIN (SELECT DISTINCT ecosystem, name FROM sbom_packages WHERE digest = $1) p \
+                ON p.ecosystem = a.ecosystem AND p.name = a.package",
+        )
+        .bind(digest)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut by_package: HashMap<(&str, &str), Vec<&AdvisoryRow>> = HashMap::new();
+        for advisory in &advisories {
+            by_package
+                .entry((advisory.ecosystem.as_str(), advisory.package.as_str()))
+                .or_default()
+                .push(advisory);
+        }
+
+        let mut matches = Vec::new();
+        for package in &packages {
+            let candidates = by_package
+                .get(&(package.ecosystem.as_str(), package.name.as_str()))
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for advisory in candidates {
+                let release = (!advisory.release.is_empty()).then_some(advisory.release.as_str());
+                if !release_matches(release, package.release.as_deref()) || !advisory.affected.matches(&package.version) {
+                    continue;
+                }
+                matches.push(Match {
+                    feed_id: advisory.feed_id,
+                    advisory_id: advisory.advisory_id.clone(),
+                    ecosystem: advisory.ecosystem.clone(),
+                    release: advisory.release.clone(),
+                    package: package.name.clone(),
+                    version: package.version.clone(),
+                    severity: advisory.severity,
+                    fixed_version: advisory.affected.fixed_version(&package.version),
+                });
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM image_vulnerabilities WHERE digest = $1")
+            .bind(digest)
+            .execute(&mut *tx)
+            .await?;
+        for batch in matches.chunks(INSERT_BATCH) {
+            let mut insert = QueryBuilder::new(
+                "INSERT INTO image_vulnerabilities \
+                 (digest, feed_id, advisory_id, ecosystem, release, package, version, severity, fixed_version) ",
+            );
+            insert.push_values(batch, |mut row, m| {
+                row.push_bind(digest.to_string())
+                    .push_bind(m.feed_id)
+                    .push_bind(m.advisory_id.clone())
+                    .push_bind(m.ecosystem.clone())
+                    .push_bind(m.release.clone())
+                    .push_bind(m.package.clone())
+                    .push_bind(m.version.clone())
+                    .push_bind(m.severity)
+                    .push_bind(m.fixed_version.clone());
+            });
+            insert.push(" ON CONFLICT DO NOTHING");
+            insert.build().execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(matches.len())
+    }
+
+    /// An image's findings, most severe first.
+    pub async fn findings(&self, digest: &str) -> Result<Vec<VulnerabilityFinding>> {
+        let findings = sqlx::query_as::<_, VulnerabilityFinding>(
+            "SELECT v.advisory_id, a.aliases, a.summary, v.severity, f.name AS feed, \
+                v.ecosystem, v.package, v.version, v.fixed_version \
+             FROM image_vulnerabilities v \
+             JOIN vulnerability_feeds f ON f.id = v.feed_id \
+             JOIN advisories a ON a.feed_id = v.feed_id AND a.advisory_id = v.advisory_id \
+                AND a.ecosystem = v.ecosystem AND a.release = v.release AND a.package = v.package \
+             WHERE v.digest = $1 \
+             ORDER BY array_position(ARRAY['critical', 'high', 'medium', 'low', 'unknown'], v.severity), \
+                v.advisory_id, v.package, v.version",
+        )
+        .bind(digest)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(findings)
+    }
+
+    /// Per cluster: running image digests, how many have an SBOM, and the
+    /// advisories found in them.
+    pub async fn cluster_summaries(&self) -> Result<Vec<ClusterVulnerabilitySummary>> {
+        let summaries = sqlx::query_as::<_, ClusterVulnerabilitySummary>(&format!(
+            "SELECT c.id AS cluster_id, c.name AS cluster_name, \
+                COUNT(DISTINCT i.digest) FILTER (WHERE i.digest <> '') AS images, \
+                COUNT(DISTINCT s.digest) AS scanned, \
+                (SELECT COUNT(*) FROM container_images u \
+                    WHERE u.cluster_id = c.id AND u.running AND u.digest = '') AS unresolved, \
+                {} \
+             FROM clusters c \
+             LEFT JOIN container_images i ON i.cluster_id = c.id AND i.running \
+             LEFT JOIN image_sboms s ON s.digest = i.digest \
+             LEFT JOIN image_vulnerabilities v ON v.digest = i.digest \
+             GROUP BY c.id, c.name ORDER BY c.name",
+            SEVERITY_COUNTS
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(summaries)
+    }
+
+    /// Per running workload of a cluster, the most affected first.
+    pub async fn workload_summaries(
+        &self,
+        cluster_id: Uuid,
+        namespace: Option<&str>,
+    ) -> Result<Vec<WorkloadVulnerabilitySummary>> {
+        let mut query: QueryBuilder<'_, Postgres> = QueryBuilder::new(format!(
+            "SELECT i.namespace, i.workload_kind, i.workload_name, \
+                COALESCE(array_agg(DISTINCT i.digest) FILTER (WHERE i.digest <> ''), '{{}}') AS images, \
+                COUNT(DISTINCT s.digest) AS scanned, \
+                {} \
+             FROM container_images i \
+             LEFT JOIN image_sboms s ON s.digest = i.digest \
+             LEFT JOIN image_vulnerabilities v ON v.digest = i.digest \
+             WHERE i.running AND i.cluster_id = ",
+            SEVERITY_COUNTS
+        ));
+        query.push_bind(cluster_id);
+        if let Some(namespace) = namespace {
+            query.push(" AND i.namespace = ").push_bind(namespace.to_string());
+        }
+        query.push(
+            " GROUP BY i.namespace, i.workload_kind, i.workload_name \
+             ORDER BY critical DESC, high DESC, medium DESC, low DESC, \
+                i.namespace, i.workload_kind, i.workload_name",
+        );
+        let summaries = query
+            .build_query_as::<WorkloadVulnerabilitySummary>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(summaries)
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use futures::StreamExt;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+use crate::kube_client::{check_status, KubeApiError, KubeClient};
+use crate::models::InventoryKind;
+
+const LIST_PAGE_SIZE: usize = 500;
+/// The API server ends watches after `timeoutSeconds`; they are resumed from
+/// the last seen resourceVersion.
+const WATCH_TIMEOUT_SECS: u64 = 300;
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A message sent to the browser. Object events mirror Kubernetes watch events;
+/// `SYNCED` follows the initial list, `RESET` means the client must drop what it
+/// has for the kind (the resourceVersion expired) before a fresh list arrives.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WatchMessage {
+    Added { kind: InventoryKind, resource_version: String, object: Value },
+    Modified { kind: InventoryKind, resource_version: String, object: Value },
+    Deleted { kind: InventoryKind, resource_version: String, object: Value },
+    Bookmark { kind: InventoryKind, resource_version: String },
+    Synced { kind: InventoryKind, resource_version: String },
+    Reset { kind: InventoryKind, reason: String },
+    /// The watch of `kind` stopped for good, e.g. the cluster denied it
+    Error { kind: InventoryKind, code: u16, message: String },
+}
+
+/// Server-side filters passed to the API server.
+#[derive(Debug, Clone, Default)]
+pub struct WatchFilter {
+    pub namespace: Option<String>,
+    pub label_selector: Option<String>,
+    pub field_selector: Option<String>,
+}
+
+impl WatchFilter {
+    fn query(&self) -> Vec<(&'static str, String)> {
+        let mut query = Vec::new();
+        if let Some(selector) = &self.label_selector {
+            query.push(("labelSelector", selector.clone()));
+        }
+        if let Some(selector) = &self.field_selector {
+            query.push(("fieldSelector", selector.clone()));
+        }
+        query
+    }
+}
+
+enum WatchEnd {
+    /// The server closed the stream; resume from the returned version
+    Closed(String),
+    /// The version is too old (410 Gone); relist
+    Expired,
+    /// The receiver went away
+    Disconnected,
+}
+
+fn resource_version(object: &Value) -> String {
+    object
+        .pointer("/metadata/resourceVersion")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Errors a retry will not fix.
+fn is_fatal(e: &anyhow::Error) -> Option<&KubeApiError> {
+    e.downcast_ref::<KubeApiError>().filter(|api| {
+        matches!(
+            api.status,
+            StatusCode::BAD_REQUEST | StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::NOT_FOUND
+        )
+    })
+}
+
+fn is_expired(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<KubeApiError>()
+        .is_some_and(|api| api.status == StatusCode::GONE)
+}
+
+async fn stream_events(
+    client: &KubeClient,
+    kind: InventoryKind,
+    path: &str,
+    filter: &WatchFilter,
+    from: String,
+    tx: &mpsc::Sender<WatchMessage>,
+) -> Result<WatchEnd> {
+    let response = client
+        .request(Method::GET, path)
+        .query(&filter.query())
+        .query(&[
+            ("watch", "1".to_string()),
+            ("allowWatchBookmarks", "true".to_string()),
+            ("resourceVersion", from.clone()),
+            ("timeoutSeconds", WATCH_TIMEOUT_SECS.to_string()),
+        ])
+        .send()
+        .await?;
+    let mut stream = check_status(response).await?.bytes_stream();
+
+    let mut last = from;
+    let mut buffer: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        buffer.extend_from_slice(&chunk?);
+        // The API server sends one JSON event per line
+        while let Some(end) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=end).collect();
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let event: Value = serde_json::from_slice(&line)?;
+            let object = event.get("object").cloned().unwrap_or(Value::Null);
+            let message = match event.get("type").and_then(Value::as_str) {
+                Some("ERROR") => {
+                    let code = object.get("code").and_then(Value::as_u64).unwrap_or(500) as u16;
+                    if code == StatusCode::GONE.as_u16() {
+                        return Ok(WatchEnd::Expired);
+                    }
+                    let message = object.get("message").and_then(Value::as_str).unwrap_or_default();
+                    return Err(KubeApiError {
+                        status: StatusCode::from_u16(code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                        message: message.to_string(),
+                    }
+                    .into());
+                }
+                Some("BOOKMARK") => {
+                    last = resource_version(&object);
+                    WatchMessage::Bookmark { kind, resource_version: last.clone() }
+                }
+                Some(event_type) => {
+                    last = resource_version(&object);
+                    let resource_version = last.clone();
+                    match event_type {
+                        "ADDED" => WatchMessage::Added { kind, resource_version, object },
+                        "MODIFIED" => WatchMessage::Modified { kind, resource_version, object },
+                        "DELETED" => WatchMessage::Deleted { kind, resource_version, object },
+                        other => return Err(anyhow!("unknown watch event type '{}'", other)),
+                    }
+                }
+                None => return Err(anyhow!("watch event without a type")),
+            };
+            if tx.send(message).await.is_err() {
+                return Ok(WatchEnd::Disconnected);
+            }
+        }
+    }
+    Ok(WatchEnd::Closed(last))
+}
+
+/// Lists (unless resuming from `from`) and then watches `kind`, sending every
+/// event to `tx` until the receiver goes away or the cluster refuses the watch.
+/// Transient failures are retried with backoff from the last seen version.
+pub async fn watch_kind(
+    client: KubeClient,
+    kind: InventoryKind,
+    filter: WatchFilter,
+    from: Option<String>,
+    tx: mpsc::Sender<WatchMessage>,
+) {
+    let path = match kind.collection_path(filter.namespace.as_deref()) {
+        Ok(path) => path,
+        Err(e) => {
+            let _ = tx
+                .send(WatchMessage::Error {
+                    kind,
+                    code: StatusCode::BAD_REQUEST.as_u16(),
+                    message: e.to_string(),
+                })
+                .await;
+            return;
+        }
+    };
+    let mut from = from.filter(|v| !v.is_empty());
+    let mut backoff = Duration::from_secs(1);
+    loop {
+        let result = match from.clone() {
+            None => {
+                match client.list_with_version(&path, &filter.query(), LIST_PAGE_SIZE).await {
+                    Ok((items, version)) => {
+                        for object in items {
+                            let resource_version = resource_version(&object);
+                            if tx.send(WatchMessage::Added { kind, resource_version, object }).await.is_err() {
+                                return;
+                            }
+                        }
+                        let synced = WatchMessage::Synced { kind, resource_version: version.clone() };
+                        if tx.send(synced).await.is_err() {
+                            return;
+                        }
+                        from = Some(version);
+                        continue;
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            Some(version) => stream_events(&client, kind, &path, &filter, version, &tx).await,
+        };
+
+        match result {
+            Ok(WatchEnd::Disconnected) => return,
+            Ok(WatchEnd::Closed(version)) => {
+                debug!("Watch of {} closed by the server at {}", kind.as_str(), version);
+                from = Some(version);
+                backoff = Duration::from_secs(1);
+                continue;
+            }
+            Ok(WatchEnd::Expired) => {
+                from = None;
+            }
+            Err(e) if is_expired(&e) => {
+                from = None;
+            }
+            Err(e) => {
+                if let Some(api) = is_fatal(&e) {
+                    let _ = tx
+                        .send(WatchMessage::Error {
+                            kind,
+                            code: api.status.as_u16(),
+                            message: api.message.clone(),
+                        })
+                        .await;
+                    return;
+                }
+                warn!("Watch of {} failed, retrying in {:?}: {:#}", kind.as_str(), backoff, e);
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = tx.closed() => return,
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        }
+
+        // Expired: the client has to start over from a fresh list
+        let reset = WatchMessage::Reset {
+            kind,
+            reason: "resource version expired".to_string(),
+        };
+        if tx.send(reset).await.is_err() {
+            return;
+        }
+    }
+}
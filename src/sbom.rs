@@ -0,0 +1,204 @@
+use anyhow::{anyhow, Context, Result};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+use crate::inventory_collector::{array_at, str_at};
+use crate::models::{SbomFormat, SbomPackage};
+use crate::vulnerability_matching::normalize_package;
+
+/// Debian and Ubuntu codenames that appear in `distro` qualifiers.
+const CODENAMES: [(&str, &str); 9] = [
+    ("buster", "10"),
+    ("bullseye", "11"),
+    ("bookworm", "12"),
+    ("trixie", "13"),
+    ("bionic", "18.04"),
+    ("focal", "20.04"),
+    ("jammy", "22.04"),
+    ("noble", "24.04"),
+    ("xenial", "16.04"),
+];
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(byte) = value.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The release in a `distro` qualifier: `debian-12` → `12`,
+/// `alpine-3.18.4` → `3.18.4`, `bookworm` → `12`.
+fn distro_release(distro: &str) -> Option<String> {
+    let release = distro.rsplit('-').next().unwrap_or(distro);
+    if release.starts_with(|c: char| c.is_ascii_digit()) {
+        return Some(release.to_string());
+    }
+    CODENAMES
+        .iter()
+        .find(|(codename, _)| distro.contains(codename))
+        .map(|(_, release)| release.to_string())
+}
+
+/// Splits a package URL (`pkg:deb/debian/openssl@3.0.11-1?distro=debian-12`)
+/// into the ecosystem, release, name and version advisories use.
+pub fn parse_purl(purl: &str) -> Option<SbomPackage> {
+    let rest = purl.strip_prefix("pkg:")?;
+    let rest = rest.split('#').next().unwrap_or(rest);
+    let (rest, qualifiers) = match rest.split_once('?') {
+        Some((rest, qualifiers)) => (rest, qualifiers),
+        None => (rest, ""),
+    };
+    let (path, version) = rest.rsplit_once('@')?;
+    let (kind, path) = path.split_once('/')?;
+    let (namespace, name) = match path.rsplit_once('/') {
+        Some((namespace, name)) => (Some(percent_decode(namespace)), percent_decode(name)),
+        None => (None, percent_decode(path)),
+    };
+    let qualifier = |key: &str| {
+        qualifiers
+            .split('&')
+            .find_map(|q| q.strip_prefix(key).and_then(|v| v.strip_prefix('=')))
+            .map(percent_decode)
+    };
+    let kind = kind.to_ascii_lowercase();
+    let (ecosystem, name) = match (kind.as_str(), namespace) {
+        ("deb" | "rpm", Some(namespace)) => (namespace.to_ascii_lowercase(), name),
+        ("apk", _) => ("alpine".to_string(), name),
+        ("npm", Some(scope)) => ("npm".to_string(), format!("{}/{}", scope, name)),
+        ("golang", Some(namespace)) => ("go".to_string(), format!("{}/{}", namespace, name)),
+        ("maven", Some(group)) => ("maven".to_string(), format!("{}:{}", group.replace('/', "."), name)),
+        ("composer", Some(vendor)) => ("packagist".to_string(), format!("{}/{}", vendor, name)),
+        ("gem", _) => ("rubygems".to_string(), name),
+        ("cargo", _) => ("crates.io".to_string(), name),
+        ("golang", None) => ("go".to_string(), name),
+        (kind, _) => (kind.to_string(), name),
+    };
+    let release = match kind.as_str() {
+        "deb" | "rpm" | "apk" => qualifier("distro").and_then(|d| distro_release(&d)),
+        _ => None,
+    };
+    Some(SbomPackage {
+        name: normalize_package(&ecosystem, &name),
+        ecosystem,
+        release,
+        version: percent_decode(version),
+        purl: purl.to_string(),
+    })
+}
+
+fn collect_cyclonedx(components: &[Value], packages: &mut Vec<SbomPackage>) {
+    for component in components {
+        packages.extend(str_at(component, "/purl").and_then(|p| parse_purl(&p)));
+        collect_cyclonedx(array_at(component, "/components"), packages);
+    }
+}
+
+/// Packages of a CycloneDX or SPDX JSON document. Components without a
+/// package URL cannot be matched and are left out.
+pub fn parse(data: &[u8]) -> Result<(SbomFormat, Vec<SbomPackage>)> {
+    let document: Value = serde_json::from_slice(data).context("SBOM is not valid JSON")?;
+    let mut packages = Vec::new();
+    let format = if str_at(&document, "/bomFormat").as_deref() == Some("CycloneDX") {
+        collect_cyclonedx(array_at(&document, "/components"), &mut packages);
+        SbomFormat::CycloneDx
+    } else if document.get("spdxVersion").is_some() {
+        for package in array_at(&document, "/packages") {
+            let purl = array_at(package, "/externalRefs")
+                .iter()
+                .find(|r| str_at(r, "/referenceType").as_deref() == Some("purl"))
+                .and_then(|r| str_at(r, "/referenceLocator"));
+            if let Some(mut parsed) = purl.and_then(|p| parse_purl(&p)) {
+                if let Some(version) = str_at(package, "/versionInfo") {
+                    parsed.version = version;
+                }
+                packages.push(parsed);
+            }
+        }
+        SbomFormat::Spdx
+    } else {
+        return Err(anyhow!("Expected a CycloneDX or SPDX JSON document"));
+    };
+    // One row per package and version, as the primary key requires
+    let unique: BTreeMap<_, _> = packages
+        .into_iter()
+        .map(|p| ((p.ecosystem.clone(), p.name.clone(), p.version.clone()), p))
+        .collect();
+    Ok((format, unique.into_values().collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn purl(value: &str) -> (String, Option<String>, String, String) {
+        let package = parse_purl(value).unwrap();
+        (package.ecosystem, package.release, package.name, package.version)
+    }
+
+    fn expected(ecosystem: &str, release: Option<&str>, name: &str, version: &str) -> (String, Option<String>, String, String) {
+        (ecosystem.to_string(), release.map(str::to_string), name.to_string(), version.to_string())
+    }
+
+    #[test]
+    fn parse_os_package_purls() {
+        assert_eq!(
+            purl("pkg:deb/debian/openssl@3.0.11-1~deb12u2?arch=amd64&distro=debian-12"),
+            expected("debian", Some("12"), "openssl", "3.0.11-1~deb12u2")
+        );
+        assert_eq!(
+            purl("pkg:deb/ubuntu/libc6@2.35-0ubuntu3.6?distro=ubuntu-22.04"),
+            expected("ubuntu", Some("22.04"), "libc6", "2.35-0ubuntu3.6")
+        );
+        assert_eq!(
+            purl("pkg:deb/debian/bash@5.2.15-2%2Bb2?distro=bookworm"),
+            expected("debian", Some("12"), "bash", "5.2.15-2+b2")
+        );
+        assert_eq!(
+            purl("pkg:apk/alpine/busybox@1.36.1-r5?arch=x86_64&distro=alpine-3.18.4"),
+            expected("alpine", Some("3.18.4"), "busybox", "1.36.1-r5")
+        );
+    }
+
+    #[test]
+    fn parse_language_package_purls() {
+        assert_eq!(purl("pkg:npm/%40babel/core@7.23.0"), expected("npm", None, "@babel/core", "7.23.0"));
+        assert_eq!(purl("pkg:npm/lodash@4.17.21"), expected("npm", None, "lodash", "4.17.21"));
+        assert_eq!(
+            purl("pkg:maven/org.apache.logging.log4j/log4j-core@2.14.1?type=jar"),
+            expected("maven", None, "org.apache.logging.log4j:log4j-core", "2.14.1")
+        );
+        assert_eq!(
+            purl("pkg:golang/golang.org/x/net@v0.17.0"),
+            expected("go", None, "golang.org/x/net", "v0.17.0")
+        );
+        assert_eq!(purl("pkg:pypi/Django_Rest.Framework@3.14.0"), expected("pypi", None, "django-rest-framework", "3.14.0"));
+        assert_eq!(purl("pkg:cargo/serde@1.0.190#subpath"), expected("crates.io", None, "serde", "1.0.190"));
+    }
+
+    #[test]
+    fn parse_purl_rejects_incomplete_urls() {
+        assert!(parse_purl("npm/lodash@4.17.21").is_none());
+        assert!(parse_purl("pkg:npm/lodash").is_none());
+        assert!(parse_purl("pkg:lodash@4.17.21").is_none());
+    }
+
+    #[test]
+    fn distro_releases() {
+        assert_eq!(distro_release("debian-12").as_deref(), Some("12"));
+        assert_eq!(distro_release("alpine-3.18.4").as_deref(), Some("3.18.4"));
+        assert_eq!(distro_release("ubuntu-jammy").as_deref(), Some("22.04"));
+        assert_eq!(distro_release("bookworm").as_deref(), Some("12"));
+        assert_eq!(distro_release("wolfi"), None);
+    }
+}
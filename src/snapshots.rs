@@ -0,0 +1,301 @@
+use anyhow::Result;
+use chrono::Utc;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::config::SnapshotConfig;
+use crate::models::{
+    ChangeOp, ChangedObject, ClusterSnapshot, DiffSummary, FieldChange, InventoryKind, ObjectRef,
+    SnapshotDiff, SnapshotEntry, SnapshotTrigger,
+};
+use crate::repositories::SnapshotRepository;
+
+/// Fields that change without anyone changing the object; they are dropped
+/// before a manifest is stored.
+const NOISY_FIELDS: [&str; 5] = [
+    "/status",
+    "/metadata/managedFields",
+    "/metadata/resourceVersion",
+    "/metadata/generation",
+    "/metadata/annotations/kubectl.kubernetes.io~1last-applied-configuration",
+];
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    let Some((parent, key)) = pointer.rsplit_once('/') else {
+        return;
+    };
+    if let Some(Value::Object(map)) = value.pointer_mut(parent) {
+        map.remove(&unescape(key));
+    }
+}
+
+/// The object as stored for snapshots: everything but the noisy fields.
+pub fn manifest(item: &Value) -> Value {
+    let mut manifest = item.clone();
+    for pointer in NOISY_FIELDS {
+        remove_pointer(&mut manifest, pointer);
+    }
+    if manifest
+        .pointer("/metadata/annotations")
+        .and_then(Value::as_object)
+        .is_some_and(|a| a.is_empty())
+    {
+        remove_pointer(&mut manifest, "/metadata/annotations");
+    }
+    manifest
+}
+
+/// Whether `path` is one of the `ignored` pointers or lies below one.
+fn is_ignored(path: &str, ignored: &[String]) -> bool {
+    ignored
+        .iter()
+        .any(|p| path == p || path.strip_prefix(p.as_str()).is_some_and(|rest| rest.starts_with('/')))
+}
+
+/// Field-level differences between two JSON values. Objects are compared by
+/// key and arrays by index; anything else is replaced as a whole.
+pub fn diff_values(path: &str, from: &Value, to: &Value, ignored: &[String], changes: &mut Vec<FieldChange>) {
+    if from == to || is_ignored(path, ignored) {
+        return;
+    }
+    let child = |path: &str, key: &str| format!("{}/{}", path, escape(key));
+    match (from, to) {
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: BTreeSet<&String> = a.keys().chain(b.keys()).collect();
+            for key in keys {
+                let path = child(path, key);
+                match (a.get(key), b.get(key)) {
+                    (Some(x), Some(y)) => diff_values(&path, x, y, ignored, changes),
+                    (x, y) if !is_ignored(&path, ignored) => changes.push(FieldChange {
+                        op: if x.is_some() { ChangeOp::Removed } else { ChangeOp::Added },
+                        path,
+                        from: x.cloned(),
+                        to: y.cloned(),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        (Value::Array(a), Value::Array(b)) => {
+            for i in 0..a.len().max(b.len()) {
+                let path = child(path, &i.to_string());
+                match (a.get(i), b.get(i)) {
+                    (Some(x), Some(y)) => diff_values(&path, x, y, ignored, changes),
+                    (x, y) if !is_ignored(&path, ignored) => changes.push(FieldChange {
+                        op: if x.is_some() { ChangeOp::Removed } else { ChangeOp::Added },
+                        path,
+                        from: x.cloned(),
+                        to: y.cloned(),
+                    }),
+                    _ => {}
+                }
+            }
+        }
+        _ => changes.push(FieldChange {
+            path: path.to_string(),
+            op: ChangeOp::Changed,
+            from: Some(from.clone()),
+            to: Some(to.clone()),
+        }),
+    }
+}
+
+fn object_ref(entry: &SnapshotEntry) -> ObjectRef {
+    ObjectRef {
+        kind: entry.kind.clone(),
+        namespace: Some(entry.namespace.clone()).filter(|n| !n.is_empty()),
+        name: entry.name.clone(),
+    }
+}
+
+/// Compares two snapshots, optionally limited to some kinds and a namespace.
+/// Objects whose only changes are under `ignored` pointers are left out.
+pub async fn compare(
+    repo: &SnapshotRepository,
+    from: ClusterSnapshot,
+    to: ClusterSnapshot,
+    kinds: &[InventoryKind],
+    namespace: Option<&str>,
+    ignored: &[String],
+) -> Result<SnapshotDiff> {
+    let index = |entries: Vec<SnapshotEntry>| -> BTreeMap<ObjectRef, String> {
+        entries.into_iter().map(|e| (object_ref(&e), e.hash)).collect()
+    };
+    let before = index(repo.entries(from.id, kinds, namespace).await?);
+    let after = index(repo.entries(to.id, kinds, namespace).await?);
+
+    let added: Vec<ObjectRef> = after.keys().filter(|o| !before.contains_key(o)).cloned().collect();
+    let removed: Vec<ObjectRef> = before.keys().filter(|o| !after.contains_key(o)).cloned().collect();
+    let modified: Vec<(&ObjectRef, &String, &String)> = before
+        .iter()
+        .filter_map(|(object, old)| after.get(object).filter(|new| *new != old).map(|new| (object, old, new)))
+        .collect();
+
+    let hashes: Vec<String> = modified
+        .iter()
+        .flat_map(|(_, old, new)| [(*old).clone(), (*new).clone()])
+        .collect();
+    let manifests = repo.manifests(&hashes).await?;
+    let mut changed = Vec::new();
+    for (object, old, new) in modified {
+        let (Some(old), Some(new)) = (manifests.get(old), manifests.get(new)) else {
+            continue;
+        };
+        let mut changes = Vec::new();
+        diff_values("", old, new, ignored, &mut changes);
+        if !changes.is_empty() {
+            changed.push(ChangedObject {
+                object: object.clone(),
+                changes,
+            });
+        }
+    }
+
+    Ok(SnapshotDiff {
+        summary: DiffSummary {
+            added: added.len(),
+            removed: removed.len(),
+            changed: changed.len(),
+        },
+        from,
+        to,
+        added,
+        removed,
+        changed,
+    })
+}
+
+/// Periodically snapshots clusters whose inventory was collected since their
+/// last snapshot, and deletes snapshots past retention.
+#[derive(Clone)]
+pub struct Snapshotter {
+    snapshots: SnapshotRepository,
+    config: SnapshotConfig,
+}
+
+impl Snapshotter {
+    pub fn new(snapshots: SnapshotRepository, config: SnapshotConfig) -> Self {
+        Self { snapshots, config }
+    }
+
+    pub async fn run_once(&self) -> Result<()> {
+        for cluster_id in self.snapshots.due().await? {
+            match self.snapshots.create(cluster_id, SnapshotTrigger::Scheduled, None).await {
+                Ok(snapshot) => info!(
+                    "Snapshot {} of cluster {} taken: {} objects",
+                    snapshot.id, cluster_id, snapshot.object_count
+                ),
+                Err(e) => warn!("Snapshot of cluster {} failed: {:#}", cluster_id, e),
+            }
+        }
+        let cutoff = (self.config.retention_days > 0)
+            .then(|| Utc::now() - chrono::Duration::days(self.config.retention_days as i64));
+        let purged = self.snapshots.purge(cutoff).await?;
+        if purged > 0 {
+            info!("Purged {} snapshots past retention", purged);
+        }
+        Ok(())
+    }
+
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(self.config.interval_secs));
+            interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.run_once().await {
+                    warn!("Snapshot round failed: {:#}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diff(from: &Value, to: &Value, ignored: &[&str]) -> Value {
+        let ignored: Vec<String> = ignored.iter().map(|p| p.to_string()).collect();
+        let mut changes = Vec::new();
+        diff_values("", from, to, &ignored, &mut changes);
+        serde_json::to_value(changes).unwrap()
+    }
+
+    #[test]
+    fn manifest_drops_noisy_fields() {
+        let item = json!({
+            "metadata": {
+                "name": "web",
+                "resourceVersion": "42",
+                "generation": 3,
+                "managedFields": [{}],
+                "annotations": { "kubectl.kubernetes.io/last-applied-configuration": "{}" }
+            },
+            "spec": { "replicas": 2 },
+            "status": { "readyReplicas": 2 }
+        });
+        assert_eq!(
+            manifest(&item),
+            json!({ "metadata": { "name": "web" }, "spec": { "replicas": 2 } })
+        );
+
+        let item = json!({ "metadata": { "name": "web", "annotations": { "team": "shop" } } });
+        assert_eq!(manifest(&item), item);
+    }
+
+    #[test]
+    fn is_ignored_matches_pointer_and_children_only() {
+        let ignored = vec!["/spec/replicas".to_string(), "/metadata/labels".to_string()];
+        assert!(is_ignored("/spec/replicas", &ignored));
+        assert!(is_ignored("/metadata/labels/app", &ignored));
+        assert!(!is_ignored("/spec/replicasets", &ignored));
+        assert!(!is_ignored("/spec", &ignored));
+    }
+
+    #[test]
+    fn diff_values_reports_field_changes() {
+        let from = json!({
+            "metadata": { "labels": { "app": "web", "a/b": "x" } },
+            "spec": { "replicas": 2, "ports": [80, 443], "paused": false }
+        });
+        let to = json!({
+            "metadata": { "labels": { "app": "web", "tier": "front" } },
+            "spec": { "replicas": 3, "ports": [80], "paused": false }
+        });
+        assert_eq!(
+            diff(&from, &to, &[]),
+            json!([
+                { "path": "/metadata/labels/a~1b", "op": "removed", "from": "x" },
+                { "path": "/metadata/labels/tier", "op": "added", "to": "front" },
+                { "path": "/spec/ports/1", "op": "removed", "from": 443 },
+                { "path": "/spec/replicas", "op": "changed", "from": 2, "to": 3 }
+            ])
+        );
+        assert_eq!(
+            diff(&from, &to, &["/metadata/labels", "/spec/ports/1"]),
+            json!([{ "path": "/spec/replicas", "op": "changed", "from": 2, "to": 3 }])
+        );
+        assert_eq!(diff(&from, &from, &[]), json!([]));
+    }
+
+    #[test]
+    fn diff_values_replaces_values_of_different_types() {
+        assert_eq!(
+            diff(&json!({ "x": [1] }), &json!({ "x": { "a": 1 } }), &[]),
+            json!([{ "path": "/x", "op": "changed", "from": [1], "to": { "a": 1 } }])
+        );
+    }
+}
@@ -0,0 +1,357 @@
+use anyhow::Result;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::inventory_collector::{array_at, i64_at, str_at, string_map};
+use crate::kube_client::{collection_path, KubeApiError, KubeClient};
+use crate::label_selector::LabelSelector;
+use crate::models::{EdgeType, TopologyEdge, TopologyGraph, TopologyNode, TopologyWarning};
+
+const LIST_PAGE_SIZE: usize = 500;
+/// Label linking an EndpointSlice to its Service
+const SERVICE_NAME_LABEL: &str = "kubernetes.io/service-name";
+
+/// A kind read from the cluster to build the graph. ConfigMaps, Secrets,
+/// PersistentVolumeClaims and ServiceAccounts are not read: they appear as
+/// references from pods, so no secret is ever fetched.
+#[derive(Debug, Clone, Copy)]
+pub struct ListedKind {
+    pub kind: &'static str,
+    pub api_group: &'static str,
+    pub resource: &'static str,
+}
+
+pub const LISTED_KINDS: [ListedKind; 11] = [
+    ListedKind { kind: "Ingress", api_group: "networking.k8s.io", resource: "ingresses" },
+    ListedKind { kind: "Service", api_group: "", resource: "services" },
+    ListedKind { kind: "EndpointSlice", api_group: "discovery.k8s.io", resource: "endpointslices" },
+    ListedKind { kind: "Pod", api_group: "", resource: "pods" },
+    ListedKind { kind: "ReplicaSet", api_group: "apps", resource: "replicasets" },
+    ListedKind { kind: "Deployment", api_group: "apps", resource: "deployments" },
+    ListedKind { kind: "StatefulSet", api_group: "apps", resource: "statefulsets" },
+    ListedKind { kind: "DaemonSet", api_group: "apps", resource: "daemonsets" },
+    ListedKind { kind: "Job", api_group: "batch", resource: "jobs" },
+    ListedKind { kind: "CronJob", api_group: "batch", resource: "cronjobs" },
+    ListedKind { kind: "NetworkPolicy", api_group: "networking.k8s.io", resource: "networkpolicies" },
+];
+
+pub fn node_id(kind: &str, namespace: &str, name: &str) -> String {
+    format!("{}/{}/{}", kind, namespace, name)
+}
+
+/// Ready/desired for workloads, the phase for pods.
+fn status(kind: &str, item: &Value) -> Option<String> {
+    let replicas = |ready: &str, desired: &str| {
+        Some(format!(
+            "{}/{}",
+            i64_at(item, ready).unwrap_or(0),
+            i64_at(item, desired).unwrap_or(1)
+        ))
+    };
+    match kind {
+        "Pod" => str_at(item, "/status/phase"),
+        "Deployment" | "StatefulSet" | "ReplicaSet" => replicas("/status/readyReplicas", "/spec/replicas"),
+        "DaemonSet" => replicas("/status/numberReady", "/status/desiredNumberScheduled"),
+        _ => None,
+    }
+}
+
+#[derive(Default)]
+struct GraphBuilder {
+    nodes: BTreeMap<String, TopologyNode>,
+    edges: BTreeMap<String, TopologyEdge>,
+}
+
+impl GraphBuilder {
+    fn object(&mut self, kind: &str, item: &Value) -> String {
+        let namespace = str_at(item, "/metadata/namespace").unwrap_or_default();
+        let name = str_at(item, "/metadata/name").unwrap_or_default();
+        let id = node_id(kind, &namespace, &name);
+        self.nodes.insert(
+            id.clone(),
+            TopologyNode {
+                id: id.clone(),
+                kind: kind.to_string(),
+                namespace,
+                name,
+                uid: str_at(item, "/metadata/uid"),
+                labels: string_map(item, "/metadata/labels"),
+                status: status(kind, item),
+            },
+        );
+        id
+    }
+
+    /// A node for a referenced object; a node read from the cluster wins.
+    fn reference(&mut self, kind: &str, namespace: &str, name: &str) -> String {
+        let id = node_id(kind, namespace, name);
+        self.nodes.entry(id.clone()).or_insert_with(|| TopologyNode {
+            id: id.clone(),
+            kind: kind.to_string(),
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+            uid: None,
+            labels: BTreeMap::new(),
+            status: None,
+        });
+        id
+    }
+
+    fn edge(&mut self, source: &str, edge_type: EdgeType, target: &str) {
+        let id = format!("{}|{}|{}", source, edge_type.as_str(), target);
+        self.edges.entry(id.clone()).or_insert_with(|| TopologyEdge {
+            id,
+            source: source.to_string(),
+            target: target.to_string(),
+            edge_type,
+        });
+    }
+
+    fn owners(&mut self, id: &str, namespace: &str, item: &Value) {
+        for owner in array_at(item, "/metadata/ownerReferences") {
+            if let (Some(kind), Some(name)) = (str_at(owner, "/kind"), str_at(owner, "/name")) {
+                let owner = self.reference(&kind, namespace, &name);
+                self.edge(id, EdgeType::OwnedBy, &owner);
+            }
+        }
+    }
+
+    fn ingress(&mut self, id: &str, namespace: &str, item: &Value) {
+        let mut backends: Vec<String> = str_at(item, "/spec/defaultBackend/service/name").into_iter().collect();
+        for rule in array_at(item, "/spec/rules") {
+            for path in array_at(rule, "/http/paths") {
+                backends.extend(str_at(path, "/backend/service/name"));
+            }
+        }
+        for name in backends {
+            let service = self.reference("Service", namespace, &name);
+            self.edge(id, EdgeType::Routes, &service);
+        }
+    }
+
+    fn endpoint_slice(&mut self, id: &str, namespace: &str, item: &Value) {
+        if let Some(name) = str_at(item, &format!("/metadata/labels/{}", SERVICE_NAME_LABEL.replace('/', "~1"))) {
+            let service = self.reference("Service", namespace, &name);
+            self.edge(&service, EdgeType::Endpoints, id);
+        }
+        for endpoint in array_at(item, "/endpoints") {
+            if str_at(endpoint, "/targetRef/kind").as_deref() != Some("Pod") {
+                continue;
+            }
+            if let Some(name) = str_at(endpoint, "/targetRef/name") {
+                let pod_namespace = str_at(endpoint, "/targetRef/namespace").unwrap_or_else(|| namespace.to_string());
+                let pod = self.reference("Pod", &pod_namespace, &name);
+                self.edge(id, EdgeType::Targets, &pod);
+            }
+        }
+    }
+
+    fn pod(&mut self, id: &str, namespace: &str, item: &Value) {
+        let link = |builder: &mut Self, kind: &str, name: Option<String>, edge_type: EdgeType| {
+            if let Some(name) = name.filter(|n| !n.is_empty()) {
+                let target = builder.reference(kind, namespace, &name);
+                builder.edge(id, edge_type, &target);
+            }
+        };
+        for volume in array_at(item, "/spec/volumes") {
+            link(self, "ConfigMap", str_at(volume, "/configMap/name"), EdgeType::Mounts);
+            link(self, "Secret", str_at(volume, "/secret/secretName"), EdgeType::Mounts);
+            link(
+                self,
+                "PersistentVolumeClaim",
+                str_at(volume, "/persistentVolumeClaim/claimName"),
+                EdgeType::Mounts,
+            );
+            for source in array_at(volume, "/projected/sources") {
+                link(self, "ConfigMap", str_at(source, "/configMap/name"), EdgeType::Mounts);
+                link(self, "Secret", str_at(source, "/secret/name"), EdgeType::Mounts);
+            }
+        }
+        let containers = ["/spec/initContainers", "/spec/containers", "/spec/ephemeralContainers"]
+            .iter()
+            .flat_map(|p| array_at(item, p));
+        for container in containers {
+            for env in array_at(container, "/env") {
+                link(self, "ConfigMap", str_at(env, "/valueFrom/configMapKeyRef/name"), EdgeType::References);
+                link(self, "Secret", str_at(env, "/valueFrom/secretKeyRef/name"), EdgeType::References);
+            }
+            for source in array_at(container, "/envFrom") {
+                link(self, "ConfigMap", str_at(source, "/configMapRef/name"), EdgeType::References);
+                link(self, "Secret", str_at(source, "/secretRef/name"), EdgeType::References);
+            }
+        }
+        for secret in array_at(item, "/spec/imagePullSecrets") {
+            link(self, "Secret", str_at(secret, "/name"), EdgeType::References);
+        }
+        let account = str_at(item, "/spec/serviceAccountName").unwrap_or_else(|| "default".to_string());
+        link(self, "ServiceAccount", Some(account), EdgeType::RunsAs);
+    }
+
+    /// Links the policy to the pods its `podSelector` selects; a selector
+    /// that cannot be parsed is returned as an error and links nothing.
+    fn network_policy(
+        &mut self,
+        id: &str,
+        namespace: &str,
+        item: &Value,
+        pods: &[(String, String, BTreeMap<String, String>)],
+    ) -> Result<()> {
+        let selector = LabelSelector::from_spec(item.pointer("/spec/podSelector").unwrap_or(&Value::Null))?;
+        for (pod_id, pod_namespace, labels) in pods {
+            if pod_namespace == namespace && selector.matches(labels) {
+                self.edge(id, EdgeType::Selects, pod_id);
+            }
+        }
+        Ok(())
+    }
+
+    fn build(self, warnings: Vec<TopologyWarning>) -> TopologyGraph {
+        TopologyGraph {
+            nodes: self.nodes.into_values().collect(),
+            edges: self.edges.into_values().collect(),
+            warnings,
+        }
+    }
+}
+
+/// Builds the graph from listed objects, keyed by kind. Network policies
+/// whose pod selector is invalid are reported as warnings.
+pub fn build(objects: &[(ListedKind, Vec<Value>)], mut warnings: Vec<TopologyWarning>) -> TopologyGraph {
+    let mut builder = GraphBuilder::default();
+    let mut pods = Vec::new();
+    let mut policies = Vec::new();
+    for (kind, items) in objects {
+        for item in items {
+            let id = builder.object(kind.kind, item);
+            let namespace = str_at(item, "/metadata/namespace").unwrap_or_default();
+            builder.owners(&id, &namespace, item);
+            match kind.kind {
+                "Ingress" => builder.ingress(&id, &namespace, item),
+                "EndpointSlice" => builder.endpoint_slice(&id, &namespace, item),
+                "Pod" => {
+                    builder.pod(&id, &namespace, item);
+                    pods.push((id, namespace, string_map(item, "/metadata/labels")));
+                }
+                "NetworkPolicy" => policies.push((id, namespace, item)),
+                _ => {}
+            }
+        }
+    }
+    for (id, namespace, item) in policies {
+        if let Err(e) = builder.network_policy(&id, &namespace, item, &pods) {
+            warnings.push(TopologyWarning {
+                kind: "NetworkPolicy".to_string(),
+                message: format!(
+                    "{}/{}: invalid podSelector: {}",
+                    namespace,
+                    str_at(item, "/metadata/name").unwrap_or_default(),
+                    e
+                ),
+            });
+        }
+    }
+    builder.build(warnings)
+}
+
+/// Reads every listed kind, in one namespace or the whole cluster. Kinds the
+/// cluster denies or does not serve are skipped with a warning.
+pub async fn collect(client: &KubeClient, namespace: Option<&str>) -> Result<TopologyGraph> {
+    let lists = futures::future::join_all(LISTED_KINDS.iter().map(|kind| async move {
+        let result = match collection_path(kind.api_group, kind.resource, namespace) {
+            Ok(path) => client.list_all(&path, LIST_PAGE_SIZE).await,
+            Err(e) => Err(e),
+        };
+        (*kind, result)
+    }))
+    .await;
+
+    let mut objects = Vec::new();
+    let mut warnings = Vec::new();
+    for (kind, result) in lists {
+        match result {
+            Ok(items) => objects.push((kind, items)),
+            Err(e) => match e.downcast_ref::<KubeApiError>() {
+                Some(api) if matches!(api.status, StatusCode::FORBIDDEN | StatusCode::NOT_FOUND) => {
+                    warnings.push(TopologyWarning {
+                        kind: kind.kind.to_string(),
+                        message: api.message.clone(),
+                    });
+                }
+                _ => return Err(e),
+            },
+        }
+    }
+    Ok(build(&objects, warnings))
+}
+
+/// The part of the graph within `depth` edges of `start`, following edges in
+/// both directions. `None` if `start` is not in the graph.
+pub fn neighbourhood(graph: TopologyGraph, start: &str, depth: usize) -> Option<TopologyGraph> {
+    if !graph.nodes.iter().any(|n| n.id == start) {
+        return None;
+    }
+    let mut adjacent: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for edge in &graph.edges {
+        adjacent.entry(&edge.source).or_default().push(&edge.target);
+        adjacent.entry(&edge.target).or_default().push(&edge.source);
+    }
+    let mut reached: BTreeSet<String> = BTreeSet::from([start.to_string()]);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((id, distance)) = queue.pop_front() {
+        if distance == depth {
+            continue;
+        }
+        for next in adjacent.get(id).into_iter().flatten() {
+            if reached.insert(next.to_string()) {
+                queue.push_back((next, distance + 1));
+            }
+        }
+    }
+    Some(TopologyGraph {
+        nodes: graph.nodes.into_iter().filter(|n| reached.contains(&n.id)).collect(),
+        edges: graph
+            .edges
+            .into_iter()
+            .filter(|e| reached.contains(&e.source) && reached.contains(&e.target))
+            .collect(),
+        warnings: graph.warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn listed(kind: &str) -> ListedKind {
+        *LISTED_KINDS.iter().find(|k| k.kind == kind).unwrap()
+    }
+
+    #[test]
+    fn network_policies_select_pods_and_report_invalid_selectors() {
+        let pod = json!({ "metadata": { "namespace": "shop", "name": "web-0", "labels": { "app": "web" } } });
+        let valid = json!({
+            "metadata": { "namespace": "shop", "name": "web" },
+            "spec": { "podSelector": { "matchLabels": { "app": "web" } } }
+        });
+        let invalid = json!({
+            "metadata": { "namespace": "shop", "name": "broken" },
+            "spec": { "podSelector": { "matchExpressions": [{ "key": "app", "operator": "Near" }] } }
+        });
+        let graph = build(
+            &[(listed("Pod"), vec![pod]), (listed("NetworkPolicy"), vec![valid, invalid])],
+            Vec::new(),
+        );
+        let selects: Vec<&str> = graph
+            .edges
+            .iter()
+            .filter(|e| e.edge_type == EdgeType::Selects)
+            .map(|e| e.id.as_str())
+            .collect();
+        assert_eq!(selects, ["NetworkPolicy/shop/web|selects|Pod/shop/web-0"]);
+        assert_eq!(graph.warnings.len(), 1);
+        assert_eq!(graph.warnings[0].kind, "NetworkPolicy");
+        assert!(graph.warnings[0].message.starts_with("shop/broken: invalid podSelector"));
+    }
+}
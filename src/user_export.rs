@@ -0,0 +1,237 @@
+use anyhow::{anyhow, Result};
+use axum::body::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+use crate::auth::{AuthService, KeycloakError};
+use crate::models::UserExportRecord;
+
+/// Users fetched from Keycloak per request; each page becomes one response chunk.
+const PAGE_SIZE: usize = 100;
+/// Per-user lookups (roles, groups, last login) running at once within a page.
+const ENRICH_CONCURRENCY: usize = 8;
+
+const CSV_HEADER: [&str; 10] = [
+    "id",
+    "username",
+    "email",
+    "first_name",
+    "last_name",
+    "enabled",
+    "roles",
+    "groups",
+    "created_at",
+    "last_login",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+}
+
+impl ExportFormat {
+    pub fn parse(value: Option<&str>) -> Result<Self> {
+        match value.map(|v| v.to_ascii_lowercase()).as_deref() {
+            None | Some("json") => Ok(Self::Json),
+            Some("csv") => Ok(Self::Csv),
+            Some(other) => Err(anyhow!("Unsupported export format '{}'", other)),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub fn file_extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Json => "json",
+        }
+    }
+}
+
+enum Phase {
+    Start,
+    Page { first: usize },
+    Finish,
+    Done,
+}
+
+struct ExportState {
+    auth_service: AuthService,
+    format: ExportFormat,
+    phase: Phase,
+    wrote_any: bool,
+    /// Cleared on the first 403 so the rest of the export skips the lookup
+    login_events_readable: AtomicBool,
+}
+
+/// Streams all realm users page by page so memory use does not grow with the realm.
+pub fn export_users(
+    auth_service: AuthService,
+    format: ExportFormat,
+) -> impl Stream<Item = std::io::Result<Bytes>> {
+    let state = ExportState {
+        auth_service,
+        format,
+        phase: Phase::Start,
+        wrote_any: false,
+        login_events_readable: AtomicBool::new(true),
+    };
+    stream::unfold(state, |mut state| async move {
+        match state.phase {
+            Phase::Start => {
+                state.phase = Phase::Page { first: 0 };
+                let head = match state.format {
+                    ExportFormat::Csv => csv_line(&CSV_HEADER),
+                    ExportFormat::Json => b"[".to_vec(),
+                };
+                Some((Ok(Bytes::from(head)), state))
+            }
+            Phase::Page { first } => match export_page(&mut state, first).await {
+                Ok(chunk) => Some((Ok(Bytes::from(chunk)), state)),
+                Err(e) => {
+                    warn!("User export aborted at offset {}: {}", first, e);
+                    state.phase = Phase::Done;
+                    Some((Err(std::io::Error::other(e.to_string())), state))
+                }
+            },
+            Phase::Finish => {
+                state.phase = Phase::Done;
+                let tail = match state.format {
+                    ExportFormat::Csv => Vec::new(),
+                    ExportFormat::Json => b"]".to_vec(),
+                };
+                Some((Ok(Bytes::from(tail)), state))
+            }
+            Phase::Done => None,
+        }
+    })
+}
+
+async fn export_page(state: &mut ExportState, first: usize) -> Result<Vec<u8>> {
+    // A fresh token per page keeps long exports from outliving the token lifetime
+    let admin_token = state.auth_service.get_admin_access_token().await?;
+    let users = state
+        .auth_service
+        .list_users_page(&admin_token, first, PAGE_SIZE)
+        .await?;
+
+    state.phase = if users.len() < PAGE_SIZE {
+        Phase::Finish
+    } else {
+        Phase::Page {
+            first: first + users.len(),
+        }
+    };
+
+    let auth_service = &state.auth_service;
+    let admin_token = admin_token.as_str();
+    let login_events_readable = &state.login_events_readable;
+    let records: Vec<Result<UserExportRecord>> = stream::iter(users)
+        .map(|user| enrich_user(auth_service, admin_token, login_events_readable, user))
+        .buffered(ENRICH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut chunk = Vec::new();
+    for record in records {
+        let record = record?;
+        match state.format {
+            ExportFormat::Csv => chunk.extend(csv_record(&record)),
+            ExportFormat::Json => {
+                if state.wrote_any {
+                    chunk.push(b',');
+                }
+                chunk.extend(serde_json::to_vec(&record)?);
+            }
+        }
+        state.wrote_any = true;
+    }
+    Ok(chunk)
+}
+
+async fn enrich_user(
+    auth_service: &AuthService,
+    admin_token: &str,
+    login_events_readable: &AtomicBool,
+    user: serde_json::Value,
+) -> Result<UserExportRecord> {
+    let str_field = |name: &str| user.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let id = str_field("id").ok_or_else(|| anyhow!("User representation without id"))?;
+
+    let roles = auth_service
+        .get_user_realm_role_names(admin_token, &id)
+        .await?;
+    let groups = auth_service.get_user_group_paths(admin_token, &id).await?;
+    // Login events are optional in Keycloak, so a failure here is not fatal
+    let last_login = if login_events_readable.load(Ordering::Relaxed) {
+        match auth_service.get_user_last_login(admin_token, &id).await {
+            Ok(ts) => ts.and_then(millis_to_datetime),
+            Err(e) if matches!(e.downcast_ref(), Some(KeycloakError::Forbidden(_))) => {
+                if login_events_readable.swap(false, Ordering::Relaxed) {
+                    warn!("Cannot read login events, exporting without last_login: {}", e);
+                }
+                None
+            }
+            Err(e) => {
+                warn!("Cannot read last login for user {}: {}", id, e);
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    Ok(UserExportRecord {
+        username: str_field("username").unwrap_or_default(),
+        email: str_field("email"),
+        first_name: str_field("firstName"),
+        last_name: str_field("lastName"),
+        enabled: user.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false),
+        roles,
+        groups,
+        created_at: user
+            .get("createdTimestamp")
+            .and_then(|v| v.as_i64())
+            .and_then(millis_to_datetime),
+        last_login,
+        id,
+    })
+}
+
+fn millis_to_datetime(millis: i64) -> Option<DateTime<Utc>> {
+    Utc.timestamp_millis_opt(millis).single()
+}
+
+fn csv_line(fields: &[&str]) -> Vec<u8> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    // Writing into a Vec cannot fail
+    let _ = writer.write_record(fields);
+    writer.into_inner().unwrap_or_default()
+}
+
+fn csv_record(record: &UserExportRecord) -> Vec<u8> {
+    let roles = record.roles.join(";");
+    let groups = record.groups.join(";");
+    let created_at = record.created_at.map(|t| t.to_rfc3339()).unwrap_or_default();
+    let last_login = record.last_login.map(|t| t.to_rfc3339()).unwrap_or_default();
+    csv_line(&[
+        &record.id,
+        &record.username,
+        record.email.as_deref().unwrap_or(""),
+        record.first_name.as_deref().unwrap_or(""),
+        record.last_name.as_deref().unwrap_or(""),
+        if record.enabled { "true" } else { "false" },
+        &roles,
+        &groups,
+        &created_at,
+        &last_login,
+    ])
+}
@@ -0,0 +1,431 @@
+use anyhow::{anyhow, Result};
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+use crate::auth::AuthService;
+use crate::config::PasswordPolicy;
+use crate::models::{
+    CreateUserRequest, ImportRowResult, ImportRowStatus, ImportUserRecord, ImportUsersReport,
+};
+use crate::validation::{self, ValidationErrors};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Csv,
+    Json,
+}
+
+impl ImportFormat {
+    /// Picks the format from an explicit value, then the file name, then the
+    /// content type, then the data itself.
+    pub fn detect(
+        explicit: Option<&str>,
+        file_name: Option<&str>,
+        content_type: Option<&str>,
+        data: &[u8],
+    ) -> Result<Self> {
+        if let Some(format) = explicit {
+            return match format.to_ascii_lowercase().as_str() {
+                "csv" => Ok(Self::Csv),
+                "json" => Ok(Self::Json),
+                other => Err(anyhow!("Unsupported import format '{}'", other)),
+            };
+        }
+        if let Some(name) = file_name.map(|n| n.to_ascii_lowercase()) {
+            if name.ends_with(".csv") {
+                return Ok(Self::Csv);
+            }
+            if name.ends_with(".json") {
+                return Ok(Self::Json);
+            }
+        }
+        match content_type {
+            Some(ct) if ct.contains("csv") => return Ok(Self::Csv),
+            Some(ct) if ct.contains("json") => return Ok(Self::Json),
+            _ => {}
+        }
+        Self::sniff(data).ok_or_else(|| {
+            anyhow!("Cannot detect import format; use a .csv/.json file or pass format=csv|json")
+        })
+    }
+
+    /// JSON starts with an array or object; CSV with a header naming `username`.
+    fn sniff(data: &[u8]) -> Option<Self> {
+        let text = String::from_utf8_lossy(data);
+        let text = text.trim_start_matches('\u{feff}').trim_start();
+        if text.starts_with('[') || text.starts_with('{') {
+            return Some(Self::Json);
+        }
+        let header = text.lines().next()?;
+        header
+            .split(',')
+            .any(|column| column.trim().trim_matches('"') == "username")
+            .then_some(Self::Csv)
+    }
+}
+
+/// A parsed input row: either a record or the reason it could not be read.
+pub type ParsedRow = std::result::Result<ImportUserRecord, String>;
+
+#[derive(Debug, Deserialize)]
+struct CsvUserRow {
+    username: String,
+    email: String,
+    #[serde(default)]
+    first_name: Option<String>,
+    #[serde(default)]
+    last_name: Option<String>,
+    password: String,
+    #[serde(default)]
+    roles: Option<String>,
+    #[serde(default)]
+    groups: Option<String>,
+}
+
+/// Splits a `;`-separated CSV cell into trimmed, non-empty values.
+fn split_list(value: Option<String>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(';')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.filter(|v| !v.trim().is_empty())
+}
+
+pub fn parse_records(data: &[u8], format: ImportFormat) -> Result<Vec<ParsedRow>> {
+    match format {
+        ImportFormat::Csv => {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_reader(data);
+            Ok(reader
+                .deserialize::<CsvUserRow>()
+                .map(|row| {
+                    row.map(|r| ImportUserRecord {
+                        username: r.username,
+                        email: r.email,
+                        first_name: non_empty(r.first_name),
+                        last_name: non_empty(r.last_name),
+                        password: r.password,
+                        roles: split_list(r.roles),
+                        groups: split_list(r.groups),
+                    })
+                    .map_err(|e| format!("Malformed CSV row: {}", e))
+                })
+                .collect())
+        }
+        ImportFormat::Json => {
+            let value: serde_json::Value = serde_json::from_slice(data)
+                .map_err(|e| anyhow!("Malformed JSON: {}", e))?;
+            // Accept both a bare array and `{ "users": [...] }`
+            let items = match value {
+                serde_json::Value::Array(items) => items,
+                serde_json::Value::Object(mut obj) => match obj.remove("users") {
+                    Some(serde_json::Value::Array(items)) => items,
+                    _ => return Err(anyhow!("JSON import must be an array or {{\"users\": [...]}}")),
+                },
+                _ => return Err(anyhow!("JSON import must be an array or {{\"users\": [...]}}")),
+            };
+            Ok(items
+                .into_iter()
+                .map(|item| {
+                    serde_json::from_value::<ImportUserRecord>(item)
+                        .map_err(|e| format!("Malformed JSON row: {}", e))
+                })
+                .collect())
+        }
+    }
+}
+
+/// Usernames and emails already seen in the file, compared case-insensitively.
+#[derive(Default)]
+struct SeenInFile {
+    usernames: HashSet<String>,
+    emails: HashSet<String>,
+}
+
+impl SeenInFile {
+    fn duplicates(&mut self, record: &ImportUserRecord) -> Vec<String> {
+        let mut reasons = Vec::new();
+        if !self.usernames.insert(record.username.to_lowercase()) {
+            reasons.push(format!("duplicate username '{}' in file", record.username));
+        }
+        if !self.emails.insert(record.email.to_lowercase()) {
+            reasons.push(format!("duplicate email '{}' in file", record.email));
+        }
+        reasons
+    }
+}
+
+fn validate_record(
+    record: &ImportUserRecord,
+    policy: &PasswordPolicy,
+    known_roles: &HashSet<String>,
+    known_groups: &HashMap<String, bool>,
+) -> Vec<String> {
+    let mut errors = ValidationErrors::new();
+    validation::validate_username(&mut errors, "username", &record.username);
+    validation::validate_email(&mut errors, "email", &record.email);
+    validation::validate_password(&mut errors, "password", &record.password, policy);
+    validation::validate_name(&mut errors, "first_name", record.first_name.as_deref());
+    validation::validate_name(&mut errors, "last_name", record.last_name.as_deref());
+    validation::validate_roles_exist(&mut errors, "roles", &record.roles, known_roles);
+    for group in &record.groups {
+        if !known_groups.get(group).copied().unwrap_or(false) {
+            errors.add("groups", format!("group '{}' does not exist", group));
+        }
+    }
+    errors.messages()
+}
+
+/// Validates every row, skips users that already exist and creates the rest
+/// through `create_keycloak_user`, at most `concurrency` at a time.
+pub async fn import_users(
+    auth_service: &AuthService,
+    rows: Vec<ParsedRow>,
+    dry_run: bool,
+    concurrency: usize,
+    policy: &PasswordPolicy,
+) -> Result<ImportUsersReport> {
+    let admin_token = auth_service.get_admin_access_token().await?;
+
+    let known_roles: HashSet<String> = auth_service
+        .list_realm_role_names(&admin_token)
+        .await?
+        .into_iter()
+        .collect();
+
+    let mut known_groups: HashMap<String, bool> = HashMap::new();
+    for group in rows.iter().flatten().flat_map(|r| r.groups.iter()) {
+        if !known_groups.contains_key(group) {
+            let exists = auth_service
+                .find_group_id(&admin_token, group)
+                .await?
+                .is_some();
+            known_groups.insert(group.clone(), exists);
+        }
+    }
+
+    // Rows are numbered from 1 as a human would count them in the file
+    let mut results: Vec<ImportRowResult> = Vec::with_capacity(rows.len());
+    let mut pending: Vec<(usize, ImportUserRecord)> = Vec::new();
+    let mut seen = SeenInFile::default();
+
+    for (idx, row) in rows.into_iter().enumerate() {
+        let row_no = idx + 1;
+        let record = match row {
+            Ok(record) => record,
+            Err(reason) => {
+                results.push(ImportRowResult {
+                    row: row_no,
+                    username: None,
+                    status: ImportRowStatus::Failed,
+                    reasons: vec![reason],
+                });
+                continue;
+            }
+        };
+
+        let mut reasons = validate_record(&record, policy, &known_roles, &known_groups);
+        reasons.extend(seen.duplicates(&record));
+        if !reasons.is_empty() {
+            results.push(ImportRowResult {
+                row: row_no,
+                username: Some(record.username),
+                status: ImportRowStatus::Failed,
+                reasons,
+            });
+            continue;
+        }
+        pending.push((row_no, record));
+    }
+
+    let processed: Vec<ImportRowResult> = stream::iter(pending)
+        .map(|(row_no, record)| {
+            let admin_token = admin_token.clone();
+            async move { import_one(auth_service, &admin_token, row_no, record, dry_run).await }
+        })
+        .buffer_unordered(concurrency.max(1))
+        .collect()
+        .await;
+    results.extend(processed);
+    results.sort_by_key(|r| r.row);
+
+    let count = |status: ImportRowStatus| results.iter().filter(|r| r.status == status).count();
+    let created = if dry_run {
+        count(ImportRowStatus::WouldCreate)
+    } else {
+        count(ImportRowStatus::Created)
+    };
+    let report = ImportUsersReport {
+        dry_run,
+        total: results.len(),
+        created,
+        skipped_existing: count(ImportRowStatus::SkippedExisting),
+        failed: count(ImportRowStatus::Failed),
+        rows: results,
+    };
+    info!(
+        "User import finished (dry_run={}): total={}, created={}, skipped={}, failed={}",
+        report.dry_run, report.total, report.created, report.skipped_existing, report.failed
+    );
+    Ok(report)
+}
+
+async fn import_one(
+    auth_service: &AuthService,
+    admin_token: &str,
+    row_no: usize,
+    record: ImportUserRecord,
+    dry_run: bool,
+) -> ImportRowResult {
+    let username = record.username.clone();
+    let result = |status, reasons| ImportRowResult {
+        row: row_no,
+        username: Some(username.clone()),
+        status,
+        reasons,
+    };
+
+    match auth_service
+        .find_user_id_by_username(admin_token, &record.username)
+        .await
+    {
+        Ok(Some(_)) => {
+            return result(
+                ImportRowStatus::SkippedExisting,
+                vec!["user already exists".to_string()],
+            )
+        }
+        Ok(None) => {}
+        Err(e) => return result(ImportRowStatus::Failed, vec![e.to_string()]),
+    }
+
+    if dry_run {
+        return result(ImportRowStatus::WouldCreate, vec![]);
+    }
+
+    let req = CreateUserRequest {
+        username: record.username,
+        email: record.email,
+        first_name: record.first_name,
+        last_name: record.last_name,
+        password: record.password,
+        roles: record.roles,
+        groups: record.groups,
+    };
+    match auth_service.create_keycloak_user(req).await {
+        Ok(_) => result(ImportRowStatus::Created, vec![]),
+        Err(e) => {
+            warn!("Import row {} ('{}') failed: {}", row_no, username, e);
+            result(ImportRowStatus::Failed, vec![e.to_string()])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(username: &str, email: &str) -> ImportUserRecord {
+        ImportUserRecord {
+            username: username.to_string(),
+            email: email.to_string(),
+            first_name: None,
+            last_name: None,
+            password: "Secret123!".to_string(),
+            roles: Vec::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn detect_prefers_explicit_then_name_then_content_type() {
+        let detect = |explicit, name, content_type| ImportFormat::detect(explicit, name, content_type, b"");
+        assert_eq!(detect(Some("JSON"), Some("users.csv"), None).unwrap(), ImportFormat::Json);
+        assert!(detect(Some("xml"), None, None).is_err());
+        assert_eq!(detect(None, Some("Users.CSV"), Some("application/json")).unwrap(), ImportFormat::Csv);
+        assert_eq!(detect(None, Some("users.json"), None).unwrap(), ImportFormat::Json);
+        assert_eq!(detect(None, Some("users.txt"), Some("text/csv")).unwrap(), ImportFormat::Csv);
+        assert_eq!(detect(None, None, Some("application/json; charset=utf-8")).unwrap(), ImportFormat::Json);
+        assert!(detect(None, Some("users.txt"), Some("application/octet-stream")).is_err());
+    }
+
+    #[test]
+    fn detect_sniffs_content_last() {
+        let sniff = |data: &[u8]| ImportFormat::detect(None, Some("upload"), Some("application/octet-stream"), data);
+        assert_eq!(sniff(b"  \n[{\"username\": \"alice\"}]").unwrap(), ImportFormat::Json);
+        assert_eq!(sniff(b"\xef\xbb\xbf{\"users\": []}").unwrap(), ImportFormat::Json);
+        assert_eq!(sniff(b"\"username\",email,password\nalice,a@x.io,pw\n").unwrap(), ImportFormat::Csv);
+        assert!(sniff(b"name,mail\nalice,a@x.io\n").is_err());
+        assert!(sniff(b"").is_err());
+        // The file name wins over the content
+        assert_eq!(
+            ImportFormat::detect(None, Some("users.csv"), None, b"[]").unwrap(),
+            ImportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn csv_rows_split_roles_and_groups() {
+        let csv = b"username,email,first_name,last_name,password,roles,groups\n\
+alice, alice@example.com ,Alice,,Secret123!, admin ; viewer ;,/Platform Team;devs\n\
+bob,bob@example.com,,,Secret123!,,\n\
+carol,carol@example.com\n";
+        let rows = parse_records(csv, ImportFormat::Csv).unwrap();
+        assert_eq!(rows.len(), 3);
+
+        let alice = rows[0].as_ref().unwrap();
+        assert_eq!(alice.email, "alice@example.com");
+        assert_eq!(alice.first_name.as_deref(), Some("Alice"));
+        assert_eq!(alice.last_name, None);
+        assert_eq!(alice.roles, ["admin", "viewer"]);
+        assert_eq!(alice.groups, ["/Platform Team", "devs"]);
+
+        let bob = rows[1].as_ref().unwrap();
+        assert!(bob.roles.is_empty() && bob.groups.is_empty());
+
+        assert!(rows[2].as_ref().unwrap_err().starts_with("Malformed CSV row"));
+    }
+
+    #[test]
+    fn json_accepts_array_or_users_object() {
+        let user = r#"{"username": "alice", "email": "alice@example.com", "password": "Secret123!", "roles": ["viewer"]}"#;
+        let array = parse_records(format!("[{}]", user).as_bytes(), ImportFormat::Json).unwrap();
+        let object = parse_records(format!(r#"{{"users": [{}]}}"#, user).as_bytes(), ImportFormat::Json).unwrap();
+        for rows in [array, object] {
+            let alice = rows[0].as_ref().unwrap();
+            assert_eq!(alice.username, "alice");
+            assert_eq!(alice.roles, ["viewer"]);
+            assert!(alice.groups.is_empty());
+        }
+
+        let rows = parse_records(br#"[{"username": "bob"}]"#, ImportFormat::Json).unwrap();
+        assert!(rows[0].as_ref().unwrap_err().starts_with("Malformed JSON row"));
+        assert!(parse_records(br#"{"people": []}"#, ImportFormat::Json).is_err());
+        assert!(parse_records(b"\"alice\"", ImportFormat::Json).is_err());
+        assert!(parse_records(b"[", ImportFormat::Json).is_err());
+    }
+
+    #[test]
+    fn duplicates_in_file_ignore_case() {
+        let mut seen = SeenInFile::default();
+        assert!(seen.duplicates(&record("alice", "alice@example.com")).is_empty());
+        assert_eq!(
+            seen.duplicates(&record("Alice", "other@example.com")),
+            ["duplicate username 'Alice' in file"]
+        );
+        assert_eq!(
+            seen.duplicates(&record("bob", "ALICE@example.com")),
+            ["duplicate email 'ALICE@example.com' in file"]
+        );
+        assert_eq!(seen.duplicates(&record("alice", "alice@example.com")).len(), 2);
+    }
+}
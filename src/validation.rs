@@ -0,0 +1,542 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::config::PasswordPolicy;
+use crate::models::{
+    ClusterAuth, ClusterCredential, CreateClusterRequest, CreateUserRequest, ImpersonationSettings,
+    UpdateClusterRequest, UpdateUserRequest,
+};
+
+const USERNAME_MIN_LENGTH: usize = 3;
+const USERNAME_MAX_LENGTH: usize = 64;
+const NAME_MAX_LENGTH: usize = 255;
+const EMAIL_MAX_LENGTH: usize = 254;
+const CLUSTER_NAME_MAX_LENGTH: usize = 63;
+const DESCRIPTION_MAX_LENGTH: usize = 1024;
+
+/// Per-field error messages, serialized as `{"field": ["message", ...]}`.
+#[derive(Debug, Default, Clone, Serialize)]
+#[serde(transparent)]
+pub struct ValidationErrors {
+    fields: BTreeMap<String, Vec<String>>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn single(field: &str, message: impl Into<String>) -> Self {
+        let mut errors = Self::new();
+        errors.add(field, message);
+        errors
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.fields
+            .entry(field.to_string())
+            .or_default()
+            .push(message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Flattens to `field: message` strings for reports that are not keyed by field.
+    pub fn messages(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .flat_map(|(field, messages)| messages.iter().map(move |m| format!("{}: {}", field, m)))
+            .collect()
+    }
+
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+
+    /// Builds the common error body: `{"error", "message", "fields"}`.
+    pub fn into_error(self, status: StatusCode, message: &str) -> (StatusCode, Json<serde_json::Value>) {
+        let error = status.canonical_reason().unwrap_or("Error");
+        (
+            status,
+            Json(json!({
+                "error": error,
+                "message": message,
+                "fields": self
+            })),
+        )
+    }
+}
+
+impl IntoResponse for ValidationErrors {
+    fn into_response(self) -> Response {
+        self.into_error(StatusCode::BAD_REQUEST, "Request validation failed")
+            .into_response()
+    }
+}
+
+pub fn validate_username(errors: &mut ValidationErrors, field: &str, username: &str) {
+    let len = username.chars().count();
+    if !(USERNAME_MIN_LENGTH..=USERNAME_MAX_LENGTH).contains(&len) {
+        errors.add(
+            field,
+            format!(
+                "must be between {} and {} characters",
+                USERNAME_MIN_LENGTH, USERNAME_MAX_LENGTH
+            ),
+        );
+    }
+    if !username
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'))
+    {
+        errors.add(field, "may only contain letters, digits, '.', '_' and '-'");
+    }
+    if !username.chars().next().is_some_and(|c| c.is_ascii_alphanumeric()) {
+        errors.add(field, "must start with a letter or digit");
+    }
+}
+
+/// Pragmatic syntax check: `local@domain.tld`, no whitespace, sane lengths.
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > EMAIL_MAX_LENGTH || email.chars().any(char::is_whitespace) {
+        return false;
+    }
+    let Some((local, domain)) = email.rsplit_once('@') else {
+        return false;
+    };
+    if local.is_empty() || local.len() > 64 || local.contains('@') {
+        return false;
+    }
+    let labels: Vec<&str> = domain.split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+pub fn validate_email(errors: &mut ValidationErrors, field: &str, email: &str) {
+    if email.trim().is_empty() {
+        errors.add(field, "is required");
+    } else if !is_valid_email(email) {
+        errors.add(field, "is not a valid email address");
+    }
+}
+
+pub fn validate_password(
+    errors: &mut ValidationErrors,
+    field: &str,
+    password: &str,
+    policy: &PasswordPolicy,
+) {
+    if password.chars().count() < policy.min_length {
+        errors.add(
+            field,
+            format!("must be at least {} characters", policy.min_length),
+        );
+    }
+    if policy.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+        errors.add(field, "must contain an uppercase letter");
+    }
+    if policy.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+        errors.add(field, "must contain a lowercase letter");
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        errors.add(field, "must contain a digit");
+    }
+    if policy.require_special && password.chars().all(|c| c.is_alphanumeric()) {
+        errors.add(field, "must contain a special character");
+    }
+}
+
+pub fn validate_name(errors: &mut ValidationErrors, field: &str, name: Option<&str>) {
+    if let Some(name) = name {
+        if name.chars().count() > NAME_MAX_LENGTH {
+            errors.add(field, format!("must be at most {} characters", NAME_MAX_LENGTH));
+        }
+        if name.chars().any(char::is_control) {
+            errors.add(field, "must not contain control characters");
+        }
+    }
+}
+
+pub fn validate_roles_exist(
+    errors: &mut ValidationErrors,
+    field: &str,
+    roles: &[String],
+    known_roles: &HashSet<String>,
+) {
+    for role in roles {
+        if !known_roles.contains(role) {
+            errors.add(field, format!("role '{}' does not exist", role));
+        }
+    }
+}
+
+/// Checks everything that does not need Keycloak; role existence is checked separately.
+pub fn validate_create_user(req: &CreateUserRequest, policy: &PasswordPolicy) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    validate_username(&mut errors, "username", &req.username);
+    validate_email(&mut errors, "email", &req.email);
+    validate_password(&mut errors, "password", &req.password, policy);
+    validate_name(&mut errors, "first_name", req.first_name.as_deref());
+    validate_name(&mut errors, "last_name", req.last_name.as_deref());
+    errors
+}
+
+pub fn validate_update_user(req: &UpdateUserRequest) -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    if let Some(email) = &req.email {
+        validate_email(&mut errors, "email", email);
+    }
+    validate_name(&mut errors, "first_name", req.first_name.as_deref());
+    validate_name(&mut errors, "last_name", req.last_name.as_deref());
+    errors
+}
+
+/// Names the field behind a Keycloak 409, e.g. "User exists with same email".
+pub fn conflict_field(message: &str) -> &'static str {
+    if message.to_ascii_lowercase().contains("email") {
+        "email"
+    } else {
+        "username"
+    }
+}
+
+/// A DNS-1123 label, which is what Kubernetes requires for namespace names.
+pub fn is_dns_label(value: &str) -> bool {
+    value.len() <= 63 && is_dns_subdomain(value) && !value.contains('.')
+}
+
+/// A DNS-1123 subdomain, the format of most object names such as pods:
+/// dot-separated parts of lowercase letters, digits and '-' that start and
+/// end with a letter or digit, at most 253 characters in total.
+pub fn is_dns_subdomain(value: &str) -> bool {
+    !value.is_empty()
+        && value.len() <= 253
+        && value.split('.').all(|part| {
+            !part.is_empty()
+                && part.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+                && !part.starts_with('-')
+                && !part.ends_with('-')
+        })
+}
+
+/// Path parameters that are interpolated into a Kubernetes API path: the
+/// namespace must be a DNS-1123 label and the pod a DNS-1123 subdomain, so
+/// neither can add or climb path segments.
+pub fn validate_pod_ref(errors: &mut ValidationErrors, namespace: &str, pod: Option<&str>) {
+    if !is_dns_label(namespace) {
+        errors.add("namespace", "must be a DNS-1123 label");
+    }
+    if pod.is_some_and(|pod| !is_dns_subdomain(pod)) {
+        errors.add("pod", "must be a DNS-1123 subdomain");
+    }
+}
+
+/// Cluster names are DNS-1123 labels so they can be used in URLs and kubeconfigs.
+pub fn validate_cluster_name(errors: &mut ValidationErrors, field: &str, name: &str) {
+    if name.is_empty() || name.len() > CLUSTER_NAME_MAX_LENGTH {
+        errors.add(
+            field,
+            format!("must be between 1 and {} characters", CLUSTER_NAME_MAX_LENGTH),
+        );
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+        || name.starts_with('-')
+        || name.ends_with('-')
+    {
+        errors.add(
+            field,
+            "may only contain lowercase letters, digits and '-', and must start and end with a letter or digit",
+        );
+    }
+}
+
+/// Parses an API server URL and returns it without a trailing slash.
+pub fn normalize_endpoint(endpoint: &str) -> Result<String, String> {
+    let url = reqwest::Url::parse(endpoint.trim()).map_err(|e| format!("is not a valid URL: {}", e))?;
+    if url.scheme() != "https" && url.scheme() != "http" {
+        return Err("must use the https or http scheme".to_string());
+    }
+    if url.host_str().is_none_or(str::is_empty) {
+        return Err("must include a host".to_string());
+    }
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err("must not contain a query or fragment".to_string());
+    }
+    if !url.username().is_empty() || url.password().is_some() {
+        return Err("must not embed credentials".to_string());
+    }
+    Ok(url.as_str().trim_end_matches('/').to_string())
+}
+
+fn validate_description(errors: &mut ValidationErrors, description: Option<&str>) {
+    if description.is_some_and(|d| d.chars().count() > DESCRIPTION_MAX_LENGTH) {
+        errors.add(
+            "description",
+            format!("must be at most {} characters", DESCRIPTION_MAX_LENGTH),
+        );
+    }
+}
+
+fn is_pem(value: &str) -> bool {
+    value.contains("-----BEGIN ") && value.contains("-----END ")
+}
+
+fn require_non_empty(errors: &mut ValidationErrors, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        errors.add(field, "is required");
+    }
+}
+
+/// Checks credential shape only; whether it is accepted is up to the API server.
+pub fn validate_cluster_credential(errors: &mut ValidationErrors, field: &str, credential: &ClusterCredential) {
+    if let Some(ca) = &credential.ca_bundle_pem {
+        if !is_pem(ca) {
+            errors.add(&format!("{}.ca_bundle_pem", field), "must be a PEM certificate bundle");
+        }
+    }
+    if credential.tls_server_name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+        errors.add(&format!("{}.tls_server_name", field), "must not be empty");
+    }
+    let auth = format!("{}.auth", field);
+    match &credential.auth {
+        ClusterAuth::None => {}
+        ClusterAuth::Token { token } => require_non_empty(errors, &format!("{}.token", auth), token),
+        ClusterAuth::ClientCertificate { certificate_pem, key_pem } => {
+            if !is_pem(certificate_pem) {
+                errors.add(&format!("{}.certificate_pem", auth), "must be a PEM certificate");
+            }
+            if !is_pem(key_pem) {
+                errors.add(&format!("{}.key_pem", auth), "must be a PEM private key");
+            }
+        }
+        ClusterAuth::Oidc { issuer_url, client_id, refresh_token, id_token, .. } => {
+            if let Err(message) = normalize_endpoint(issuer_url) {
+                errors.add(&format!("{}.issuer_url", auth), message);
+            }
+            require_non_empty(errors, &format!("{}.client_id", auth), client_id);
+            if refresh_token.is_none() && id_token.is_none() {
+                errors.add(&auth, "oidc needs a refresh_token or an id_token");
+            }
+        }
+    }
+}
+
+/// Checks the user and group prefixes. They end up in `Impersonate-*`
+/// headers; `system:` would let Keycloak names map onto Kubernetes system
+/// users and groups.
+pub fn validate_impersonation(errors: &mut ValidationErrors, field: &str, settings: &ImpersonationSettings) {
+    for (name, prefix) in [("user_prefix", &settings.user_prefix), ("group_prefix", &settings.group_prefix)] {
+        let field = format!("{}.{}", field, name);
+        if prefix.len() > 64 {
+            errors.add(&field, "must be at most 64 characters");
+        }
+        if !prefix.chars().all(|c| c.is_ascii_graphic()) {
+            errors.add(&field, "must contain only printable ASCII characters without spaces");
+        }
+        if prefix.starts_with("system:") {
+            errors.add(&field, "must not start with 'system:'");
+        }
+    }
+}
+
+/// Validates the request and returns the normalized endpoint.
+pub fn validate_create_cluster(req: &CreateClusterRequest) -> Result<String, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    validate_cluster_name(&mut errors, "name", &req.name);
+    validate_description(&mut errors, req.description.as_deref());
+    if let Some(credential) = &req.credential {
+        validate_cluster_credential(&mut errors, "credential", credential);
+    }
+    if let Some(impersonation) = &req.impersonation {
+        validate_impersonation(&mut errors, "impersonation", impersonation);
+    }
+    let endpoint = normalize_endpoint(&req.endpoint)
+        .map_err(|message| errors.add("endpoint", message))
+        .ok();
+    errors.into_result().map(|_| endpoint.unwrap_or_default())
+}
+
+/// Validates the request and returns the normalized endpoint, if one was given.
+pub fn validate_update_cluster(req: &UpdateClusterRequest) -> Result<Option<String>, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    if let Some(name) = &req.name {
+        validate_cluster_name(&mut errors, "name", name);
+    }
+    validate_description(&mut errors, req.description.as_deref());
+    if let Some(impersonation) = &req.impersonation {
+        validate_impersonation(&mut errors, "impersonation", impersonation);
+    }
+    let endpoint = match &req.endpoint {
+        Some(endpoint) => normalize_endpoint(endpoint)
+            .map_err(|message| errors.add("endpoint", message))
+            .ok(),
+        None => None,
+    };
+    errors.into_result().map(|_| endpoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn username_errors(username: &str) -> Vec<String> {
+        let mut errors = ValidationErrors::new();
+        validate_username(&mut errors, "username", username);
+        errors.messages()
+    }
+
+    fn password_errors(password: &str, policy: &PasswordPolicy) -> Vec<String> {
+        let mut errors = ValidationErrors::new();
+        validate_password(&mut errors, "password", password, policy);
+        errors.messages()
+    }
+
+    #[test]
+    fn usernames() {
+        for valid in ["bob", "alice.smith", "j_doe-2", "007", &"a".repeat(64)] {
+            assert!(username_errors(valid).is_empty(), "{}", valid);
+        }
+        assert_eq!(username_errors("ab"), ["username: must be between 3 and 64 characters"]);
+        assert_eq!(username_errors(&"a".repeat(65)), ["username: must be between 3 and 64 characters"]);
+        assert_eq!(username_errors("bob smith"), ["username: may only contain letters, digits, '.', '_' and '-'"]);
+        assert_eq!(username_errors("jürgen"), ["username: may only contain letters, digits, '.', '_' and '-'"]);
+        assert_eq!(username_errors(".bob"), ["username: must start with a letter or digit"]);
+        assert_eq!(
+            username_errors(""),
+            [
+                "username: must be between 3 and 64 characters",
+                "username: must start with a letter or digit"
+            ]
+        );
+    }
+
+    #[test]
+    fn emails() {
+        for valid in ["a@b.io", "first.last+tag@mail.example.com", "x@sub-domain.example.org"] {
+            assert!(is_valid_email(valid), "{}", valid);
+        }
+        let long_local = format!("{}@example.com", "a".repeat(65));
+        let long_total = format!("a@{}.com", "b".repeat(250));
+        for invalid in [
+            "",
+            "plain",
+            "@example.com",
+            "user@localhost",
+            "user@@example.com",
+            "user name@example.com",
+            "user@-example.com",
+            "user@example-.com",
+            "user@exa_mple.com",
+            "user@example..com",
+            &long_local,
+            &long_total,
+        ] {
+            assert!(!is_valid_email(invalid), "{}", invalid);
+        }
+
+        let mut errors = ValidationErrors::new();
+        validate_email(&mut errors, "email", "  ");
+        validate_email(&mut errors, "email", "nope");
+        validate_email(&mut errors, "email", "ok@example.com");
+        assert_eq!(errors.messages(), ["email: is required", "email: is not a valid email address"]);
+    }
+
+    #[test]
+    fn passwords() {
+        let strict = PasswordPolicy {
+            min_length: 8,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: true,
+        };
+        for valid in ["Secret123!", "Пароль-2024x"] {
+            assert!(password_errors(valid, &strict).is_empty(), "{}", valid);
+        }
+        assert_eq!(password_errors("Sec1!", &strict), ["password: must be at least 8 characters"]);
+        assert_eq!(
+            password_errors("secretpassword", &strict),
+            [
+                "password: must contain an uppercase letter",
+                "password: must contain a digit",
+                "password: must contain a special character"
+            ]
+        );
+        assert_eq!(password_errors("SECRET123!", &strict), ["password: must contain a lowercase letter"]);
+
+        let lax = PasswordPolicy {
+            min_length: 4,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_special: false,
+        };
+        assert!(password_errors("abcd", &lax).is_empty());
+        // Length counts characters, not bytes
+        assert_eq!(password_errors("äöü", &lax), ["password: must be at least 4 characters"]);
+    }
+
+    #[test]
+    fn conflict_fields() {
+        assert_eq!(conflict_field("User exists with same email"), "email");
+        assert_eq!(conflict_field("User exists with same EMAIL"), "email");
+        assert_eq!(conflict_field("User exists with same username"), "username");
+        assert_eq!(conflict_field(""), "username");
+    }
+
+    #[test]
+    fn dns_labels() {
+        for valid in ["shop", "kube-system", "a", "0abc", &"a".repeat(63)] {
+            assert!(is_dns_label(valid), "{}", valid);
+        }
+        for invalid in ["", "Shop", "-shop", "shop-", "sh_op", "shop.prod", "../x", &"a".repeat(64)] {
+            assert!(!is_dns_label(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn dns_subdomains() {
+        for valid in ["web-0", "web.shop.svc", "a", &"a".repeat(253)] {
+            assert!(is_dns_subdomain(valid), "{}", valid);
+        }
+        for invalid in ["", "web..0", ".web", "web.", "web-.x", "Web", "web/0", "web%2F0", &"a".repeat(254)] {
+            assert!(!is_dns_subdomain(invalid), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn pod_refs_cannot_escape_the_pod_path() {
+        let mut errors = ValidationErrors::new();
+        validate_pod_ref(&mut errors, "shop", Some("web-0"));
+        assert!(errors.is_empty());
+
+        let mut errors = ValidationErrors::new();
+        validate_pod_ref(&mut errors, "shop", Some("../../secrets?"));
+        validate_pod_ref(&mut errors, "../other-ns", None);
+        assert_eq!(
+            errors.messages(),
+            [
+                "namespace: must be a DNS-1123 label",
+                "pod: must be a DNS-1123 subdomain"
+            ]
+        );
+    }
+}
@@ -0,0 +1,470 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Words that mark a pre-release when they follow a version, e.g. `1.0rc1`
+/// or `2.0.0-beta.2`; other trailing words (`+dfsg`, `.Final`) sort after.
+const PRE_RELEASE_WORDS: [&str; 10] = [
+    "alpha", "beta", "rc", "pre", "preview", "dev", "snapshot", "milestone", "m", "ea",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u64),
+    Word(String),
+    /// `~`, which sorts before everything (Debian pre-releases)
+    Tilde,
+}
+
+fn tokens(version: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = version.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                number.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Number(number.parse().unwrap_or(u64::MAX)));
+        } else if c.is_alphabetic() {
+            let mut word = String::new();
+            while let Some(&l) = chars.peek().filter(|l| l.is_alphabetic()) {
+                word.push(l.to_ascii_lowercase());
+                chars.next();
+            }
+            tokens.push(Token::Word(word));
+        } else {
+            if c == '~' {
+                tokens.push(Token::Tilde);
+            }
+            chars.next();
+        }
+    }
+    tokens
+}
+
+/// Whether a version extended by `rest` is older than the version without it.
+fn is_pre_release(rest: &[Token]) -> bool {
+    match rest {
+        [Token::Tilde, ..] => true,
+        // A lone letter is a pre-release only when numbered (`1.0a1`), not
+        // as a patch letter (`1.1.1w`)
+        [Token::Word(w), next, ..] if w.len() == 1 => matches!(next, Token::Number(_)) && "abc".contains(w.as_str()),
+        [Token::Word(w), ..] => PRE_RELEASE_WORDS.contains(&w.as_str()),
+        _ => false,
+    }
+}
+
+fn split_epoch(version: &str) -> (u64, &str) {
+    match version.split_once(':') {
+        Some((epoch, rest)) if !epoch.is_empty() && epoch.chars().all(|c| c.is_ascii_digit()) => {
+            (epoch.parse().unwrap_or(0), rest)
+        }
+        _ => (0, version),
+    }
+}
+
+/// Compares versions across ecosystems: an optional `epoch:`, then numbers
+/// numerically and words alphabetically, with pre-release suffixes sorting
+/// before the release. An approximation of each ecosystem's own rules that
+/// agrees with them on the common cases.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let (epoch_a, a) = split_epoch(a.trim().trim_start_matches('v'));
+    let (epoch_b, b) = split_epoch(b.trim().trim_start_matches('v'));
+    if epoch_a != epoch_b {
+        return epoch_a.cmp(&epoch_b);
+    }
+    let (a, b) = (tokens(a), tokens(b));
+    for (x, y) in a.iter().zip(&b) {
+        let order = match (x, y) {
+            (Token::Number(x), Token::Number(y)) => x.cmp(y),
+            (Token::Word(x), Token::Word(y)) => x.cmp(y),
+            (Token::Tilde, Token::Tilde) => Ordering::Equal,
+            (Token::Tilde, _) => Ordering::Less,
+            (_, Token::Tilde) => Ordering::Greater,
+            (Token::Number(_), Token::Word(_)) => Ordering::Greater,
+            (Token::Word(_), Token::Number(_)) => Ordering::Less,
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    let longer_order = |rest: &[Token]| {
+        if rest.iter().all(|t| *t == Token::Number(0)) {
+            Ordering::Equal
+        } else if is_pre_release(rest) {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    };
+    match a.len().cmp(&b.len()) {
+        Ordering::Greater => longer_order(&a[b.len()..]),
+        Ordering::Less => longer_order(&b[a.len()..]).reverse(),
+        Ordering::Equal => Ordering::Equal,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bound {
+    pub version: String,
+    pub inclusive: bool,
+}
+
+/// A version interval; an absent bound is open.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionRange {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lower: Option<Bound>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub upper: Option<Bound>,
+}
+
+impl VersionRange {
+    pub fn contains(&self, version: &str) -> bool {
+        let above = self.lower.as_ref().is_none_or(|b| match compare_versions(version, &b.version) {
+            Ordering::Greater => true,
+            Ordering::Equal => b.inclusive,
+            Ordering::Less => false,
+        });
+        let below = self.upper.as_ref().is_none_or(|b| match compare_versions(version, &b.version) {
+            Ordering::Less => true,
+            Ordering::Equal => b.inclusive,
+            Ordering::Greater => false,
+        });
+        above && below
+    }
+
+    /// Parses constraints such as `>=1.0, <1.2.3` or `= 2.0`. A constraint
+    /// without any bound is `None`, not a range matching every version.
+    pub fn parse_constraint(constraint: &str) -> Option<Self> {
+        let mut range = Self::default();
+        for part in constraint.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (op, version) = ["<=", ">=", "==", "<", ">", "=", "^", "~"]
+                .iter()
+                .find_map(|op| part.strip_prefix(op).map(|v| (*op, v.trim())))
+                .unwrap_or(("=", part));
+            if version.is_empty() {
+                return None;
+            }
+            let bound = |inclusive| Some(Bound { version: version.to_string(), inclusive });
+            match op {
+                ">=" => range.lower = bound(true),
+                ">" => range.lower = bound(false),
+                "<=" => range.upper = bound(true),
+                "<" => range.upper = bound(false),
+                "=" | "==" => {
+                    range.lower = bound(true);
+                    range.upper = bound(true);
+                }
+                // Caret and tilde ranges are not expanded; matched as exact
+                _ => {
+                    range.lower = bound(true);
+                    range.upper = bound(true);
+                }
+            }
+        }
+        (range.lower.is_some() || range.upper.is_some()).then_some(range)
+    }
+}
+
+/// Which versions of a package an advisory covers. A version is affected when
+/// it is listed, or falls in a range and in no unaffected range.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Affected {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ranges: Vec<VersionRange>,
+    /// Patched versions, for feeds that only say what is fixed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unaffected: Vec<VersionRange>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fixed: Vec<String>,
+}
+
+impl Affected {
+    pub fn merge(&mut self, other: Affected) {
+        self.versions.extend(other.versions);
+        self.ranges.extend(other.ranges);
+        self.unaffected.extend(other.unaffected);
+        self.fixed.extend(other.fixed);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.versions.is_empty() && self.ranges.is_empty() && self.unaffected.is_empty()
+    }
+
+    pub fn matches(&self, version: &str) -> bool {
+        if self.unaffected.iter().any(|r| r.contains(version)) {
+            return false;
+        }
+        if self.versions.iter().any(|v| compare_versions(v, version) == Ordering::Equal) {
+            return true;
+        }
+        if self.ranges.is_empty() && self.versions.is_empty() {
+            return !self.unaffected.is_empty();
+        }
+        self.ranges.iter().any(|r| r.contains(version))
+    }
+
+    /// The lowest fixed version above `version`, if any.
+    pub fn fixed_version(&self, version: &str) -> Option<String> {
+        self.fixed
+            .iter()
+            .filter(|f| compare_versions(f, version) == Ordering::Greater)
+            .min_by(|a, b| compare_versions(a, b))
+            .cloned()
+    }
+}
+
+/// Splits an ecosystem name into its lower-case base and release:
+/// `Debian:12` and `debian 12` → (`debian`, `12`), `Alpine:v3.18` →
+/// (`alpine`, `3.18`), `npm::GitHub Security Advisory npm` → (`npm`, none).
+pub fn normalize_ecosystem(value: &str) -> (String, Option<String>) {
+    let value = value.trim();
+    let is_release = |part: &&str| part.trim().trim_start_matches('v').starts_with(|c: char| c.is_ascii_digit());
+    let (base, release) = if let Some((base, _)) = value.split_once("::") {
+        (base, None)
+    } else if let Some((base, rest)) = value.split_once(':') {
+        // OSV also has forms like `Ubuntu:Pro:22.04:LTS`
+        (base, rest.split(':').find(is_release))
+    } else {
+        match value.rsplit_once(' ') {
+            Some((base, release)) if is_release(&release) => (base, Some(release)),
+            _ => (value, None),
+        }
+    };
+    let release = release.map(|r| r.trim().trim_start_matches('v').to_string());
+    let base = base.trim().to_ascii_lowercase();
+    let base = match base.as_str() {
+        "pip" => "pypi".to_string(),
+        "cargo" => "crates.io".to_string(),
+        "composer" => "packagist".to_string(),
+        "golang" => "go".to_string(),
+        "gem" => "rubygems".to_string(),
+        _ => base,
+    };
+    (base, release)
+}
+
+/// Whether an advisory for `advisory_release` applies to a package from
+/// `package_release`; unknown releases match any.
+pub fn release_matches(advisory_release: Option<&str>, package_release: Option<&str>) -> bool {
+    match (advisory_release, package_release) {
+        (Some(advisory), Some(package)) => {
+            package == advisory || package.starts_with(&format!("{}.", advisory))
+        }
+        _ => true,
+    }
+}
+
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        ((scaled / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+/// CVSS v3.x base score from a vector such as
+/// `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+pub fn cvss3_base_score(vector: &str) -> Option<f64> {
+    if !vector.starts_with("CVSS:3") {
+        return None;
+    }
+    let metric = |name: &str| {
+        vector
+            .split('/')
+            .find_map(|part| part.strip_prefix(name).and_then(|v| v.strip_prefix(':')))
+    };
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let av: f64 = match metric("AV")? { "N" => 0.85, "A" => 0.62, "L" => 0.55, "P" => 0.2, _ => return None };
+    let ac = match metric("AC")? { "L" => 0.77, "H" => 0.44, _ => return None };
+    let pr = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match metric("UI")? { "N" => 0.85, "R" => 0.62, _ => return None };
+    let cia = |name: &str| match metric(name) {
+        Some("H") => Some(0.56),
+        Some("L") => Some(0.22),
+        Some("N") => Some(0.0),
+        _ => None,
+    };
+    let iss: f64 = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    Some(if changed {
+        round_up((1.08 * (impact + exploitability)).min(10.0))
+    } else {
+        round_up((impact + exploitability).min(10.0))
+    })
+}
+
+/// Package names as advisories spell them: PyPI names are case-insensitive
+/// with `_` and `.` equal to `-`.
+pub fn normalize_package(ecosystem: &str, name: &str) -> String {
+    match ecosystem {
+        "pypi" => name.to_ascii_lowercase().replace(['_', '.'], "-"),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_order(a: &str, b: &str, order: Ordering) {
+        assert_eq!(compare_versions(a, b), order, "{} vs {}", a, b);
+        assert_eq!(compare_versions(b, a), order.reverse(), "{} vs {}", b, a);
+    }
+
+    #[test]
+    fn compare_debian_versions() {
+        assert_order("1.0~rc1", "1.0", Ordering::Less);
+        assert_order("1.0~rc1", "1.0~rc2", Ordering::Less);
+        assert_order("2.36-9", "2.36-9+deb12u1", Ordering::Less);
+        assert_order("2.36-9+deb12u1", "2.36-9+deb12u3", Ordering::Less);
+        assert_order("3.0.11-1~deb12u2", "3.0.11-1", Ordering::Less);
+    }
+
+    #[test]
+    fn compare_alpine_versions() {
+        assert_order("1.2.3-r1", "1.2.3-r2", Ordering::Less);
+        assert_order("1.2.3-r9", "1.2.3-r10", Ordering::Less);
+        assert_order("1.2.3-r10", "1.2.4-r0", Ordering::Less);
+    }
+
+    #[test]
+    fn compare_patch_letters() {
+        assert_order("1.1.1", "1.1.1w", Ordering::Less);
+        assert_order("1.1.1v", "1.1.1w", Ordering::Less);
+        assert_order("1.1.1w", "3.0.0", Ordering::Less);
+    }
+
+    #[test]
+    fn compare_pre_releases() {
+        assert_order("2.0.0-beta.2", "2.0.0", Ordering::Less);
+        assert_order("2.0.0-alpha", "2.0.0-beta", Ordering::Less);
+        assert_order("2.0.0-alpha", "2.0.0-alpha.1", Ordering::Less);
+        assert_order("1.0.0-beta.11", "1.0.0-rc.1", Ordering::Less);
+        assert_order("1.0rc1", "1.0", Ordering::Less);
+        assert_order("1.0a1", "1.0", Ordering::Less);
+        assert_order("1.0", "1.0.post1", Ordering::Less);
+    }
+
+    #[test]
+    fn compare_numbers_epochs_and_prefixes() {
+        assert_order("1.9", "1.10", Ordering::Less);
+        assert_order("1.0", "1.0.0", Ordering::Equal);
+        assert_order("v1.2.3", "1.2.3", Ordering::Equal);
+        assert_order("2.0", "1:1.0", Ordering::Less);
+        assert_order("0:1.0", "1.0", Ordering::Equal);
+        assert_order("1:1.0", "1:1.1", Ordering::Less);
+    }
+
+    fn bound(version: &str, inclusive: bool) -> Option<Bound> {
+        Some(Bound { version: version.to_string(), inclusive })
+    }
+
+    #[test]
+    fn parse_constraints() {
+        assert_eq!(
+            VersionRange::parse_constraint(">=1.0, <1.2.3"),
+            Some(VersionRange { lower: bound("1.0", true), upper: bound("1.2.3", false) })
+        );
+        assert_eq!(
+            VersionRange::parse_constraint("> 1.0,<= 2"),
+            Some(VersionRange { lower: bound("1.0", false), upper: bound("2", true) })
+        );
+        for exact in ["= 2.0", "==2.0", "2.0"] {
+            assert_eq!(
+                VersionRange::parse_constraint(exact),
+                Some(VersionRange { lower: bound("2.0", true), upper: bound("2.0", true) }),
+                "{}",
+                exact
+            );
+        }
+        assert_eq!(VersionRange::parse_constraint(">="), None);
+        assert_eq!(VersionRange::parse_constraint(""), None);
+        assert_eq!(VersionRange::parse_constraint(" , "), None);
+    }
+
+    #[test]
+    fn ranges_contain_versions() {
+        let range = VersionRange::parse_constraint(">=1.0, <1.2.3").unwrap();
+        assert!(range.contains("1.0"));
+        assert!(range.contains("1.2.3-rc1"));
+        assert!(!range.contains("1.2.3"));
+        assert!(!range.contains("0.9"));
+    }
+
+    #[test]
+    fn cvss3_reference_scores() {
+        for (vector, score) in [
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", 9.8),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H", 7.5),
+            ("CVSS:3.1/AV:N/AC:H/PR:N/UI:N/S:U/C:H/I:N/A:N", 5.9),
+            ("CVSS:3.1/AV:L/AC:L/PR:L/UI:N/S:U/C:H/I:H/A:H", 7.8),
+            ("CVSS:3.0/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N", 0.0),
+            // Scope changed
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H", 10.0),
+            ("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:C/C:H/I:H/A:H", 9.9),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N", 6.1),
+            ("CVSS:3.1/AV:L/AC:L/PR:H/UI:N/S:C/C:H/I:H/A:H", 8.2),
+        ] {
+            assert_eq!(cvss3_base_score(vector), Some(score), "{}", vector);
+        }
+    }
+
+    #[test]
+    fn cvss3_rejects_other_vectors() {
+        assert_eq!(cvss3_base_score("AV:N/AC:L/Au:N/C:P/I:P/A:P"), None);
+        assert_eq!(cvss3_base_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H"), None);
+        assert_eq!(cvss3_base_score("CVSS:3.1/AV:X/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), None);
+    }
+
+    #[test]
+    fn affected_versions() {
+        let affected = Affected {
+            ranges: vec![VersionRange::parse_constraint(">=1.0, <2.0").unwrap()],
+            unaffected: vec![VersionRange::parse_constraint("=1.5").unwrap()],
+            fixed: vec!["2.0".to_string(), "1.4.2".to_string()],
+            ..Affected::default()
+        };
+        assert!(affected.matches("1.4"));
+        assert!(!affected.matches("1.5"));
+        assert!(!affected.matches("2.0"));
+        assert_eq!(affected.fixed_version("1.4").as_deref(), Some("1.4.2"));
+        assert_eq!(affected.fixed_version("1.8").as_deref(), Some("2.0"));
+    }
+
+    #[test]
+    fn ecosystems() {
+        assert_eq!(normalize_ecosystem("Debian:12"), ("debian".to_string(), Some("12".to_string())));
+        assert_eq!(normalize_ecosystem("Alpine:v3.18"), ("alpine".to_string(), Some("3.18".to_string())));
+        assert_eq!(normalize_ecosystem("Ubuntu:Pro:22.04:LTS"), ("ubuntu".to_string(), Some("22.04".to_string())));
+        assert_eq!(normalize_ecosystem("npm::GitHub Security Advisory npm"), ("npm".to_string(), None));
+        assert_eq!(normalize_ecosystem("PIP"), ("pypi".to_string(), None));
+        assert!(release_matches(Some("3.18"), Some("3.18.4")));
+        assert!(!release_matches(Some("3.1"), Some("3.18.4")));
+        assert!(release_matches(None, Some("12")));
+    }
+}